@@ -1,3 +1,6 @@
+mod ros_args;
+pub use ros_args::*;
+
 use std::ffi::CString;
 use std::os::raw::c_char;
 use std::os::raw::c_void;
@@ -79,6 +82,33 @@ pub fn extract_non_ros_args(
     ret
 }
 
+/// Returns an error for every ROS argument that rcl did not recognize.
+///
+/// rcl only warns about such arguments, e.g. a misspelled flag, and then ignores them. This
+/// function must be called after `rcl_arguments` was initialized from `args`.
+pub(crate) fn check_unknown_ros_args(
+    rcl_arguments: *const rcl_arguments_t,
+    args: &[String],
+) -> Result<(), RclrsError> {
+    let positions = get_rcl_argument_indices(
+        rcl_arguments_get_count_unparsed_ros,
+        rcl_arguments_get_unparsed_ros,
+        rcl_arguments,
+    )?;
+    if positions.is_empty() {
+        return Ok(());
+    }
+    let errors = positions
+        .into_iter()
+        .map(|position| RosArgsError {
+            position,
+            argument: args[position].clone(),
+            kind: RosArgsErrorKind::Unknown,
+        })
+        .collect();
+    Err(RclrsError::InvalidRosArgs { errors })
+}
+
 /// Returns arguments type held by `rcl_arguments` basing on `rcl_get_count` and `rcl_get_indices` function pointers.
 ///
 /// This function must be called after `rcl_arguments` was initialized. `args` must be array of input arguments passed to node/program.
@@ -97,6 +127,26 @@ pub(crate) fn get_rcl_arguments(
     rcl_arguments: *const rcl_arguments_t,
     args: &[String],
 ) -> Result<Vec<String>, RclrsError> {
+    let indices = get_rcl_argument_indices(rcl_get_count, rcl_get_indices, rcl_arguments)?;
+    // SAFETY: rcl_get_indices and rcl_get_count are matching functions according
+    // to documentation of this function
+    Ok(indices
+        .into_iter()
+        .map(|index| args.get(index).unwrap().clone())
+        .collect())
+}
+
+// Helper for get_rcl_arguments() and check_unknown_ros_args(), with the same requirements as
+// get_rcl_arguments()
+fn get_rcl_argument_indices(
+    rcl_get_count: unsafe extern "C" fn(*const rcl_arguments_t) -> std::os::raw::c_int,
+    rcl_get_indices: unsafe extern "C" fn(
+        *const rcl_arguments_t,
+        rcl_allocator_t,
+        *mut *mut std::os::raw::c_int,
+    ) -> rcl_ret_t,
+    rcl_arguments: *const rcl_arguments_t,
+) -> Result<Vec<usize>, RclrsError> {
     // SAFETY: No preconditions for this function.
     let args_count = unsafe { rcl_get_count(rcl_arguments) };
     debug_assert!(args_count != -1);
@@ -105,7 +155,7 @@ pub(crate) fn get_rcl_arguments(
     if args_count == 0 {
         return Ok(Vec::new());
     }
-    let mut extracted_args: Vec<String> = Vec::with_capacity(args_count);
+    let mut indices: Vec<usize> = Vec::with_capacity(args_count);
     let mut indices_ptr: *mut i32 = null_mut();
     unsafe {
         // SAFETY: No preconditions for this function.
@@ -118,17 +168,14 @@ pub(crate) fn get_rcl_arguments(
             // SAFETY: rcl_get_indices finished with success and rcl_get_count is matching function
             // according to documentation of this function
             let index = *(indices_ptr.add(i));
-            // SAFETY: rcl_get_indices and rcl_get_count are matching functions according
-            // to documentation of this function
-            let arg = args.get(index as usize).unwrap();
-            extracted_args.push(arg.clone());
+            indices.push(index as usize);
         }
         // SAFETY: No preconditions for this function.
         let allocator = rcutils_get_default_allocator();
         // SAFETY: indices_ptr was allocated with given allocator
         allocator.deallocate.unwrap()(indices_ptr as *mut c_void, null_mut());
     }
-    Ok(extracted_args)
+    Ok(indices)
 }
 
 #[cfg(test)]
@@ -172,6 +219,42 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_unknown_ros_arguments() -> Result<(), RclrsError> {
+        let args = [
+            "exe",
+            "--ros-args",
+            "-r",
+            "a:=b",
+            "--unknown",
+            "--",
+            "--not-ros",
+        ]
+        .map(String::from);
+        let expected_errors = vec![RosArgsError {
+            position: 4,
+            argument: String::from("--unknown"),
+            kind: RosArgsErrorKind::Unknown,
+        }];
+        assert_eq!(
+            crate::Context::new(args.clone()).err(),
+            Some(RclrsError::InvalidRosArgs {
+                errors: expected_errors.clone()
+            })
+        );
+        let context = crate::Context::new([])?;
+        let result = crate::NodeBuilder::new(&context, "unknown_ros_arguments")
+            .arguments(args)
+            .build();
+        assert_eq!(
+            result.err(),
+            Some(RclrsError::InvalidRosArgs {
+                errors: expected_errors
+            })
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_empty_non_ros_arguments() -> Result<(), RclrsError> {
         let empty_non_ros_args = extract_non_ros_args(vec![])?;
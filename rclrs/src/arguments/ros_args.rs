@@ -0,0 +1,494 @@
+use std::fmt::{self, Display};
+
+use crate::RclrsError;
+
+const ROS_ARGS_FLAG: &str = "--ros-args";
+const ROS_ARGS_END: &str = "--";
+const LOG_LEVELS: [&str; 6] = ["unset", "debug", "info", "warn", "error", "fatal"];
+
+/// The ROS arguments in a list of command line arguments, i.e. the arguments between
+/// `--ros-args` and `--`.
+///
+/// See the [design article][1] for the meaning of each argument. Arguments are kept in the order
+/// in which they appear, since e.g. the first matching remap rule is applied.
+///
+/// # Example
+/// ```
+/// # use rclrs::{RclrsError, RemapRule, RosArgs};
+/// let args = [
+///     "my_executable", "--ros-args", "-r", "talker:chatter:=/robot1/chatter",
+///     "-p", "rate:=10.0", "--log-level", "warn", "--", "--not-a-ros-arg",
+/// ].map(String::from);
+/// let ros_args = RosArgs::parse(args)?;
+/// assert_eq!(
+///     ros_args.remap_rules,
+///     [RemapRule::new("chatter", "/robot1/chatter").for_node("talker")]
+/// );
+/// assert_eq!(ros_args.parameters[0].name, "rate");
+/// assert_eq!(ros_args.parameters[0].value, "10.0");
+/// assert_eq!(ros_args.log_levels[0].level, "warn");
+///
+/// let unknown = ["--ros-args", "--remap", "a:=b", "--unknown"].map(String::from);
+/// let err = RosArgs::parse(unknown).unwrap_err();
+/// assert_eq!(err.to_string(), "Unknown ROS argument '--unknown' at position 3");
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: https://design.ros2.org/articles/ros_command_line_arguments.html
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RosArgs {
+    /// The remap rules, given with `-r` or `--remap`.
+    pub remap_rules: Vec<RemapRule>,
+    /// The parameter overrides, given with `-p` or `--param`.
+    pub parameters: Vec<ParameterArgument>,
+    /// The paths of parameter files, given with `--params-file`.
+    pub parameter_files: Vec<String>,
+    /// The log levels, given with `--log-level`.
+    pub log_levels: Vec<LogLevelArgument>,
+    /// The path of the external logging configuration file, given with `--log-config-file`.
+    pub log_config_file: Option<String>,
+    /// The security enclave, given with `-e` or `--enclave`.
+    pub enclave: Option<String>,
+    /// Whether logging to rosout is enabled, given with `--enable-rosout-logs` or
+    /// `--disable-rosout-logs`.
+    pub rosout_logs: Option<bool>,
+    /// Whether logging to standard output is enabled, given with `--enable-stdout-logs` or
+    /// `--disable-stdout-logs`.
+    pub stdout_logs: Option<bool>,
+    /// Whether logging with an external library is enabled, given with
+    /// `--enable-external-lib-logs` or `--disable-external-lib-logs`.
+    pub external_lib_logs: Option<bool>,
+}
+
+/// A rule that replaces a topic, service or node name, given as `[node:]from:=to`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemapRule {
+    /// The node that the rule applies to, or `None` if it applies to all nodes.
+    pub node: Option<String>,
+    /// The name to match, e.g. `chatter`, or `__node` and `__ns` to remap the node name and
+    /// namespace.
+    pub from: String,
+    /// The replacement.
+    pub to: String,
+}
+
+/// A parameter override, given as `[node:]name:=value`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParameterArgument {
+    /// The node that the override applies to, or `None` if it applies to all nodes.
+    pub node: Option<String>,
+    /// The name of the parameter.
+    pub name: String,
+    /// The value of the parameter, in YAML syntax.
+    pub value: String,
+}
+
+/// A log level, given as `[logger:=]level`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogLevelArgument {
+    /// The logger that the level applies to, or `None` for the default log level.
+    pub logger: Option<String>,
+    /// The log level, e.g. `debug` or `INFO`.
+    pub level: String,
+}
+
+/// A ROS argument that could not be parsed.
+///
+/// This is returned as part of [`RclrsError::InvalidRosArgs`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RosArgsError {
+    /// The position of the argument in the list of arguments.
+    pub position: usize,
+    /// The argument.
+    pub argument: String,
+    /// The reason why the argument could not be parsed.
+    pub kind: RosArgsErrorKind,
+}
+
+/// The reason why a ROS argument could not be parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RosArgsErrorKind {
+    /// The argument is not a known ROS argument.
+    Unknown,
+    /// The flag is the last argument, but requires a value.
+    MissingValue,
+    /// The value of a remap flag does not have the form `[node:]from:=to`.
+    InvalidRemapRule,
+    /// The value of a parameter flag does not have the form `[node:]name:=value`.
+    InvalidParameter,
+    /// The value of a log level flag does not have the form `[logger:=]level`, with a known level.
+    InvalidLogLevel,
+}
+
+impl RosArgs {
+    /// Parses the ROS arguments in a list of command line arguments.
+    ///
+    /// Arguments outside of `--ros-args` sections are ignored. Every argument in a `--ros-args`
+    /// section that is unknown or invalid is reported in an [`RclrsError::InvalidRosArgs`] error.
+    ///
+    /// Names and values are only checked for their syntax here, e.g. whether a remapped name is a
+    /// valid topic name is checked when the arguments are used by a context or node.
+    ///
+    /// This only knows the arguments that are listed in [`RosArgs`]. Contexts and nodes don't use
+    /// it to check their arguments, but rely on `rcl`, which may know more arguments.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, RclrsError> {
+        let args: Vec<String> = args.into_iter().collect();
+        let mut ros_args = Self::default();
+        let mut errors = Vec::new();
+        let mut in_ros_args = false;
+        let mut position = 0;
+        while position < args.len() {
+            let arg = args[position].as_str();
+            let error = |position: usize, argument: &str, kind| RosArgsError {
+                position,
+                argument: argument.to_owned(),
+                kind,
+            };
+            if arg == ROS_ARGS_FLAG {
+                in_ros_args = true;
+            } else if !in_ros_args {
+                // Not a ROS argument
+            } else if arg == ROS_ARGS_END {
+                in_ros_args = false;
+            } else if takes_value(arg) {
+                match args.get(position + 1) {
+                    Some(value) => {
+                        if let Err(kind) = ros_args.add_flag_with_value(arg, value) {
+                            errors.push(error(position + 1, value, kind));
+                        }
+                    }
+                    None => errors.push(error(position, arg, RosArgsErrorKind::MissingValue)),
+                }
+                position += 1;
+            } else if let Err(kind) = ros_args.add_flag(arg) {
+                errors.push(error(position, arg, kind));
+            }
+            position += 1;
+        }
+        if errors.is_empty() {
+            Ok(ros_args)
+        } else {
+            Err(RclrsError::InvalidRosArgs { errors })
+        }
+    }
+
+    fn add_flag_with_value(&mut self, flag: &str, value: &str) -> Result<(), RosArgsErrorKind> {
+        match flag {
+            "-r" | "--remap" => {
+                let rule = RemapRule::parse(value).ok_or(RosArgsErrorKind::InvalidRemapRule)?;
+                self.remap_rules.push(rule);
+            }
+            "-p" | "--param" => {
+                let parameter =
+                    ParameterArgument::parse(value).ok_or(RosArgsErrorKind::InvalidParameter)?;
+                self.parameters.push(parameter);
+            }
+            "--params-file" => self.parameter_files.push(value.to_owned()),
+            "--log-level" => {
+                let level =
+                    LogLevelArgument::parse(value).ok_or(RosArgsErrorKind::InvalidLogLevel)?;
+                self.log_levels.push(level);
+            }
+            "--log-config-file" => self.log_config_file = Some(value.to_owned()),
+            "-e" | "--enclave" => self.enclave = Some(value.to_owned()),
+            _ => return Err(RosArgsErrorKind::Unknown),
+        }
+        Ok(())
+    }
+
+    // Handles the flags that enable or disable a logging output
+    fn add_flag(&mut self, flag: &str) -> Result<(), RosArgsErrorKind> {
+        let (enable, output) = if let Some(output) = flag.strip_prefix("--enable-") {
+            (true, output)
+        } else if let Some(output) = flag.strip_prefix("--disable-") {
+            (false, output)
+        } else {
+            return Err(RosArgsErrorKind::Unknown);
+        };
+        let setting = match output {
+            "rosout-logs" => &mut self.rosout_logs,
+            "stdout-logs" => &mut self.stdout_logs,
+            "external-lib-logs" => &mut self.external_lib_logs,
+            _ => return Err(RosArgsErrorKind::Unknown),
+        };
+        *setting = Some(enable);
+        Ok(())
+    }
+
+    /// Returns whether there are no ROS arguments.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Converts the ROS arguments back into command line arguments.
+    ///
+    /// The result can be passed to e.g. [`NodeBuilder::arguments()`][1]. It is empty if there
+    /// are no ROS arguments, and a single `--ros-args` section otherwise.
+    ///
+    /// [1]: crate::NodeBuilder::arguments
+    pub fn to_args(&self) -> Vec<String> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let mut args = vec![String::from(ROS_ARGS_FLAG)];
+        let mut push = |flag: &str, value: String| {
+            args.push(flag.to_owned());
+            args.push(value);
+        };
+        for rule in &self.remap_rules {
+            push("-r", rule.to_string());
+        }
+        for parameter in &self.parameters {
+            push("-p", parameter.to_string());
+        }
+        for path in &self.parameter_files {
+            push("--params-file", path.clone());
+        }
+        for level in &self.log_levels {
+            push("--log-level", level.to_string());
+        }
+        if let Some(path) = &self.log_config_file {
+            push("--log-config-file", path.clone());
+        }
+        if let Some(enclave) = &self.enclave {
+            push("--enclave", enclave.clone());
+        }
+        let flags = [
+            ("rosout-logs", self.rosout_logs),
+            ("stdout-logs", self.stdout_logs),
+            ("external-lib-logs", self.external_lib_logs),
+        ];
+        for (suffix, enabled) in flags {
+            match enabled {
+                Some(true) => args.push(format!("--enable-{}", suffix)),
+                Some(false) => args.push(format!("--disable-{}", suffix)),
+                None => {}
+            }
+        }
+        args
+    }
+}
+
+fn takes_value(flag: &str) -> bool {
+    matches!(
+        flag,
+        "-r" | "--remap"
+            | "-p"
+            | "--param"
+            | "--params-file"
+            | "--log-level"
+            | "--log-config-file"
+            | "-e"
+            | "--enclave"
+    )
+}
+
+impl RemapRule {
+    /// Creates a rule that applies to all nodes.
+    pub fn new(from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            node: None,
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    /// Restricts the rule to the node with the given name.
+    pub fn for_node(mut self, node: impl Into<String>) -> Self {
+        self.node = Some(node.into());
+        self
+    }
+
+    fn parse(rule: &str) -> Option<Self> {
+        let (target, to) = rule.split_once(":=")?;
+        // Names with a URL scheme contain a colon, but no node name
+        let (node, from) =
+            if target.starts_with("rostopic://") || target.starts_with("rosservice://") {
+                (None, target)
+            } else {
+                match target.split_once(':') {
+                    Some((node, from)) => (Some(node), from),
+                    None => (None, target),
+                }
+            };
+        if node == Some("") || from.is_empty() || to.is_empty() {
+            return None;
+        }
+        Some(Self {
+            node: node.map(str::to_owned),
+            from: from.to_owned(),
+            to: to.to_owned(),
+        })
+    }
+}
+
+impl Display for RemapRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(node) = &self.node {
+            write!(f, "{}:", node)?;
+        }
+        write!(f, "{}:={}", self.from, self.to)
+    }
+}
+
+impl ParameterArgument {
+    fn parse(parameter: &str) -> Option<Self> {
+        let (target, value) = parameter.split_once(":=")?;
+        let (node, name) = match target.split_once(':') {
+            Some((node, name)) => (Some(node), name),
+            None => (None, target),
+        };
+        if node == Some("") || name.is_empty() {
+            return None;
+        }
+        Some(Self {
+            node: node.map(str::to_owned),
+            name: name.to_owned(),
+            value: value.to_owned(),
+        })
+    }
+}
+
+impl Display for ParameterArgument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(node) = &self.node {
+            write!(f, "{}:", node)?;
+        }
+        write!(f, "{}:={}", self.name, self.value)
+    }
+}
+
+impl LogLevelArgument {
+    fn parse(level: &str) -> Option<Self> {
+        let (logger, level) = match level.split_once(":=") {
+            Some((logger, level)) => (Some(logger), level),
+            None => (None, level),
+        };
+        if logger == Some("")
+            || !LOG_LEVELS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(level))
+        {
+            return None;
+        }
+        Some(Self {
+            logger: logger.map(str::to_owned),
+            level: level.to_owned(),
+        })
+    }
+}
+
+impl Display for LogLevelArgument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(logger) = &self.logger {
+            write!(f, "{}:=", logger)?;
+        }
+        write!(f, "{}", self.level)
+    }
+}
+
+impl Display for RosArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self.kind {
+            RosArgsErrorKind::Unknown => "Unknown ROS argument",
+            RosArgsErrorKind::MissingValue => "Missing value for ROS argument",
+            RosArgsErrorKind::InvalidRemapRule => "Invalid remap rule",
+            RosArgsErrorKind::InvalidParameter => "Invalid parameter override",
+            RosArgsErrorKind::InvalidLogLevel => "Invalid log level",
+        };
+        write!(
+            f,
+            "{} '{}' at position {}",
+            description, self.argument, self.position
+        )
+    }
+}
+
+impl std::error::Error for RosArgsError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<RosArgs, RclrsError> {
+        RosArgs::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_ros_args() -> Result<(), RclrsError> {
+        let ros_args = parse(&[
+            "exe",
+            "--ros-args",
+            "--remap",
+            "rosservice://my_node/get:=get",
+            "-p",
+            "my_node:names:=['a', 'b']",
+            "--params-file",
+            "params.yaml",
+            "--",
+            "-p",
+            "--ros-args",
+            "-e",
+            "/my_enclave",
+            "--log-level",
+            "rclrs:=DEBUG",
+            "--disable-stdout-logs",
+        ])?;
+        assert_eq!(
+            ros_args,
+            RosArgs {
+                remap_rules: vec![RemapRule::new("rosservice://my_node/get", "get")],
+                parameters: vec![ParameterArgument {
+                    node: Some(String::from("my_node")),
+                    name: String::from("names"),
+                    value: String::from("['a', 'b']"),
+                }],
+                parameter_files: vec![String::from("params.yaml")],
+                log_levels: vec![LogLevelArgument {
+                    logger: Some(String::from("rclrs")),
+                    level: String::from("DEBUG"),
+                }],
+                enclave: Some(String::from("/my_enclave")),
+                stdout_logs: Some(false),
+                ..Default::default()
+            }
+        );
+        assert_eq!(RosArgs::parse(ros_args.to_args())?, ros_args);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_ros_args() {
+        let error = |position: usize, argument: &str, kind| RosArgsError {
+            position,
+            argument: argument.to_owned(),
+            kind,
+        };
+        assert_eq!(
+            parse(&[
+                "--ros-args",
+                "-r",
+                ":=b",
+                "--unknown",
+                "-p",
+                "no_value",
+                "--log-level",
+                "verbose",
+                "--enclave",
+            ]),
+            Err(RclrsError::InvalidRosArgs {
+                errors: vec![
+                    error(2, ":=b", RosArgsErrorKind::InvalidRemapRule),
+                    error(3, "--unknown", RosArgsErrorKind::Unknown),
+                    error(5, "no_value", RosArgsErrorKind::InvalidParameter),
+                    error(7, "verbose", RosArgsErrorKind::InvalidLogLevel),
+                    error(8, "--enclave", RosArgsErrorKind::MissingValue),
+                ]
+            })
+        );
+        assert_eq!(
+            parse(&["--unknown", "--ros-args", "--", "--unknown"]),
+            Ok(RosArgs::default())
+        );
+    }
+}
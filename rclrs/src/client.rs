@@ -2,14 +2,19 @@ use std::boxed::Box;
 use std::collections::HashMap;
 use std::ffi::CString;
 use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::{self, RecvTimeoutError, TryRecvError};
 use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 
 use futures::channel::oneshot;
 use rosidl_runtime_rs::Message;
 
+#[cfg(not(ros_distro = "humble"))]
+use crate::clock::ClockHandle;
 use crate::error::{RclReturnCode, ToResult};
-use crate::MessageCow;
-use crate::{rcl_bindings::*, RclrsError};
+#[cfg(not(ros_distro = "humble"))]
+use crate::ServiceIntrospectionState;
+use crate::{rcl_bindings::*, ClientError, MessageCow, QoSProfile, RclrsError, WaitSet};
 
 // SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
 // they are running in. Therefore, this type can be safely sent to another thread.
@@ -20,6 +25,10 @@ pub struct ClientHandle {
     rcl_client_mtx: Mutex<rcl_client_t>,
     rcl_node_mtx: Arc<Mutex<rcl_node_t>>,
     pub(crate) in_use_by_wait_set: Arc<AtomicBool>,
+    // The clock used to stamp service events, created when introspection is first configured.
+    // It is dropped after the client, which holds a pointer to it.
+    #[cfg(not(ros_distro = "humble"))]
+    introspection_clock: Mutex<Option<ClockHandle>>,
 }
 
 impl ClientHandle {
@@ -47,12 +56,69 @@ pub trait ClientBase: Send + Sync {
     fn handle(&self) -> &ClientHandle;
     /// Tries to take a new response and run the callback or future with it.
     fn execute(&self) -> Result<(), RclrsError>;
+    /// Returns the earliest point in time at which a pending request times out, if any.
+    fn next_request_deadline(&self) -> Option<Instant>;
+    /// Completes all pending requests whose timeout has elapsed with [`ClientError::Timeout`].
+    ///
+    /// Returns the number of requests that timed out.
+    fn prune_timed_out_requests(&self) -> usize;
 }
 
-type RequestValue<Response> = Box<dyn FnOnce(Response) + 'static + Send>;
+type RequestCallback<Response> = Box<dyn FnOnce(Result<Response, ClientError>) + 'static + Send>;
 
 type RequestId = i64;
 
+enum PendingRequest<Response> {
+    Callback {
+        callback: RequestCallback<Response>,
+        deadline: Option<Instant>,
+    },
+    Future {
+        sender: oneshot::Sender<Result<Response, ClientError>>,
+        deadline: Option<Instant>,
+    },
+}
+
+impl<Response> PendingRequest<Response> {
+    fn deadline(&self) -> Option<Instant> {
+        match self {
+            PendingRequest::Callback { deadline, .. } | PendingRequest::Future { deadline, .. } => {
+                *deadline
+            }
+        }
+    }
+
+    // Must not be called while the pending requests are locked, since the callback may send new
+    // requests
+    fn complete(self, result: Result<Response, ClientError>) {
+        match self {
+            PendingRequest::Callback { callback, .. } => callback(result),
+            PendingRequest::Future { sender, .. } => {
+                // The receiver is gone if the future was dropped in the meantime
+                let _ = sender.send(result);
+            }
+        }
+    }
+}
+
+type PendingRequests<Response> = Mutex<HashMap<RequestId, PendingRequest<Response>>>;
+
+// Removes a pending request when the future waiting for its response is dropped, so that
+// cancelled requests don't accumulate.
+struct PendingRequestGuard<'a, Response> {
+    pending_requests: &'a PendingRequests<Response>,
+    sequence_number: RequestId,
+}
+
+impl<Response> Drop for PendingRequestGuard<'_, Response> {
+    fn drop(&mut self) {
+        self.pending_requests
+            .lock()
+            .unwrap()
+            .remove(&self.sequence_number);
+    }
+}
+
 /// Main class responsible for sending requests to a ROS service.
 ///
 /// The only available way to instantiate clients is via [`Node::create_client`][1], this is to
@@ -65,8 +131,8 @@ where
     T: rosidl_runtime_rs::Service,
 {
     pub(crate) handle: Arc<ClientHandle>,
-    requests: Mutex<HashMap<RequestId, RequestValue<T::Response>>>,
-    futures: Arc<Mutex<HashMap<RequestId, oneshot::Sender<T::Response>>>>,
+    rcl_context_mtx: Arc<Mutex<rcl_context_t>>,
+    pending_requests: PendingRequests<T::Response>,
 }
 
 impl<T> Client<T>
@@ -74,7 +140,12 @@ where
     T: rosidl_runtime_rs::Service,
 {
     /// Creates a new client.
-    pub(crate) fn new(rcl_node_mtx: Arc<Mutex<rcl_node_t>>, topic: &str) -> Result<Self, RclrsError>
+    pub(crate) fn new(
+        rcl_node_mtx: Arc<Mutex<rcl_node_t>>,
+        rcl_context_mtx: Arc<Mutex<rcl_context_t>>,
+        topic: &str,
+        qos: QoSProfile,
+    ) -> Result<Self, RclrsError>
     // This uses pub(crate) visibility to avoid instantiating this struct outside
     // [`Node::create_client`], see the struct's documentation for the rationale
     where
@@ -90,7 +161,8 @@ where
         })?;
 
         // SAFETY: No preconditions for this function.
-        let client_options = unsafe { rcl_client_get_default_options() };
+        let mut client_options = unsafe { rcl_client_get_default_options() };
+        client_options.qos = qos.into();
 
         unsafe {
             // SAFETY: The rcl_client is zero-initialized as expected by this function.
@@ -111,14 +183,14 @@ where
             rcl_client_mtx: Mutex::new(rcl_client),
             rcl_node_mtx,
             in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
+            #[cfg(not(ros_distro = "humble"))]
+            introspection_clock: Mutex::new(None),
         });
 
         Ok(Self {
             handle,
-            requests: Mutex::new(HashMap::new()),
-            futures: Arc::new(Mutex::new(
-                HashMap::<RequestId, oneshot::Sender<T::Response>>::new(),
-            )),
+            rcl_context_mtx,
+            pending_requests: Mutex::new(HashMap::new()),
         })
     }
 
@@ -134,6 +206,9 @@ where
     ///
     /// Hence, when a message will not be needed anymore after publishing, pass it by value.
     /// When a message will be needed again after publishing, pass it by reference, instead of cloning and passing by value.
+    ///
+    /// The callback is not called if no response arrives. Use
+    /// [`Client::async_send_request_with_callback_and_timeout()`] to be notified of that.
     pub fn async_send_request_with_callback<'a, M: MessageCow<'a, T::Request>, F>(
         &self,
         message: M,
@@ -142,19 +217,51 @@ where
     where
         F: FnOnce(T::Response) + 'static + Send,
     {
-        let rmw_message = T::Request::into_rmw_message(message.into_cow());
-        let mut sequence_number = -1;
-        unsafe {
-            // SAFETY: The request type is guaranteed to match the client type by the type system.
-            rcl_send_request(
-                &*self.handle.lock() as *const _,
-                rmw_message.as_ref() as *const <T::Request as Message>::RmwMsg as *mut _,
-                &mut sequence_number,
-            )
-        }
-        .ok()?;
-        let requests = &mut *self.requests.lock().unwrap();
-        requests.insert(sequence_number, Box::new(callback));
+        let callback = Box::new(move |result: Result<T::Response, ClientError>| {
+            // Without a timeout, the only error is that the request was discarded
+            if let Ok(response) = result {
+                callback(response);
+            }
+        });
+        self.send_request(
+            message,
+            PendingRequest::Callback {
+                callback,
+                deadline: None,
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Sends a request with a callback to be called with the response, or with an error if no
+    /// response arrives within the given timeout.
+    ///
+    /// When the timeout elapses, the callback is called with [`ClientError::Timeout`] and a
+    /// response that arrives later is discarded. If the request is discarded before that, e.g. by
+    /// [`Client::prune_pending_requests()`], the callback is called with
+    /// [`ClientError::ServiceUnavailable`].
+    /// The timeout is checked while the client's node is being spun, e.g. by
+    /// [`spin()`][1], so it is not enforced when nothing is spinning.
+    ///
+    /// See [`Client::async_send_request_with_callback()`] for more details.
+    ///
+    /// [1]: crate::spin
+    pub fn async_send_request_with_callback_and_timeout<'a, M: MessageCow<'a, T::Request>, F>(
+        &self,
+        message: M,
+        timeout: Duration,
+        callback: F,
+    ) -> Result<(), RclrsError>
+    where
+        F: FnOnce(Result<T::Response, ClientError>) + 'static + Send,
+    {
+        self.send_request(
+            message,
+            PendingRequest::Callback {
+                callback: Box::new(callback),
+                deadline: Some(Instant::now() + timeout),
+            },
+        )?;
         Ok(())
     }
 
@@ -170,6 +277,11 @@ where
     ///
     /// Hence, when a message will not be needed anymore after publishing, pass it by value.
     /// When a message will be needed again after publishing, pass it by reference, instead of cloning and passing by value.
+    ///
+    /// Dropping the future before it completes cancels the request, i.e. a response that arrives
+    /// later is discarded.
+    /// If the request is discarded before a response arrives, e.g. by
+    /// [`Client::prune_pending_requests()`], this returns [`ClientError::ServiceUnavailable`].
     pub async fn call_async<'a, R: MessageCow<'a, T::Request>>(
         &self,
         request: R,
@@ -177,7 +289,172 @@ where
     where
         T: rosidl_runtime_rs::Service,
     {
-        let rmw_message = T::Request::into_rmw_message(request.into_cow());
+        self.call_async_with_deadline(request, None).await
+    }
+
+    /// Sends a request and returns the response as a `Future`, failing if no response arrives
+    /// within the given timeout.
+    ///
+    /// When the timeout elapses, the future completes with [`ClientError::Timeout`].
+    /// The timeout is checked while the client's node is being spun, e.g. by
+    /// [`spin()`][1], so it is not enforced when nothing is spinning.
+    ///
+    /// See [`Client::call_async()`] for more details.
+    ///
+    /// [1]: crate::spin
+    pub async fn call_async_with_timeout<'a, R: MessageCow<'a, T::Request>>(
+        &self,
+        request: R,
+        timeout: Duration,
+    ) -> Result<T::Response, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+    {
+        self.call_async_with_deadline(request, Some(Instant::now() + timeout))
+            .await
+    }
+
+    async fn call_async_with_deadline<'a, R: MessageCow<'a, T::Request>>(
+        &self,
+        request: R,
+        deadline: Option<Instant>,
+    ) -> Result<T::Response, RclrsError> {
+        let (sender, receiver) = oneshot::channel();
+        let sequence_number =
+            self.send_request(request, PendingRequest::Future { sender, deadline })?;
+        let _guard = PendingRequestGuard {
+            pending_requests: &self.pending_requests,
+            sequence_number,
+        };
+        match receiver.await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(err)) => Err(RclrsError::RequestFailed { err }),
+            // The sender is only dropped without sending when the request is discarded
+            Err(oneshot::Canceled) => Err(RclrsError::RequestFailed {
+                err: ClientError::ServiceUnavailable,
+            }),
+        }
+    }
+
+    /// Sends a request and blocks until the response arrives or the timeout elapses.
+    ///
+    /// Unlike [`Client::call_async()`], this does not require the client's node to be spun
+    /// elsewhere: the client is waited on in a dedicated wait set that contains only this client.
+    /// Responses to other pending requests of this client that arrive in the meantime are
+    /// dispatched to their callbacks or futures as usual.
+    ///
+    /// A timeout of `None` waits indefinitely. When the timeout elapses, this returns
+    /// [`ClientError::Timeout`] and a response that arrives later is discarded.
+    ///
+    /// # Deadlocks
+    ///
+    /// - Calling this from a callback that is run by [`spin()`][1] or [`spin_once()`][2] on
+    ///   the client's node is fine, since the executor does not keep its wait set while running
+    ///   callbacks. However, no other callbacks of that executor run until this returns, so the
+    ///   service must not be served by the same executor, or this will only return once the
+    ///   timeout elapses.
+    /// - If another thread is waiting on this client, e.g. because it is spinning the client's
+    ///   node, the response is delivered by that thread instead and this merely waits for it.
+    ///   Conversely, while this is waiting on the client, a thread that starts spinning the
+    ///   client's node fails with [`RclrsError::AlreadyAddedToWaitSet`]. Prefer
+    ///   [`Client::call_async()`] when the node is spun in another thread.
+    ///
+    /// [1]: crate::spin
+    /// [2]: crate::spin_once
+    pub fn call<'a, R: MessageCow<'a, T::Request>>(
+        self: &Arc<Self>,
+        request: R,
+        timeout: Option<Duration>,
+    ) -> Result<T::Response, RclrsError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let (sender, receiver) = mpsc::channel();
+        let sequence_number = self.send_request(
+            request,
+            PendingRequest::Callback {
+                callback: Box::new(move |result| {
+                    // The receiver is only gone once this function has returned
+                    let _ = sender.send(result);
+                }),
+                // An executor spinning the node in another thread times the request out as well
+                deadline,
+            },
+        )?;
+        let _guard = PendingRequestGuard {
+            pending_requests: &self.pending_requests,
+            sequence_number,
+        };
+        let mut wait_set = WaitSet::new_with_rcl_context(0, 0, 0, 1, 0, 0, &self.rcl_context_mtx)?;
+        let mut in_wait_set = false;
+        loop {
+            match receiver.try_recv() {
+                Ok(Ok(response)) => return Ok(response),
+                Ok(Err(err)) => return Err(RclrsError::RequestFailed { err }),
+                // The sender is only dropped without sending when the request is discarded
+                Err(TryRecvError::Disconnected) => {
+                    return Err(RclrsError::RequestFailed {
+                        err: ClientError::ServiceUnavailable,
+                    })
+                }
+                Err(TryRecvError::Empty) => {}
+            }
+            let remaining = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(remaining) => Some(remaining),
+                    None => {
+                        return Err(RclrsError::RequestFailed {
+                            err: ClientError::Timeout,
+                        })
+                    }
+                },
+                None => None,
+            };
+            if !in_wait_set {
+                match wait_set.add_client(Arc::clone(self) as Arc<dyn ClientBase>) {
+                    Ok(()) => in_wait_set = true,
+                    // Another thread is waiting on this client and will deliver the response
+                    Err(RclrsError::AlreadyAddedToWaitSet) => {}
+                    Err(err) => return Err(err),
+                }
+            }
+            if in_wait_set {
+                match wait_set.wait_for_ready_indices(remaining) {
+                    Ok(ready_indices) => {
+                        if !ready_indices.clients.is_empty() {
+                            self.execute()?;
+                        }
+                    }
+                    Err(RclrsError::RclError {
+                        code: RclReturnCode::Timeout,
+                        ..
+                    }) => {}
+                    Err(err) => return Err(err),
+                }
+            } else {
+                let received = match remaining {
+                    Some(remaining) => receiver.recv_timeout(remaining),
+                    None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                match received {
+                    Ok(Ok(response)) => return Ok(response),
+                    Ok(Err(err)) => return Err(RclrsError::RequestFailed { err }),
+                    // Handled at the start of the next iteration
+                    Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => {}
+                }
+            }
+        }
+    }
+
+    // Sends the request and stores the pending request under its sequence number.
+    fn send_request<'a, M: MessageCow<'a, T::Request>>(
+        &self,
+        message: M,
+        pending_request: PendingRequest<T::Response>,
+    ) -> Result<RequestId, RclrsError> {
+        let rmw_message = T::Request::try_into_rmw_message(message.into_cow())
+            .map_err(|err| RclrsError::InvalidMessage { err })?;
+        // Holding the lock while sending ensures that the response can't be taken before the
+        // pending request is stored.
+        let mut pending_requests = self.pending_requests.lock().unwrap();
         let mut sequence_number = -1;
         unsafe {
             // SAFETY: The request type is guaranteed to match the client type by the type system.
@@ -188,12 +465,44 @@ where
             )
         }
         .ok()?;
-        let (tx, rx) = oneshot::channel::<T::Response>();
-        self.futures.lock().unwrap().insert(sequence_number, tx);
-        // It is safe to call unwrap() here since the `Canceled` error will only happen when the
-        // `Sender` is dropped
-        // https://docs.rs/futures/latest/futures/channel/oneshot/struct.Canceled.html
-        Ok(rx.await.unwrap())
+        pending_requests.insert(sequence_number, pending_request);
+        Ok(sequence_number)
+    }
+
+    /// Discards all pending requests.
+    ///
+    /// Futures of discarded requests complete with [`ClientError::ServiceUnavailable`], and so do
+    /// callbacks of requests sent with
+    /// [`Client::async_send_request_with_callback_and_timeout()`]. Other callbacks are dropped
+    /// without being called.
+    /// Responses to discarded requests that arrive later are ignored.
+    ///
+    /// Returns the number of discarded requests.
+    pub fn prune_pending_requests(&self) -> usize {
+        let pending_requests = std::mem::take(&mut *self.pending_requests.lock().unwrap());
+        let num_pruned = pending_requests.len();
+        for pending_request in pending_requests.into_values() {
+            pending_request.complete(Err(ClientError::ServiceUnavailable));
+        }
+        num_pruned
+    }
+
+    /// Returns the number of requests that are still waiting for a response.
+    pub fn num_pending_requests(&self) -> usize {
+        self.pending_requests.lock().unwrap().len()
+    }
+
+    /// Checks whether a service server is available for this client.
+    pub fn service_is_ready(&self) -> Result<bool, RclrsError> {
+        let mut is_ready = false;
+        let client = &*self.handle.lock();
+        let node = &*self.handle.rcl_node_mtx.lock().unwrap();
+        unsafe {
+            // SAFETY: The node and client are both valid, and is_ready is a valid out-parameter.
+            rcl_service_server_is_available(node, client, &mut is_ready)
+        }
+        .ok()?;
+        Ok(is_ready)
     }
 
     /// Fetches a new response.
@@ -236,7 +545,50 @@ where
             )
         }
         .ok()?;
-        Ok((T::Response::from_rmw_message(response_out), request_id_out))
+        let response = T::Response::try_from_rmw_message(response_out)
+            .map_err(|err| RclrsError::InvalidMessage { err })?;
+        Ok((response, request_id_out))
+    }
+
+    /// Configures service introspection for this client.
+    ///
+    /// Service events are published on the `<service>/_service_event` topic with the given QoS
+    /// profile, see [`ServiceIntrospectionState`] for the available levels of detail.
+    /// Events are stamped with the system time.
+    ///
+    /// This can be called repeatedly, e.g. to turn introspection off again.
+    #[cfg(not(ros_distro = "humble"))]
+    pub fn configure_introspection(
+        &self,
+        state: ServiceIntrospectionState,
+        qos: QoSProfile,
+    ) -> Result<(), RclrsError> {
+        let type_support = <T as rosidl_runtime_rs::Service>::get_type_support()
+            as *const rosidl_service_type_support_t;
+        // SAFETY: No preconditions for this function.
+        let mut publisher_options = unsafe { rcl_publisher_get_default_options() };
+        publisher_options.qos = qos.into();
+
+        let mut introspection_clock = self.handle.introspection_clock.lock().unwrap();
+        if introspection_clock.is_none() {
+            *introspection_clock = Some(ClockHandle::new(rcl_clock_type_t::RCL_SYSTEM_TIME)?);
+        }
+        let clock = introspection_clock.as_mut().unwrap();
+        let rcl_client = &mut *self.handle.lock();
+        let rcl_node = &mut *self.handle.rcl_node_mtx.lock().unwrap();
+        unsafe {
+            // SAFETY: The client and node are valid. The clock is owned by the client handle
+            // and outlives the client. The publisher options are copied by this function.
+            rcl_client_configure_service_introspection(
+                rcl_client,
+                rcl_node,
+                clock.as_mut_ptr(),
+                type_support,
+                publisher_options,
+                state.into(),
+            )
+            .ok()
+        }
     }
 }
 
@@ -261,13 +613,46 @@ where
             }
             Err(e) => return Err(e),
         };
-        let requests = &mut *self.requests.lock().unwrap();
-        let futures = &mut *self.futures.lock().unwrap();
-        if let Some(callback) = requests.remove(&req_id.sequence_number) {
-            callback(res);
-        } else if let Some(future) = futures.remove(&req_id.sequence_number) {
-            let _ = future.send(res);
+        let pending_request = self
+            .pending_requests
+            .lock()
+            .unwrap()
+            .remove(&req_id.sequence_number);
+        // The lock is released before running the callback, so that it can send new requests.
+        // There is no pending request if it was cancelled or pruned.
+        if let Some(pending_request) = pending_request {
+            pending_request.complete(Ok(res));
         }
         Ok(())
     }
+
+    fn next_request_deadline(&self) -> Option<Instant> {
+        { self.pending_requests.lock().unwrap() }
+            .values()
+            .filter_map(PendingRequest::deadline)
+            .min()
+    }
+
+    fn prune_timed_out_requests(&self) -> usize {
+        let now = Instant::now();
+        let timed_out: Vec<PendingRequest<T::Response>> = {
+            let mut pending_requests = self.pending_requests.lock().unwrap();
+            let sequence_numbers: Vec<RequestId> = pending_requests
+                .iter()
+                .filter(|(_, pending_request)| {
+                    matches!(pending_request.deadline(), Some(deadline) if deadline <= now)
+                })
+                .map(|(sequence_number, _)| *sequence_number)
+                .collect();
+            sequence_numbers
+                .iter()
+                .filter_map(|sequence_number| pending_requests.remove(sequence_number))
+                .collect()
+        };
+        let num_timed_out = timed_out.len();
+        for pending_request in timed_out {
+            pending_request.complete(Err(ClientError::Timeout));
+        }
+        num_timed_out
+    }
 }
@@ -0,0 +1,43 @@
+use crate::error::{RclrsError, ToResult};
+use crate::rcl_bindings::*;
+
+// SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
+// they are running in. Therefore, this type can be safely sent to another thread.
+unsafe impl Send for rcl_clock_t {}
+
+/// Internal struct owning an initialized `rcl_clock_t`.
+///
+/// The clock is boxed so that its address stays stable, since `rcl` entities that use a clock
+/// only store a pointer to it.
+pub(crate) struct ClockHandle {
+    rcl_clock: Box<rcl_clock_t>,
+}
+
+impl Drop for ClockHandle {
+    fn drop(&mut self) {
+        // SAFETY: The clock was successfully initialized in ClockHandle::new().
+        unsafe {
+            rcl_clock_fini(&mut *self.rcl_clock);
+        }
+    }
+}
+
+impl ClockHandle {
+    /// Creates a new clock of the given type.
+    pub(crate) fn new(clock_type: rcl_clock_type_t) -> Result<Self, RclrsError> {
+        // SAFETY: rcl_clock_t is a plain struct of integers, pointers and optional function
+        // pointers, for which all-zeroes is a valid bit pattern. rcl_clock_init() overwrites it.
+        let mut rcl_clock = Box::new(unsafe { std::mem::zeroed::<rcl_clock_t>() });
+        // SAFETY: No preconditions for this function.
+        let mut allocator = unsafe { rcutils_get_default_allocator() };
+        // SAFETY: The clock is not yet initialized and the allocator is valid. The allocator is
+        // copied into the clock, so it can be dropped afterwards.
+        unsafe { rcl_clock_init(clock_type, &mut *rcl_clock, &mut allocator) }.ok()?;
+        Ok(Self { rcl_clock })
+    }
+
+    /// Returns a pointer to the clock that stays valid for as long as this handle exists.
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut rcl_clock_t {
+        &mut *self.rcl_clock
+    }
+}
@@ -0,0 +1,249 @@
+use std::sync::Arc;
+
+use crate::{Context, Node, NodeBuilder, RclrsError};
+
+/// Configuration for creating the node of a [`Component`].
+///
+/// This is the equivalent of `rclcpp::NodeOptions`. A component container creates these options
+/// from the request to load the component, e.g. with the node name, namespace, remappings and
+/// parameters given to `ros2 component load` as arguments.
+pub struct ComponentOptions {
+    context: Context,
+    arguments: Vec<String>,
+    use_global_arguments: bool,
+    use_intra_process_comms: bool,
+}
+
+impl ComponentOptions {
+    /// Creates options for the given context.
+    ///
+    /// The default values are:
+    /// - `arguments: []`
+    /// - `use_global_arguments: true`
+    /// - `use_intra_process_comms: false`
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: clone_context(context),
+            arguments: vec![],
+            use_global_arguments: true,
+            use_intra_process_comms: false,
+        }
+    }
+
+    /// Sets node-specific command line arguments.
+    ///
+    /// See [`NodeBuilder::arguments()`] for details.
+    pub fn arguments(mut self, arguments: impl IntoIterator<Item = String>) -> Self {
+        self.arguments = arguments.into_iter().collect();
+        self
+    }
+
+    /// Enables or disables using the arguments the context was created with.
+    ///
+    /// See [`NodeBuilder::use_global_arguments()`] for details.
+    pub fn use_global_arguments(mut self, enable: bool) -> Self {
+        self.use_global_arguments = enable;
+        self
+    }
+
+    /// Enables or disables intra-process communication.
+    ///
+    /// See [`NodeBuilder::use_intra_process_comms()`] for details.
+    pub fn use_intra_process_comms(mut self, enable: bool) -> Self {
+        self.use_intra_process_comms = enable;
+        self
+    }
+
+    /// Returns the context that the component's node should be created in.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Creates a builder for the component's node with these options applied.
+    ///
+    /// The name is only the default name of the node, which may be remapped by the arguments.
+    pub fn node_builder(&self, node_name: &str) -> NodeBuilder {
+        NodeBuilder::new(&self.context, node_name)
+            .arguments(self.arguments.iter().cloned())
+            .use_global_arguments(self.use_global_arguments)
+            .use_intra_process_comms(self.use_intra_process_comms)
+    }
+}
+
+impl Clone for ComponentOptions {
+    fn clone(&self) -> Self {
+        Self {
+            context: clone_context(&self.context),
+            arguments: self.arguments.clone(),
+            use_global_arguments: self.use_global_arguments,
+            use_intra_process_comms: self.use_intra_process_comms,
+        }
+    }
+}
+
+/// A node that can be loaded into a component container.
+///
+/// Components are registered under a class name with a [`ComponentRegistry`], or with the
+/// [`register_components!`][1] macro for components in a shared library.
+///
+/// # Example
+/// ```
+/// # use std::sync::Arc;
+/// # use rclrs::{Component, ComponentOptions, ComponentRegistry, Context, Node, RclrsError};
+/// struct Talker {
+///     node: Arc<Node>,
+/// }
+///
+/// impl Component for Talker {
+///     fn new(options: ComponentOptions) -> Result<Self, RclrsError> {
+///         let node = Arc::new(options.node_builder("talker").build()?);
+///         Ok(Self { node })
+///     }
+///
+///     fn node(&self) -> &Arc<Node> {
+///         &self.node
+///     }
+/// }
+///
+/// let mut registry = ComponentRegistry::new();
+/// registry.register::<Talker>("demo::Talker");
+/// let context = Context::new([])?;
+/// let options = ComponentOptions::new(&context).arguments(
+///     ["--ros-args", "-r", "__node:=my_talker"].map(String::from),
+/// );
+/// let component = registry.get("demo::Talker").unwrap().create(options)?;
+/// assert_eq!(component.node().name(), "my_talker");
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::register_components
+pub trait Component: Send {
+    /// Creates the component and its node.
+    ///
+    /// The node should be created with [`ComponentOptions::node_builder()`].
+    fn new(options: ComponentOptions) -> Result<Self, RclrsError>
+    where
+        Self: Sized;
+
+    /// Returns the node of the component, which is spun by the component container.
+    fn node(&self) -> &Arc<Node>;
+}
+
+/// Creates components of one type.
+#[derive(Clone, Copy)]
+pub struct ComponentFactory {
+    class_name: &'static str,
+    create: fn(ComponentOptions) -> Result<Box<dyn Component>, RclrsError>,
+}
+
+impl ComponentFactory {
+    /// Creates a factory for components of type `C` with the given class name.
+    pub fn new<C: Component + 'static>(class_name: &'static str) -> Self {
+        Self {
+            class_name,
+            create: create_component::<C>,
+        }
+    }
+
+    /// Returns the class name of the components.
+    pub fn class_name(&self) -> &'static str {
+        self.class_name
+    }
+
+    /// Creates a component.
+    pub fn create(&self, options: ComponentOptions) -> Result<Box<dyn Component>, RclrsError> {
+        (self.create)(options)
+    }
+}
+
+/// A list of component factories, looked up by class name.
+///
+/// A component container uses a registry for components that are linked into the container,
+/// and one registry for each shared library it loads components from.
+#[derive(Clone, Default)]
+pub struct ComponentRegistry {
+    factories: Vec<ComponentFactory>,
+}
+
+impl ComponentRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers components of type `C` under the given class name.
+    ///
+    /// The class name is usually of the form `<package>::<type>`, and must be unique. A component
+    /// registered with the same class name before is replaced.
+    pub fn register<C: Component + 'static>(&mut self, class_name: &'static str) {
+        self.add(ComponentFactory::new::<C>(class_name));
+    }
+
+    /// Adds a factory to the registry, replacing any factory with the same class name.
+    pub fn add(&mut self, factory: ComponentFactory) {
+        self.factories
+            .retain(|existing| existing.class_name != factory.class_name);
+        self.factories.push(factory);
+    }
+
+    /// Returns the factory with the given class name.
+    pub fn get(&self, class_name: &str) -> Option<&ComponentFactory> {
+        self.factories
+            .iter()
+            .find(|factory| factory.class_name == class_name)
+    }
+
+    /// Returns the class names of all registered components.
+    pub fn class_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.factories.iter().map(|factory| factory.class_name)
+    }
+}
+
+/// The name of the function that is defined by [`register_components!`][1].
+///
+/// [1]: crate::register_components
+pub const REGISTER_COMPONENTS_SYMBOL: &str = "rclrs_register_components";
+
+/// Registers the components of a shared library, so that they can be loaded by a component
+/// container.
+///
+/// This defines a `rclrs_register_components()` function, which the container looks up after
+/// loading the library. The library must be a `cdylib` built with the same Rust compiler and
+/// `rclrs` version as the container, since the function uses the Rust ABI.
+///
+/// The container finds the library through the ament resource index: a file named after the
+/// package in `share/ament_index/resource_index/rclrs_components/` lists the path of the library
+/// relative to the install prefix. Without that file, the library is expected at
+/// `lib/lib<package>.so` (`.dylib` on macOS, `bin/<package>.dll` on Windows).
+///
+/// # Example
+/// ```ignore
+/// rclrs::register_components! {
+///     my_package::Talker => "my_package::Talker",
+///     my_package::Listener => "my_package::Listener",
+/// }
+/// ```
+#[macro_export]
+macro_rules! register_components {
+    ($($component:ty => $class_name:expr),+ $(,)?) => {
+        /// Registers the components of this library with a component container.
+        #[no_mangle]
+        pub fn rclrs_register_components(registry: &mut $crate::ComponentRegistry) {
+            $(registry.register::<$component>($class_name);)+
+        }
+    };
+}
+
+// The context is shared by the nodes of all components, like it is shared by all nodes
+fn clone_context(context: &Context) -> Context {
+    Context {
+        rcl_context_mtx: Arc::clone(&context.rcl_context_mtx),
+        intra_process_manager: Arc::clone(&context.intra_process_manager),
+    }
+}
+
+fn create_component<C: Component + 'static>(
+    options: ComponentOptions,
+) -> Result<Box<dyn Component>, RclrsError> {
+    Ok(Box::new(C::new(options)?))
+}
@@ -4,6 +4,8 @@ use std::string::String;
 use std::sync::{Arc, Mutex};
 use std::vec::Vec;
 
+use crate::arguments::check_unknown_ros_args;
+use crate::intra_process::IntraProcessManager;
 use crate::rcl_bindings::*;
 use crate::{RclrsError, ToResult};
 
@@ -40,6 +42,7 @@ unsafe impl Send for rcl_context_t {}
 ///
 pub struct Context {
     pub(crate) rcl_context_mtx: Arc<Mutex<rcl_context_t>>,
+    pub(crate) intra_process_manager: Arc<IntraProcessManager>,
 }
 
 impl Context {
@@ -48,20 +51,28 @@ impl Context {
     /// Usually, this would be called with `std::env::args()`, analogously to `rclcpp::init()`.
     /// See also the official "Passing ROS arguments to nodes via the command-line" tutorial.
     ///
-    /// Creating a context can fail in case the args contain invalid ROS arguments.
+    /// Creating a context can fail in case the args contain invalid ROS arguments. ROS arguments
+    /// that `rcl` does not know are an error as well, unlike in `rclcpp`, where they are only
+    /// reported as a warning.
     ///
     /// # Example
     /// ```
-    /// # use rclrs::Context;
+    /// # use rclrs::{Context, RclrsError};
     /// assert!(Context::new([]).is_ok());
     /// let invalid_remapping = ["--ros-args", "-r", ":=:*/]"].map(String::from);
     /// assert!(Context::new(invalid_remapping).is_err());
+    /// let unknown_argument = ["--ros-args", "--remapp", "a:=b"].map(String::from);
+    /// assert!(matches!(
+    ///     Context::new(unknown_argument),
+    ///     Err(RclrsError::InvalidRosArgs { .. })
+    /// ));
     /// ```
     pub fn new(args: impl IntoIterator<Item = String>) -> Result<Self, RclrsError> {
+        let args: Vec<String> = args.into_iter().collect();
         // SAFETY: Getting a zero-initialized value is always safe
         let mut rcl_context = unsafe { rcl_get_zero_initialized_context() };
         let cstring_args: Vec<CString> = args
-            .into_iter()
+            .iter()
             .map(|arg| {
                 CString::new(arg.as_str()).map_err(|err| RclrsError::StringContainsNul {
                     err,
@@ -98,8 +109,12 @@ impl Context {
             // Move the check after the last fini()
             ret?;
         }
+        // rcl ignores unknown ROS arguments, so they are checked here. The context is cleaned up
+        // when it is dropped.
+        check_unknown_ros_args(&rcl_context.global_arguments, &args)?;
         Ok(Self {
             rcl_context_mtx: Arc::new(Mutex::new(rcl_context)),
+            intra_process_manager: Arc::new(IntraProcessManager::default()),
         })
     }
 
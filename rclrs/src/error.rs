@@ -3,6 +3,7 @@ use std::ffi::{CStr, NulError};
 use std::fmt::{self, Display};
 
 use crate::rcl_bindings::*;
+use crate::RosArgsError;
 
 /// The main error type.
 #[derive(Debug, PartialEq, Eq)]
@@ -30,6 +31,38 @@ pub enum RclrsError {
     },
     /// It was attempted to add a waitable to a wait set twice.
     AlreadyAddedToWaitSet,
+    /// A request sent by a client did not receive a response.
+    RequestFailed {
+        /// The reason why no response was received.
+        err: ClientError,
+    },
+    /// Command line arguments contain unknown or invalid ROS arguments.
+    InvalidRosArgs {
+        /// Every unknown or invalid ROS argument, in the order of the arguments.
+        errors: Vec<RosArgsError>,
+    },
+    /// The sub-namespace of a [`SubNode`][1] is empty or not relative.
+    ///
+    /// [1]: crate::SubNode
+    InvalidSubNamespace {
+        /// The invalid sub-namespace.
+        sub_namespace: String,
+    },
+    /// A parameter could not be declared or set.
+    InvalidParameter {
+        /// The name of the parameter.
+        name: String,
+        /// Why the parameter is invalid.
+        reason: String,
+    },
+    /// A message has fields that violate their bounds.
+    ///
+    /// This happens when a message with too long strings is sent, or when a received message
+    /// doesn't fit into the idiomatic message type.
+    InvalidMessage {
+        /// Every field that violates its bound.
+        err: rosidl_runtime_rs::ValidationError,
+    },
 }
 
 impl Display for RclrsError {
@@ -46,10 +79,64 @@ impl Display for RclrsError {
                     "Could not add entity to wait set because it was already added to a wait set"
                 )
             }
+            RclrsError::RequestFailed { .. } => {
+                write!(f, "Did not receive a response to the service request")
+            }
+            RclrsError::InvalidRosArgs { errors } => {
+                for (i, error) in errors.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", error)?;
+                }
+                Ok(())
+            }
+            RclrsError::InvalidSubNamespace { sub_namespace } => {
+                write!(
+                    f,
+                    "The sub-namespace '{}' must be a non-empty relative namespace",
+                    sub_namespace
+                )
+            }
+            RclrsError::InvalidParameter { name, reason } => {
+                write!(f, "Invalid parameter '{}': {}", name, reason)
+            }
+            RclrsError::InvalidMessage { .. } => {
+                write!(f, "The message has fields that violate their bounds")
+            }
         }
     }
 }
 
+/// The reason why a client request did not receive a response.
+///
+/// This is returned as part of [`RclrsError::RequestFailed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClientError {
+    /// The request timed out before a response was received.
+    Timeout,
+    /// The request was discarded before a response was received.
+    ///
+    /// This happens e.g. when the pending request is pruned or the client is dropped.
+    ServiceUnavailable,
+}
+
+impl Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientError::Timeout => write!(f, "The request timed out"),
+            ClientError::ServiceUnavailable => {
+                write!(
+                    f,
+                    "The request was discarded before a response was received"
+                )
+            }
+        }
+    }
+}
+
+impl Error for ClientError {}
+
 /// Struct encapsulating an error message from the rcl layer or below.
 ///
 /// This struct is intended to be returned by the `source` method in the implementation of the
@@ -78,6 +165,11 @@ impl Error for RclrsError {
             RclrsError::UnknownRclError { msg, .. } => msg.as_ref().map(|e| e as &dyn Error),
             RclrsError::StringContainsNul { err, .. } => Some(err).map(|e| e as &dyn Error),
             RclrsError::AlreadyAddedToWaitSet => None,
+            RclrsError::RequestFailed { err } => Some(err).map(|e| e as &dyn Error),
+            RclrsError::InvalidRosArgs { errors } => errors.first().map(|e| e as &dyn Error),
+            RclrsError::InvalidSubNamespace { .. } => None,
+            RclrsError::InvalidParameter { .. } => None,
+            RclrsError::InvalidMessage { err } => Some(err).map(|e| e as &dyn Error),
         }
     }
 }
@@ -318,6 +410,16 @@ pub(crate) fn to_rclrs_result(ret: i32) -> Result<(), RclrsError> {
     if ret == 0 {
         return Ok(());
     }
+    let msg = take_error_message().map(RclErrorMsg);
+    // Finally, try to parse it into a return code.
+    Err(match RclReturnCode::try_from(ret) {
+        Ok(code) => RclrsError::RclError { code, msg },
+        Err(code) => RclrsError::UnknownRclError { code, msg },
+    })
+}
+
+/// Returns the error message set in the `rcl` layer or below, if any, and resets it.
+pub(crate) fn take_error_message() -> Option<String> {
     let mut msg = None;
     // SAFETY: No preconditions for this function.
     let error_state_ptr = unsafe { rcutils_get_error_state() };
@@ -330,15 +432,11 @@ pub(crate) fn to_rclrs_result(ret: i32) -> Result<(), RclrsError> {
         let s = unsafe { CStr::from_ptr(msg_ptr) }
             .to_string_lossy()
             .into_owned();
-        msg = Some(RclErrorMsg(s));
+        msg = Some(s);
     }
     // SAFETY: No preconditions for this function.
     unsafe { rcutils_reset_error() };
-    // Finally, try to parse it into a return code.
-    Err(match RclReturnCode::try_from(ret) {
-        Ok(code) => RclrsError::RclError { code, msg },
-        Err(code) => RclrsError::UnknownRclError { code, msg },
-    })
+    msg
 }
 
 pub(crate) trait ToResult {
@@ -0,0 +1,241 @@
+use std::os::raw::c_void;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::error::{RclReturnCode, ToResult};
+use crate::{rcl_bindings::*, RclrsError, SubscriptionHandle};
+
+// SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
+// they are running in. Therefore, this type can be safely sent to another thread.
+unsafe impl Send for rcl_event_t {}
+
+/// Internal struct used by events.
+pub struct EventHandle {
+    rcl_event_mtx: Mutex<rcl_event_t>,
+    // The event refers to the subscription, so the subscription must outlive the event.
+    _subscription_handle: Arc<SubscriptionHandle>,
+    pub(crate) in_use_by_wait_set: Arc<AtomicBool>,
+}
+
+impl EventHandle {
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_event_t> {
+        self.rcl_event_mtx.lock().unwrap()
+    }
+}
+
+impl Drop for EventHandle {
+    fn drop(&mut self) {
+        let rcl_event = self.rcl_event_mtx.get_mut().unwrap();
+        // SAFETY: No preconditions for this function (besides the arguments being valid).
+        unsafe {
+            rcl_event_fini(rcl_event);
+        }
+    }
+}
+
+/// Trait to be implemented by concrete [`Event`]s.
+pub trait EventBase: Send + Sync {
+    /// Internal function to get a reference to the `rcl` handle.
+    fn handle(&self) -> &EventHandle;
+    /// Tries to take the event status and run the callback with it.
+    fn execute(&self) -> Result<(), RclrsError>;
+}
+
+/// The kinds of QoS events a subscription can be notified about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubscriptionEventType {
+    /// A message was not received within the deadline of the QoS profile.
+    RequestedDeadlineMissed,
+    /// A publisher on the topic became alive or not alive.
+    LivelinessChanged,
+    /// A message was lost, e.g. because the history was full.
+    MessageLost,
+}
+
+impl From<SubscriptionEventType> for rcl_subscription_event_type_t {
+    fn from(event_type: SubscriptionEventType) -> Self {
+        match event_type {
+            SubscriptionEventType::RequestedDeadlineMissed => {
+                rcl_subscription_event_type_t::RCL_SUBSCRIPTION_REQUESTED_DEADLINE_MISSED
+            }
+            SubscriptionEventType::LivelinessChanged => {
+                rcl_subscription_event_type_t::RCL_SUBSCRIPTION_LIVELINESS_CHANGED
+            }
+            SubscriptionEventType::MessageLost => {
+                rcl_subscription_event_type_t::RCL_SUBSCRIPTION_MESSAGE_LOST
+            }
+        }
+    }
+}
+
+/// The status passed to the callback of an [`Event`].
+///
+/// The variant corresponds to the [`SubscriptionEventType`] the event was created for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EventStatus {
+    /// See [`SubscriptionEventType::RequestedDeadlineMissed`].
+    RequestedDeadlineMissed {
+        /// The number of missed deadlines since the subscription was created.
+        total_count: i32,
+        /// The number of missed deadlines since the last time the status was taken.
+        total_count_change: i32,
+    },
+    /// See [`SubscriptionEventType::LivelinessChanged`].
+    LivelinessChanged {
+        /// The number of publishers that are currently alive.
+        alive_count: i32,
+        /// The number of publishers that are currently not alive.
+        not_alive_count: i32,
+        /// The change of `alive_count` since the last time the status was taken.
+        alive_count_change: i32,
+        /// The change of `not_alive_count` since the last time the status was taken.
+        not_alive_count_change: i32,
+    },
+    /// See [`SubscriptionEventType::MessageLost`].
+    MessageLost {
+        /// The number of lost messages since the subscription was created.
+        total_count: usize,
+        /// The number of lost messages since the last time the status was taken.
+        total_count_change: usize,
+    },
+}
+
+/// Struct for receiving QoS events of a subscription.
+///
+/// Receiving events requires calling [`spin_once`][1] or [`spin`][2] on the event's node.
+///
+/// The only available way to instantiate events is via
+/// [`Node::create_subscription_event()`][3], this is to ensure that [`Node`][4]s can track all
+/// the events that have been created.
+///
+/// [1]: crate::spin_once
+/// [2]: crate::spin
+/// [3]: crate::Node::create_subscription_event
+/// [4]: crate::Node
+pub struct Event {
+    pub(crate) handle: Arc<EventHandle>,
+    event_type: SubscriptionEventType,
+    callback: Mutex<Box<dyn FnMut(EventStatus) + Send>>,
+}
+
+impl EventBase for Event {
+    fn handle(&self) -> &EventHandle {
+        &self.handle
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        let status = match self.take_status() {
+            Ok(status) => status,
+            Err(RclrsError::RclError {
+                code: RclReturnCode::EventTakeFailed,
+                ..
+            }) => {
+                // Spurious wakeup – this may happen even when a waitset indicated that this
+                // event was ready, so it shouldn't be an error.
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        (*self.callback.lock().unwrap())(status);
+        Ok(())
+    }
+}
+
+impl Event {
+    /// Creates a new event for the given subscription.
+    pub(crate) fn new<F>(
+        subscription_handle: Arc<SubscriptionHandle>,
+        event_type: SubscriptionEventType,
+        callback: F,
+    ) -> Result<Self, RclrsError>
+    // This uses pub(crate) visibility to avoid instantiating this struct outside
+    // [`Node::create_subscription_event`], see the struct's documentation for the rationale
+    where
+        F: FnMut(EventStatus) + 'static + Send,
+    {
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut rcl_event = unsafe { rcl_get_zero_initialized_event() };
+        unsafe {
+            // SAFETY: The rcl_event is zero-initialized as expected by this function.
+            // The subscription is kept alive because it is co-owned by the event.
+            rcl_subscription_event_init(
+                &mut rcl_event,
+                &*subscription_handle.lock(),
+                event_type.into(),
+            )
+            .ok()?;
+        }
+
+        let handle = Arc::new(EventHandle {
+            rcl_event_mtx: Mutex::new(rcl_event),
+            _subscription_handle: subscription_handle,
+            in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
+        });
+
+        Ok(Self {
+            handle,
+            event_type,
+            callback: Mutex::new(Box::new(callback)),
+        })
+    }
+
+    /// Returns the kind of event this is.
+    pub fn event_type(&self) -> SubscriptionEventType {
+        self.event_type
+    }
+
+    /// Takes the current status of the event.
+    ///
+    /// When the event has not occurred since the status was last taken, this will return an
+    /// [`EventTakeFailed`][1].
+    ///
+    /// [1]: crate::RclReturnCode
+    pub fn take_status(&self) -> Result<EventStatus, RclrsError> {
+        Ok(match self.event_type {
+            SubscriptionEventType::RequestedDeadlineMissed => {
+                let mut status = rmw_requested_deadline_missed_status_t {
+                    total_count: 0,
+                    total_count_change: 0,
+                };
+                self.take_inner(&mut status as *mut _ as *mut c_void)?;
+                EventStatus::RequestedDeadlineMissed {
+                    total_count: status.total_count,
+                    total_count_change: status.total_count_change,
+                }
+            }
+            SubscriptionEventType::LivelinessChanged => {
+                let mut status = rmw_liveliness_changed_status_t {
+                    alive_count: 0,
+                    not_alive_count: 0,
+                    alive_count_change: 0,
+                    not_alive_count_change: 0,
+                };
+                self.take_inner(&mut status as *mut _ as *mut c_void)?;
+                EventStatus::LivelinessChanged {
+                    alive_count: status.alive_count,
+                    not_alive_count: status.not_alive_count,
+                    alive_count_change: status.alive_count_change,
+                    not_alive_count_change: status.not_alive_count_change,
+                }
+            }
+            SubscriptionEventType::MessageLost => {
+                let mut status = rmw_message_lost_status_t {
+                    total_count: 0,
+                    total_count_change: 0,
+                };
+                self.take_inner(&mut status as *mut _ as *mut c_void)?;
+                EventStatus::MessageLost {
+                    total_count: status.total_count,
+                    total_count_change: status.total_count_change,
+                }
+            }
+        })
+    }
+
+    // Inner function, to be used for all event types.
+    fn take_inner(&self, event_info: *mut c_void) -> Result<(), RclrsError> {
+        // SAFETY: The event is valid, and the caller passes a pointer to the status struct that
+        // matches the event type.
+        unsafe { rcl_take_event(&*self.handle.lock(), event_info) }.ok()
+    }
+}
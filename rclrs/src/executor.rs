@@ -1,11 +1,15 @@
 use crate::rcl_bindings::rcl_context_is_valid;
-use crate::{Node, RclReturnCode, RclrsError, WaitSet};
+use crate::{Node, RclReturnCode, RclrsError, ReadyEntities, WaitSet};
 use std::sync::{Arc, Mutex, Weak};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 /// Single-threaded executor implementation.
 pub struct SingleThreadedExecutor {
     nodes_mtx: Mutex<Vec<Weak<Node>>>,
+    // The wait set and the lists of ready entities are reused across calls to spin_once(), so
+    // that spinning does not allocate. They are taken out of their mutexes while in use.
+    wait_set_mtx: Mutex<Option<WaitSet>>,
+    ready_entities_mtx: Mutex<ReadyEntities>,
 }
 
 impl Default for SingleThreadedExecutor {
@@ -19,6 +23,8 @@ impl SingleThreadedExecutor {
     pub fn new() -> Self {
         SingleThreadedExecutor {
             nodes_mtx: Mutex::new(Vec::new()),
+            wait_set_mtx: Mutex::new(None),
+            ready_entities_mtx: Mutex::new(ReadyEntities::default()),
         }
     }
 
@@ -37,25 +43,97 @@ impl SingleThreadedExecutor {
 
     /// Polls the nodes for new messages and executes the corresponding callbacks.
     ///
-    /// This function additionally checks that the context is still valid.
+    /// This function additionally checks that the context is still valid, and completes client
+    /// requests whose timeout has elapsed.
+    ///
+    /// The entities of a node are only in the executor's wait set while waiting, not while their
+    /// callbacks are executed.
     pub fn spin_once(&self, timeout: Option<Duration>) -> Result<(), RclrsError> {
+        let mut wait_set = self.wait_set_mtx.lock().unwrap().take();
+        let mut ready_entities = std::mem::take(&mut *self.ready_entities_mtx.lock().unwrap());
+        let result = self.spin_once_with(&mut wait_set, &mut ready_entities, timeout);
+        // Release the entities, so that they can be added to other wait sets
+        if let Some(wait_set) = &mut wait_set {
+            wait_set.clear();
+        }
+        ready_entities.clear();
+        *self.wait_set_mtx.lock().unwrap() = wait_set;
+        *self.ready_entities_mtx.lock().unwrap() = ready_entities;
+        result
+    }
+
+    fn spin_once_with(
+        &self,
+        wait_set: &mut Option<WaitSet>,
+        ready_entities: &mut ReadyEntities,
+        timeout: Option<Duration>,
+    ) -> Result<(), RclrsError> {
         for node in { self.nodes_mtx.lock().unwrap() }
             .iter()
             .filter_map(Weak::upgrade)
             .filter(|node| unsafe { rcl_context_is_valid(&*node.rcl_context_mtx.lock().unwrap()) })
         {
-            let wait_set = WaitSet::new_for_node(&node)?;
-            let ready_entities = wait_set.wait(timeout)?;
+            // The wait set can only be reused for nodes in the same context
+            if !matches!(wait_set, Some(wait_set) if wait_set.has_context(&node.rcl_context_mtx)) {
+                *wait_set = Some(WaitSet::new_with_rcl_context(
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                    &node.rcl_context_mtx,
+                )?);
+            }
+            let wait_set = wait_set.as_mut().unwrap();
+            wait_set.rebuild_for_node(&node)?;
+
+            // Wake up in time to time out pending client requests
+            let wait_timeout = match wait_set
+                .clients()
+                .filter_map(|client| client.next_request_deadline())
+                .min()
+            {
+                Some(deadline) => {
+                    let until_deadline = deadline.saturating_duration_since(Instant::now());
+                    Some(timeout.map_or(until_deadline, |timeout| timeout.min(until_deadline)))
+                }
+                None => timeout,
+            };
 
-            for ready_subscription in ready_entities.subscriptions {
+            let wait_result = wait_set.wait_for_ready_indices(wait_timeout).map(|_| ());
+            for client in wait_set.clients() {
+                client.prune_timed_out_requests();
+            }
+            match wait_result {
+                // Only waking up for a request timeout is not an error
+                Err(RclrsError::RclError {
+                    code: RclReturnCode::Timeout,
+                    ..
+                }) if wait_timeout != timeout => continue,
+                result => result?,
+            }
+            wait_set.fill_ready_entities(ready_entities);
+            // Callbacks may use the entities in other wait sets, e.g. in Client::call()
+            wait_set.clear();
+
+            for ready_timer in ready_entities.timers.drain(..) {
+                ready_timer.execute()?;
+            }
+
+            for ready_subscription in ready_entities.subscriptions.drain(..) {
                 ready_subscription.execute()?;
             }
 
-            for ready_client in ready_entities.clients {
+            for ready_event in ready_entities.events.drain(..) {
+                ready_event.execute()?;
+            }
+
+            for ready_client in ready_entities.clients.drain(..) {
                 ready_client.execute()?;
             }
 
-            for ready_service in ready_entities.services {
+            for ready_service in ready_entities.services.drain(..) {
                 ready_service.execute()?;
             }
         }
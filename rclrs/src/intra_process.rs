@@ -0,0 +1,254 @@
+use std::any::{Any, TypeId};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, Weak};
+use std::time::SystemTime;
+
+use rosidl_runtime_rs::Message;
+
+use crate::{
+    GuardCondition, MessageInfo, PublisherGid, QoSDurabilityPolicy, QoSHistoryPolicy, QoSProfile,
+    QoSReliabilityPolicy, RclrsError,
+};
+
+/// Keeps track of the publishers and subscriptions in a context that use intra-process
+/// communication.
+///
+/// It is shared by all nodes created from the same [`Context`][1].
+///
+/// [1]: crate::Context
+#[derive(Default)]
+pub(crate) struct IntraProcessManager {
+    subscriptions_mtx: Mutex<Vec<IntraProcessSubscriptionEntry>>,
+    // Held for reading while a message is published, and for writing while a subscription
+    // registers
+    registration_lock: RwLock<()>,
+}
+
+/// The properties of a publisher or subscription that determine whether a message can be
+/// delivered intra-process.
+#[derive(Clone, Debug)]
+pub(crate) struct Endpoint {
+    /// The topic name after remapping.
+    pub(crate) topic: String,
+    /// The Rust type of the messages.
+    pub(crate) type_id: TypeId,
+    pub(crate) qos: QoSProfile,
+}
+
+/// The intra-process state of a publisher.
+pub(crate) struct IntraProcessPublisher {
+    endpoint: Endpoint,
+    gid: PublisherGid,
+    sequence_number: AtomicU64,
+    manager: Arc<IntraProcessManager>,
+}
+
+/// The intra-process state of a subscription, containing the messages that were delivered to it.
+pub(crate) struct IntraProcessSubscription<T> {
+    endpoint: Endpoint,
+    // None for the KeepAll history policy
+    depth: Option<usize>,
+    queue_mtx: Mutex<IntraProcessQueue<T>>,
+    // Wakes up the wait set of the subscription's node when a message is delivered
+    guard_condition: Arc<GuardCondition>,
+    // The publishers that deliver their messages to this subscription
+    paired_publishers_mtx: Mutex<Vec<PublisherGid>>,
+}
+
+struct IntraProcessQueue<T> {
+    messages: VecDeque<(Arc<T>, MessageInfo)>,
+    reception_sequence_number: u64,
+}
+
+struct IntraProcessSubscriptionEntry {
+    endpoint: Endpoint,
+    // The IntraProcessSubscription<T> of the subscription
+    subscription: Weak<dyn Any + Send + Sync>,
+}
+
+impl Endpoint {
+    /// Returns whether messages from a publisher with this endpoint are delivered intra-process
+    /// to a subscription with the given endpoint.
+    ///
+    /// Transient local durability requires the middleware to store old messages for late-joining
+    /// subscriptions, so such publishers and subscriptions always communicate through the
+    /// middleware. Incompatible reliability policies don't communicate at all, as in the
+    /// middleware.
+    fn delivers_to(&self, subscription: &Endpoint) -> bool {
+        self.topic == subscription.topic
+            && self.type_id == subscription.type_id
+            && self.qos.durability != QoSDurabilityPolicy::TransientLocal
+            && subscription.qos.durability != QoSDurabilityPolicy::TransientLocal
+            && !(self.qos.reliability == QoSReliabilityPolicy::BestEffort
+                && subscription.qos.reliability == QoSReliabilityPolicy::Reliable)
+    }
+}
+
+impl IntraProcessManager {
+    fn live_subscriptions<T: Message>(
+        &self,
+        publisher: &Endpoint,
+    ) -> Vec<Arc<IntraProcessSubscription<T>>> {
+        let mut subscriptions = self.subscriptions_mtx.lock().unwrap();
+        subscriptions.retain(|entry| entry.subscription.strong_count() > 0);
+        subscriptions
+            .iter()
+            .filter(|entry| publisher.delivers_to(&entry.endpoint))
+            .filter_map(|entry| entry.subscription.upgrade())
+            .filter_map(|subscription| subscription.downcast().ok())
+            .collect()
+    }
+}
+
+impl IntraProcessPublisher {
+    /// Creates the intra-process state of a publisher in the manager's context.
+    pub(crate) fn new(
+        manager: &Arc<IntraProcessManager>,
+        endpoint: Endpoint,
+        gid: PublisherGid,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            endpoint,
+            gid,
+            sequence_number: AtomicU64::new(0),
+            manager: Arc::clone(manager),
+        })
+    }
+
+    /// Prevents subscriptions from registering until the returned guard is dropped.
+    ///
+    /// This must be held from calling [`IntraProcessPublisher::subscriptions`] until the message
+    /// is published to the middleware, see [`IntraProcessSubscription::is_duplicate`].
+    pub(crate) fn block_registrations(&self) -> RwLockReadGuard<'_, ()> {
+        self.manager.registration_lock.read().unwrap()
+    }
+
+    /// Returns the subscriptions that messages of this publisher are delivered to.
+    ///
+    /// The subscriptions will ignore the messages of this publisher that they take from the
+    /// middleware.
+    pub(crate) fn subscriptions<T: Message>(&self) -> Vec<Arc<IntraProcessSubscription<T>>> {
+        let subscriptions = self.manager.live_subscriptions::<T>(&self.endpoint);
+        for subscription in &subscriptions {
+            let mut paired_publishers = subscription.paired_publishers_mtx.lock().unwrap();
+            if !paired_publishers.contains(&self.gid) {
+                paired_publishers.push(self.gid.clone());
+            }
+        }
+        subscriptions
+    }
+
+    /// Delivers a message to the given subscriptions, which must have been returned by
+    /// [`IntraProcessPublisher::subscriptions`].
+    ///
+    /// The message is shared by all subscriptions and only cloned when a subscription callback
+    /// takes the message by value while other subscriptions still hold it.
+    pub(crate) fn deliver<T: Message>(
+        &self,
+        message: Arc<T>,
+        subscriptions: &[Arc<IntraProcessSubscription<T>>],
+    ) -> Result<(), RclrsError> {
+        let publication_sequence_number = self.sequence_number.fetch_add(1, Ordering::Relaxed) + 1;
+        let source_timestamp = SystemTime::now();
+        for subscription in subscriptions {
+            subscription.push(
+                Arc::clone(&message),
+                source_timestamp,
+                publication_sequence_number,
+                self.gid.clone(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Message> IntraProcessSubscription<T> {
+    /// Registers a subscription with the manager.
+    ///
+    /// This must be called before the subscription is created in the middleware, see
+    /// [`IntraProcessSubscription::is_duplicate`]. The subscription is unregistered when the
+    /// returned value is dropped.
+    pub(crate) fn new(
+        manager: &Arc<IntraProcessManager>,
+        endpoint: Endpoint,
+        guard_condition: Arc<GuardCondition>,
+    ) -> Arc<Self> {
+        let depth = match endpoint.qos.history {
+            QoSHistoryPolicy::KeepLast { depth } | QoSHistoryPolicy::SystemDefault { depth } => {
+                Some((depth as usize).max(1))
+            }
+            QoSHistoryPolicy::KeepAll => None,
+        };
+        let subscription = Arc::new(Self {
+            endpoint: endpoint.clone(),
+            depth,
+            queue_mtx: Mutex::new(IntraProcessQueue {
+                messages: VecDeque::new(),
+                reception_sequence_number: 0,
+            }),
+            guard_condition,
+            paired_publishers_mtx: Mutex::new(Vec::new()),
+        });
+        let _registration = manager.registration_lock.write().unwrap();
+        let mut subscriptions = manager.subscriptions_mtx.lock().unwrap();
+        subscriptions.retain(|entry| entry.subscription.strong_count() > 0);
+        subscriptions.push(IntraProcessSubscriptionEntry {
+            endpoint,
+            subscription: Arc::downgrade(&subscription) as Weak<dyn Any + Send + Sync>,
+        });
+        subscription
+    }
+
+    /// Returns whether a message taken from the middleware was sent by a publisher that also
+    /// delivered it to this subscription intra-process.
+    ///
+    /// Such messages are received twice, and the copy from the middleware should be ignored.
+    ///
+    /// The subscription is registered before it exists in the middleware, and publishers don't let
+    /// subscriptions register while they publish a message. So every message that the middleware
+    /// delivers to this subscription was published after the registration, and it was delivered
+    /// intra-process if its publisher is paired with this subscription. Pairings are kept after a
+    /// publisher is dropped, since its messages may still be waiting in the middleware.
+    pub(crate) fn is_duplicate(&self, message_info: &MessageInfo) -> bool {
+        self.paired_publishers_mtx
+            .lock()
+            .unwrap()
+            .contains(&message_info.publisher_gid)
+    }
+
+    /// Returns whether there are messages that have not been taken yet.
+    pub(crate) fn has_messages(&self) -> bool {
+        !self.queue_mtx.lock().unwrap().messages.is_empty()
+    }
+
+    /// Takes the oldest message, if any.
+    pub(crate) fn pop(&self) -> Option<(Arc<T>, MessageInfo)> {
+        self.queue_mtx.lock().unwrap().messages.pop_front()
+    }
+
+    fn push(
+        &self,
+        message: Arc<T>,
+        source_timestamp: SystemTime,
+        publication_sequence_number: u64,
+        publisher_gid: PublisherGid,
+    ) -> Result<(), RclrsError> {
+        {
+            let mut queue = self.queue_mtx.lock().unwrap();
+            if Some(queue.messages.len()) == self.depth {
+                queue.messages.pop_front();
+            }
+            queue.reception_sequence_number += 1;
+            let message_info = MessageInfo {
+                source_timestamp: Some(source_timestamp),
+                received_timestamp: Some(SystemTime::now()),
+                publication_sequence_number,
+                reception_sequence_number: queue.reception_sequence_number,
+                publisher_gid,
+            };
+            queue.messages.push_back((message, message_info));
+        }
+        self.guard_condition.trigger()
+    }
+}
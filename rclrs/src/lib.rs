@@ -7,15 +7,21 @@
 
 mod arguments;
 mod client;
+mod clock;
+mod component;
 mod context;
 mod error;
+mod event;
 mod executor;
+mod intra_process;
 mod node;
 mod parameter;
 mod publisher;
 mod qos;
+mod serialization;
 mod service;
 mod subscription;
+mod timer;
 mod vendor;
 mod wait;
 
@@ -29,16 +35,21 @@ use std::time::Duration;
 
 pub use arguments::*;
 pub use client::*;
+pub use component::*;
 pub use context::*;
 pub use error::*;
+pub use event::*;
 pub use executor::*;
 pub use node::*;
 pub use parameter::*;
 pub use publisher::*;
 pub use qos::*;
 pub use rcl_bindings::rmw_request_id_t;
+pub use rclrs_macros::Parameters;
+pub use serialization::*;
 pub use service::*;
 pub use subscription::*;
+pub use timer::*;
 pub use wait::*;
 
 /// Polls the node for new messages and executes the corresponding callbacks.
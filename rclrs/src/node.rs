@@ -1,21 +1,30 @@
 mod builder;
 mod graph;
+mod sub_node;
 use std::cmp::PartialEq;
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 use std::fmt;
-use std::os::raw::c_char;
+use std::os::raw::{c_char, c_void};
 use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, SystemTime};
 use std::vec::Vec;
 
 use rosidl_runtime_rs::Message;
 
 pub use self::builder::*;
 pub use self::graph::*;
+pub use self::sub_node::*;
+use crate::intra_process::IntraProcessManager;
 use crate::rcl_bindings::*;
+use crate::subscription::IntraProcessOptions;
+use crate::vendor::statistics_msgs::msg::MetricsMessage;
 use crate::{
-    Client, ClientBase, Context, GuardCondition, ParameterOverrideMap, Publisher, QoSProfile,
-    RclrsError, Service, ServiceBase, Subscription, SubscriptionBase, SubscriptionCallback,
-    ToResult,
+    parameters_to_yaml, AnyServiceCallback, Client, ClientBase, Context, Event, EventBase,
+    EventStatus, GuardCondition, ParameterOverrideMap, ParameterRegistry, ParameterService,
+    ParameterValue, Parameters, Publisher, QoSProfile, RclrsError, Service, ServiceBase,
+    ServiceResponder, StatisticsCollector, Subscription, SubscriptionBase, SubscriptionCallback,
+    SubscriptionEventType, Timer, TimerBase, ToResult, TopicStatistics, TopicStatisticsOptions,
+    TypedParameters, QOS_PROFILE_SERVICES_DEFAULT,
 };
 
 impl Drop for rcl_node_t {
@@ -71,7 +80,14 @@ pub struct Node {
     pub(crate) guard_conditions_mtx: Mutex<Vec<Weak<GuardCondition>>>,
     pub(crate) services_mtx: Mutex<Vec<Weak<dyn ServiceBase>>>,
     pub(crate) subscriptions_mtx: Mutex<Vec<Weak<dyn SubscriptionBase>>>,
-    _parameter_map: ParameterOverrideMap,
+    pub(crate) timers_mtx: Mutex<Vec<Weak<dyn TimerBase>>>,
+    pub(crate) events_mtx: Mutex<Vec<Weak<dyn EventBase>>>,
+    // Only set when the node uses intra-process communication
+    pub(crate) intra_process_manager: Option<Arc<IntraProcessManager>>,
+    pub(crate) parameter_map: ParameterOverrideMap,
+    pub(crate) parameter_registry: Arc<Mutex<ParameterRegistry>>,
+    // Created when the first parameters are declared
+    pub(crate) parameter_service: Mutex<Option<ParameterService>>,
 }
 
 impl Eq for Node {}
@@ -176,15 +192,97 @@ impl Node {
         unsafe { call_string_getter_with_handle(&self.rcl_node_mtx.lock().unwrap(), getter) }
     }
 
-    /// Creates a [`Client`][1].
+    /// Returns the fully qualified name of a topic, as it would be used by a publisher or
+    /// subscription created from this node.
+    ///
+    /// The name is expanded with the namespace and name of the node, and then remapped with the
+    /// remapping rules from the command line arguments.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError};
+    /// let remapping = ["--ros-args", "-r", "chatter:=talk"].map(String::from);
+    /// let context = Context::new(remapping)?;
+    /// let node = rclrs::create_node_builder(&context, "my_node")
+    ///     .namespace("/my_ns")
+    ///     .build()?;
+    /// assert_eq!(node.resolve_topic_name("chatter")?, "/my_ns/talk");
+    /// assert_eq!(node.resolve_topic_name("~/status")?, "/my_ns/my_node/status");
+    /// assert_eq!(node.resolve_topic_name("/clock")?, "/clock");
+    /// assert!(node.resolve_topic_name("invalid name").is_err());
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn resolve_topic_name(&self, topic: &str) -> Result<String, RclrsError> {
+        self.resolve_name(topic, false)
+    }
+
+    /// Returns the fully qualified name of a service, as it would be used by a client or service
+    /// created from this node.
+    ///
+    /// See [`Node::resolve_topic_name()`] for details.
+    pub fn resolve_service_name(&self, service: &str) -> Result<String, RclrsError> {
+        self.resolve_name(service, true)
+    }
+
+    // Helper for resolve_topic_name() and resolve_service_name()
+    fn resolve_name(&self, name: &str, is_service: bool) -> Result<String, RclrsError> {
+        let name_c_string = CString::new(name).map_err(|err| RclrsError::StringContainsNul {
+            err,
+            s: name.into(),
+        })?;
+        let rcl_node = &*self.rcl_node_mtx.lock().unwrap();
+        let mut output_name: *mut c_char = std::ptr::null_mut();
+        unsafe {
+            // SAFETY: Getting a default value is always safe.
+            let allocator = rcutils_get_default_allocator();
+            // SAFETY: The node is valid, and the output_name is an output parameter, so it is
+            // expected that it contains null. It is freed below.
+            rcl_node_resolve_name(
+                rcl_node,
+                name_c_string.as_ptr(),
+                allocator,
+                is_service,
+                false,
+                &mut output_name,
+            )
+            .ok()?;
+            // SAFETY: On success, output_name is a valid nul-terminated string, which is copied
+            // before it is freed.
+            let resolved_name = CStr::from_ptr(output_name).to_string_lossy().into_owned();
+            // SAFETY: output_name was allocated with the given allocator.
+            allocator.deallocate.unwrap()(output_name as *mut c_void, allocator.state);
+            Ok(resolved_name)
+        }
+    }
+
+    /// Creates a [`Client`][1] with the [`QOS_PROFILE_SERVICES_DEFAULT`] QoS profile.
     ///
     /// [1]: crate::Client
-    // TODO: make client's lifetime depend on node's lifetime
     pub fn create_client<T>(&self, topic: &str) -> Result<Arc<Client<T>>, RclrsError>
     where
         T: rosidl_runtime_rs::Service,
     {
-        let client = Arc::new(Client::<T>::new(Arc::clone(&self.rcl_node_mtx), topic)?);
+        self.create_client_with_qos(topic, QOS_PROFILE_SERVICES_DEFAULT)
+    }
+
+    /// Creates a [`Client`][1] with the given QoS profile.
+    ///
+    /// [1]: crate::Client
+    // TODO: make client's lifetime depend on node's lifetime
+    pub fn create_client_with_qos<T>(
+        &self,
+        topic: &str,
+        qos: QoSProfile,
+    ) -> Result<Arc<Client<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+    {
+        let client = Arc::new(Client::<T>::new(
+            Arc::clone(&self.rcl_node_mtx),
+            Arc::clone(&self.rcl_context_mtx),
+            topic,
+            qos,
+        )?);
         { self.clients_mtx.lock().unwrap() }.push(Arc::downgrade(&client) as Weak<dyn ClientBase>);
         Ok(client)
     }
@@ -242,18 +340,16 @@ impl Node {
     where
         T: Message,
     {
-        let publisher = Arc::new(Publisher::<T>::new(
-            Arc::clone(&self.rcl_node_mtx),
-            topic,
-            qos,
-        )?);
-        Ok(publisher)
+        let mut publisher = Publisher::<T>::new(Arc::clone(&self.rcl_node_mtx), topic, qos)?;
+        if let Some(intra_process_manager) = &self.intra_process_manager {
+            publisher.enable_intra_process(intra_process_manager)?;
+        }
+        Ok(Arc::new(publisher))
     }
 
-    /// Creates a [`Service`][1].
+    /// Creates a [`Service`][1] with the [`QOS_PROFILE_SERVICES_DEFAULT`] QoS profile.
     ///
     /// [1]: crate::Service
-    // TODO: make service's lifetime depend on node's lifetime
     pub fn create_service<T, F>(
         &self,
         topic: &str,
@@ -262,10 +358,80 @@ impl Node {
     where
         T: rosidl_runtime_rs::Service,
         F: Fn(&rmw_request_id_t, T::Request) -> T::Response + 'static + Send,
+    {
+        self.create_service_with_qos(topic, QOS_PROFILE_SERVICES_DEFAULT, callback)
+    }
+
+    /// Creates a [`Service`][1] with the given QoS profile.
+    ///
+    /// [1]: crate::Service
+    pub fn create_service_with_qos<T, F>(
+        &self,
+        topic: &str,
+        qos: QoSProfile,
+        callback: F,
+    ) -> Result<Arc<Service<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+        F: Fn(&rmw_request_id_t, T::Request) -> T::Response + 'static + Send,
+    {
+        self.create_service_inner(topic, qos, AnyServiceCallback::Regular(Box::new(callback)))
+    }
+
+    /// Creates a [`Service`][1] that can respond to requests after its callback has returned.
+    ///
+    /// Instead of returning the response, the callback receives a [`ServiceResponder`] that can
+    /// be sent to another thread and used to send the response later. This allows the service to
+    /// e.g. call other services itself before responding, without blocking the executor.
+    ///
+    /// The service uses the [`QOS_PROFILE_SERVICES_DEFAULT`] QoS profile.
+    ///
+    /// [1]: crate::Service
+    pub fn create_deferred_service<T, F>(
+        &self,
+        topic: &str,
+        callback: F,
+    ) -> Result<Arc<Service<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+        F: Fn(T::Request, ServiceResponder<T>) + 'static + Send,
+    {
+        self.create_deferred_service_with_qos(topic, QOS_PROFILE_SERVICES_DEFAULT, callback)
+    }
+
+    /// Creates a [`Service`][1] that can respond to requests after its callback has returned,
+    /// with the given QoS profile.
+    ///
+    /// See [`Node::create_deferred_service()`] for details.
+    ///
+    /// [1]: crate::Service
+    pub fn create_deferred_service_with_qos<T, F>(
+        &self,
+        topic: &str,
+        qos: QoSProfile,
+        callback: F,
+    ) -> Result<Arc<Service<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+        F: Fn(T::Request, ServiceResponder<T>) + 'static + Send,
+    {
+        self.create_service_inner(topic, qos, AnyServiceCallback::Deferred(Box::new(callback)))
+    }
+
+    // TODO: make service's lifetime depend on node's lifetime
+    fn create_service_inner<T>(
+        &self,
+        topic: &str,
+        qos: QoSProfile,
+        callback: AnyServiceCallback<T>,
+    ) -> Result<Arc<Service<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
     {
         let service = Arc::new(Service::<T>::new(
             Arc::clone(&self.rcl_node_mtx),
             topic,
+            qos,
             callback,
         )?);
         { self.services_mtx.lock().unwrap() }
@@ -286,11 +452,21 @@ impl Node {
     where
         T: Message,
     {
+        let intra_process = match &self.intra_process_manager {
+            Some(manager) => Some(IntraProcessOptions {
+                manager,
+                topic: self.resolve_topic_name(topic)?,
+                // Wakes up the wait set when a message is delivered intra-process
+                guard_condition: self.create_guard_condition(),
+            }),
+            None => None,
+        };
         let subscription = Arc::new(Subscription::<T>::new(
             Arc::clone(&self.rcl_node_mtx),
             topic,
             qos,
             callback,
+            intra_process,
         )?);
         { self.subscriptions_mtx.lock() }
             .unwrap()
@@ -298,33 +474,72 @@ impl Node {
         Ok(subscription)
     }
 
-    /// Returns the subscriptions that have not been dropped yet.
-    pub(crate) fn live_subscriptions(&self) -> Vec<Arc<dyn SubscriptionBase>> {
-        { self.subscriptions_mtx.lock().unwrap() }
-            .iter()
-            .filter_map(Weak::upgrade)
-            .collect()
-    }
-
-    pub(crate) fn live_clients(&self) -> Vec<Arc<dyn ClientBase>> {
-        { self.clients_mtx.lock().unwrap() }
-            .iter()
-            .filter_map(Weak::upgrade)
-            .collect()
+    /// Creates a [`Timer`][1] that calls the callback with the given period.
+    ///
+    /// [1]: crate::Timer
+    pub fn create_timer<F>(&self, period: Duration, callback: F) -> Result<Arc<Timer>, RclrsError>
+    where
+        F: FnMut() + 'static + Send,
+    {
+        let timer = Arc::new(Timer::new(
+            Arc::clone(&self.rcl_context_mtx),
+            period,
+            callback,
+        )?);
+        { self.timers_mtx.lock().unwrap() }.push(Arc::downgrade(&timer) as Weak<dyn TimerBase>);
+        Ok(timer)
     }
 
-    pub(crate) fn live_guard_conditions(&self) -> Vec<Arc<GuardCondition>> {
-        { self.guard_conditions_mtx.lock().unwrap() }
-            .iter()
-            .filter_map(Weak::upgrade)
-            .collect()
+    /// Creates an [`Event`][1] that calls the callback when the given QoS event occurs for the
+    /// subscription.
+    ///
+    /// The subscription is kept alive for as long as the event exists.
+    ///
+    /// [1]: crate::Event
+    pub fn create_subscription_event<T, F>(
+        &self,
+        subscription: &Subscription<T>,
+        event_type: SubscriptionEventType,
+        callback: F,
+    ) -> Result<Arc<Event>, RclrsError>
+    where
+        T: Message,
+        F: FnMut(EventStatus) + 'static + Send,
+    {
+        let event = Arc::new(Event::new(
+            Arc::clone(&subscription.handle),
+            event_type,
+            callback,
+        )?);
+        { self.events_mtx.lock().unwrap() }.push(Arc::downgrade(&event) as Weak<dyn EventBase>);
+        Ok(event)
     }
 
-    pub(crate) fn live_services(&self) -> Vec<Arc<dyn ServiceBase>> {
-        { self.services_mtx.lock().unwrap() }
-            .iter()
-            .filter_map(Weak::upgrade)
-            .collect()
+    /// Starts publishing statistics about the messages received by the subscription.
+    ///
+    /// The statistics are published by a timer of this node for as long as the returned
+    /// [`TopicStatistics`] exists. See its documentation for the published metrics.
+    pub fn create_topic_statistics<T>(
+        &self,
+        subscription: &Subscription<T>,
+        options: TopicStatisticsOptions,
+    ) -> Result<TopicStatistics, RclrsError>
+    where
+        T: Message,
+    {
+        let collector = Arc::new(StatisticsCollector::new(self.name(), SystemTime::now()));
+        let publisher =
+            self.create_publisher::<MetricsMessage>(&options.publish_topic, options.qos)?;
+        let collector_clone = Arc::clone(&collector);
+        let timer = self.create_timer(options.publish_period, move || {
+            for metric in collector_clone.finish_window(SystemTime::now()) {
+                // There is no way to report the error from a timer callback, and the next window
+                // is published regardless
+                let _ = publisher.publish(metric);
+            }
+        })?;
+        subscription.add_statistics_collector(&collector);
+        Ok(TopicStatistics::new(collector, timer))
     }
 
     /// Returns the ROS domain ID that the node is using.
@@ -360,6 +575,80 @@ impl Node {
         domain_id
     }
 
+    /// Returns the parameters of the node as a parameter file.
+    ///
+    /// This contains the parameter overrides of the node, and the current values of the
+    /// parameters declared with [`Node::declare_parameters()`].
+    ///
+    /// The file applies to any node (`/**`), so that it can be passed to a node with a different
+    /// name through [`NodeBuilder::params_file()`] or the `--params-file` command line argument
+    /// to reproduce this node's configuration. Byte arrays and empty arrays are not supported,
+    /// since they can't be loaded from parameter files, and are only written as comments.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, ParameterValue, RclrsError};
+    /// let context = Context::new([])?;
+    /// let node = rclrs::create_node_builder(&context, "my_node")
+    ///     .parameter_overrides([(String::from("rate"), ParameterValue::Double(10.0))])
+    ///     .build()?;
+    /// assert_eq!(
+    ///     node.dump_parameters_yaml(),
+    ///     "/**:\n  ros__parameters:\n    rate: 10.0\n"
+    /// );
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn dump_parameters_yaml(&self) -> String {
+        let mut parameters = self.parameter_map.clone();
+        parameters.extend(self.parameter_registry.lock().unwrap().values());
+        parameters_to_yaml(&parameters)
+    }
+
+    /// Declares the fields of `P` as parameters of this node.
+    ///
+    /// Each parameter starts with the value of its parameter override, e.g. from the command
+    /// line or a parameter file, or with its default value otherwise. The returned handle is
+    /// updated whenever the parameters are set, e.g. with `ros2 param set` or
+    /// [`Node::set_parameter()`].
+    ///
+    /// When parameters are declared for the first time, the node starts providing the standard
+    /// parameter services, such as `~/get_parameters` and `~/set_parameters`. Setting a
+    /// parameter through these services fails with a reason in the `SetParametersResult` if the
+    /// parameter is read-only, or if the value has the wrong type or is not in the range of the
+    /// parameter.
+    ///
+    /// Returns an error if an override is not valid for its parameter, or if a parameter has
+    /// already been declared on this node.
+    ///
+    /// See [`Parameters`] for an example.
+    pub fn declare_parameters<P: Parameters>(&self) -> Result<TypedParameters<P>, RclrsError> {
+        let parameters = self
+            .parameter_registry
+            .lock()
+            .unwrap()
+            .declare::<P>(&self.parameter_map)?;
+        let mut parameter_service = self.parameter_service.lock().unwrap();
+        if parameter_service.is_none() {
+            *parameter_service = Some(ParameterService::new(self, &self.parameter_registry)?);
+        }
+        Ok(parameters)
+    }
+
+    /// Sets a parameter that was declared with [`Node::declare_parameters()`].
+    ///
+    /// Returns an error if the parameter has not been declared or is read-only, or if the value
+    /// has the wrong type or is not in the range of the parameter.
+    pub fn set_parameter(&self, name: &str, value: ParameterValue) -> Result<(), RclrsError> {
+        self.parameter_registry
+            .lock()
+            .unwrap()
+            .set(name, value)
+            .map_err(|reason| RclrsError::InvalidParameter {
+                name: name.to_owned(),
+                reason,
+            })
+    }
+
     /// Creates a [`NodeBuilder`][1] with the given name.
     ///
     /// Convenience function equivalent to [`NodeBuilder::new()`][2].
@@ -409,4 +698,38 @@ mod tests {
         assert_send::<Node>();
         assert_sync::<Node>();
     }
+
+    #[test]
+    fn test_dumped_parameters_can_be_loaded() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+
+        let overrides = [
+            ("rate", ParameterValue::Double(10.0)),
+            (
+                "frames",
+                ParameterValue::StringArray(vec![String::from("a")]),
+            ),
+            ("no_frames", ParameterValue::StringArray(vec![])),
+        ]
+        .map(|(name, value)| (String::from(name), value));
+        let context = Context::new([])?;
+        let node = Node::builder(&context, "dumping_node")
+            .parameter_overrides(overrides)
+            .build()?;
+        let yaml = node.dump_parameters_yaml();
+        let mut params_file = tempfile::NamedTempFile::new()?;
+        write!(params_file, "{}", yaml)?;
+
+        let loading_node = Node::builder(&context, "loading_node")
+            .params_file(params_file.path())
+            .build()?;
+        let mut expected = node.parameter_map.clone();
+        expected.remove("no_frames");
+        assert_eq!(loading_node.parameter_map, expected);
+        assert_eq!(
+            crate::parse_parameters_file(params_file.path())?["/**"],
+            expected
+        );
+        Ok(())
+    }
 }
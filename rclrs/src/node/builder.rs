@@ -1,9 +1,13 @@
 use std::ffi::CString;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
+use crate::arguments::check_unknown_ros_args;
+use crate::intra_process::IntraProcessManager;
 use crate::rcl_bindings::*;
 use crate::{
-    node::call_string_getter_with_handle, resolve_parameter_overrides, Context, Node, RclrsError,
+    node::call_string_getter_with_handle, resolve_parameter_overrides, Context, Node,
+    ParameterOverrideMap, ParameterRegistry, ParameterValue, RclrsError, RemapRule, RosArgs,
     ToResult,
 };
 
@@ -16,7 +20,11 @@ use crate::{
 /// - `namespace: "/"`
 /// - `use_global_arguments: true`
 /// - `arguments: []`
+/// - `remap_rules: []`
+/// - `parameter_files: []`
+/// - `parameter_overrides: {}`
 /// - `enable_rosout: true`
+/// - `use_intra_process_comms: false`
 ///
 /// # Example
 /// ```
@@ -45,7 +53,12 @@ pub struct NodeBuilder {
     namespace: String,
     use_global_arguments: bool,
     arguments: Vec<String>,
+    remap_rules: Vec<RemapRule>,
+    parameter_files: Vec<String>,
+    parameter_overrides: ParameterOverrideMap,
     enable_rosout: bool,
+    intra_process_manager: Arc<IntraProcessManager>,
+    use_intra_process_comms: bool,
 }
 
 impl NodeBuilder {
@@ -91,7 +104,12 @@ impl NodeBuilder {
             namespace: "/".to_string(),
             use_global_arguments: true,
             arguments: vec![],
+            remap_rules: vec![],
+            parameter_files: vec![],
+            parameter_overrides: ParameterOverrideMap::new(),
             enable_rosout: true,
+            intra_process_manager: Arc::clone(&context.intra_process_manager),
+            use_intra_process_comms: false,
         }
     }
 
@@ -185,6 +203,7 @@ impl NodeBuilder {
     /// These arguments are parsed the same way as those for [`Context::new()`][1].
     /// However, the node-specific command line arguments have higher precedence than the arguments
     /// used in creating the context.
+    /// Unknown or invalid ROS arguments are reported by [`NodeBuilder::build()`].
     ///
     /// For more details about command line arguments, see [here][2].
     ///    
@@ -213,6 +232,70 @@ impl NodeBuilder {
         self
     }
 
+    /// Adds a remap rule for this node.
+    ///
+    /// This is equivalent to passing `--ros-args -r from:=to` in the node-specific
+    /// [arguments][1], and can be used to e.g. rename topics, services, the node name (`__node`)
+    /// or the node namespace (`__ns`). The rules are applied after those from the arguments, so
+    /// for the same name, a rule from the arguments takes precedence.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError};
+    /// let context = Context::new([])?;
+    /// let node = rclrs::create_node_builder(&context, "my_node")
+    ///     .remap("chatter", "/robot1/chatter")
+    ///     .remap("__node", "talker")
+    ///     .build()?;
+    /// assert_eq!(node.name(), "talker");
+    /// assert_eq!(node.resolve_topic_name("chatter")?, "/robot1/chatter");
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: NodeBuilder::arguments
+    pub fn remap(mut self, from: &str, to: &str) -> Self {
+        self.remap_rules.push(RemapRule::new(from, to));
+        self
+    }
+
+    /// Adds a parameter file for this node.
+    ///
+    /// This is equivalent to passing `--ros-args --params-file <path>` in the node-specific
+    /// [arguments][1]. The file is read when the node is built, and a file that can't be read or
+    /// parsed makes [`NodeBuilder::build()`] fail. Parameters from later files override those
+    /// from earlier files.
+    ///
+    /// [1]: NodeBuilder::arguments
+    pub fn params_file(mut self, path: impl AsRef<Path>) -> Self {
+        self.parameter_files
+            .push(path.as_ref().to_string_lossy().into_owned());
+        self
+    }
+
+    /// Sets parameter overrides for this node.
+    ///
+    /// The overrides take precedence over the parameters from the command line arguments and
+    /// parameter files. Calling this function several times adds to the overrides, with later
+    /// values for the same parameter replacing earlier ones.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, ParameterValue, RclrsError};
+    /// let context = Context::new(["--ros-args", "-p", "rate:=5.0"].map(String::from))?;
+    /// let node = rclrs::create_node_builder(&context, "my_node")
+    ///     .parameter_overrides([(String::from("rate"), ParameterValue::Double(10.0))])
+    ///     .build()?;
+    /// assert!(node.dump_parameters_yaml().contains("rate: 10.0"));
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn parameter_overrides(
+        mut self,
+        overrides: impl IntoIterator<Item = (String, ParameterValue)>,
+    ) -> Self {
+        self.parameter_overrides.extend(overrides);
+        self
+    }
+
     /// Enables or disables logging to rosout.
     ///
     /// When enabled, log messages are published to the `/rosout` topic in addition to
@@ -224,6 +307,37 @@ impl NodeBuilder {
         self
     }
 
+    /// Enables or disables intra-process communication.
+    ///
+    /// When enabled, messages published by this node's publishers are passed directly to the
+    /// subscriptions of nodes in the same [`Context`] that also enable intra-process communication,
+    /// without being serialized. Owned messages are moved into an [`Arc`] that is shared by all
+    /// such subscriptions, so subscription callbacks taking an `Arc<T>` never copy the message.
+    /// Messages are still sent through the middleware when it has matched any subscriptions, and
+    /// the intra-process subscriptions ignore the copies they receive from it.
+    ///
+    /// A publisher and a subscription only communicate intra-process when they use the same
+    /// message type and neither uses the [`TransientLocal`][1] durability policy.
+    /// Subscriptions with a [`ReadOnlyLoanedMessage`][2] callback never receive messages
+    /// intra-process.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, Node, RclrsError};
+    /// let context = Context::new([])?;
+    /// let node = Node::builder(&context, "my_node")
+    ///     .use_intra_process_comms(true)
+    ///     .build()?;
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::QoSDurabilityPolicy::TransientLocal
+    /// [2]: crate::ReadOnlyLoanedMessage
+    pub fn use_intra_process_comms(mut self, enable: bool) -> Self {
+        self.use_intra_process_comms = enable;
+        self
+    }
+
     /// Builds the node instance.
     ///
     /// Node name and namespace validation is performed in this method.
@@ -262,7 +376,7 @@ impl NodeBuilder {
             .ok()?;
         };
 
-        let _parameter_map = unsafe {
+        let mut parameter_map = unsafe {
             let fqn = call_string_getter_with_handle(&rcl_node, rcl_node_get_fully_qualified_name);
             resolve_parameter_overrides(
                 &fqn,
@@ -270,6 +384,7 @@ impl NodeBuilder {
                 &rcl_context.global_arguments,
             )?
         };
+        parameter_map.extend(self.parameter_overrides.clone());
         let rcl_node_mtx = Arc::new(Mutex::new(rcl_node));
 
         Ok(Node {
@@ -279,7 +394,14 @@ impl NodeBuilder {
             guard_conditions_mtx: Mutex::new(vec![]),
             services_mtx: Mutex::new(vec![]),
             subscriptions_mtx: Mutex::new(vec![]),
-            _parameter_map,
+            timers_mtx: Mutex::new(vec![]),
+            events_mtx: Mutex::new(vec![]),
+            intra_process_manager: self
+                .use_intra_process_comms
+                .then(|| Arc::clone(&self.intra_process_manager)),
+            parameter_map,
+            parameter_registry: Arc::new(Mutex::new(ParameterRegistry::default())),
+            parameter_service: Mutex::new(None),
         })
     }
 
@@ -293,8 +415,19 @@ impl NodeBuilder {
         // SAFETY: No preconditions for this function.
         let mut rcl_node_options = unsafe { rcl_node_get_default_options() };
 
-        let cstring_args = self
+        let builder_args = RosArgs {
+            remap_rules: self.remap_rules.clone(),
+            parameter_files: self.parameter_files.clone(),
+            ..Default::default()
+        }
+        .to_args();
+        let args: Vec<String> = self
             .arguments
+            .iter()
+            .chain(&builder_args)
+            .cloned()
+            .collect();
+        let cstring_args = args
             .iter()
             .map(|s| match CString::new(s.as_str()) {
                 Ok(cstr) => Ok(cstr),
@@ -314,6 +447,8 @@ impl NodeBuilder {
             )
         }
         .ok()?;
+        // rcl ignores unknown ROS arguments, so they are checked here
+        check_unknown_ros_args(&rcl_node_options.arguments, &args)?;
 
         rcl_node_options.use_global_arguments = self.use_global_arguments;
         rcl_node_options.enable_rosout = self.enable_rosout;
@@ -0,0 +1,268 @@
+use std::sync::Arc;
+
+use rosidl_runtime_rs::Message;
+
+use crate::rcl_bindings::*;
+use crate::{
+    Client, Node, Publisher, QoSProfile, RclrsError, Service, ServiceResponder, Subscription,
+    SubscriptionCallback, QOS_PROFILE_SERVICES_DEFAULT,
+};
+
+/// A handle to a [`Node`] that creates its topics and services in a sub-namespace.
+///
+/// A sub-node shares the underlying node, so it does not show up in e.g. `ros2 node list`, and
+/// all entities created from it are spun together with the node. Only relative names are extended
+/// with the sub-namespace: absolute names (starting with `/`) and private names (starting with
+/// `~`) are used as they are.
+///
+/// Sub-nodes are created with [`Node::create_sub_node()`] and can be nested with
+/// [`SubNode::create_sub_node()`].
+///
+/// # Example
+/// ```
+/// # use rclrs::{Context, RclrsError};
+/// let context = Context::new([])?;
+/// let node = rclrs::create_node_builder(&context, "my_node")
+///     .namespace("/robot")
+///     .build()?;
+/// let camera = node.create_sub_node("camera_left")?;
+/// assert_eq!(camera.resolve_topic_name("image")?, "/robot/camera_left/image");
+/// assert_eq!(camera.resolve_topic_name("/clock")?, "/clock");
+/// let lens = camera.create_sub_node("lens")?;
+/// assert_eq!(lens.effective_namespace(), "/robot/camera_left/lens");
+/// # Ok::<(), RclrsError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct SubNode {
+    node: Arc<Node>,
+    sub_namespace: String,
+}
+
+impl Node {
+    /// Creates a [`SubNode`] that extends the namespace of topics and services with the given
+    /// sub-namespace.
+    ///
+    /// The sub-namespace must be relative, i.e. not empty and not starting with `/`. It may
+    /// consist of several tokens, e.g. `sensors/camera_left`.
+    pub fn create_sub_node(self: &Arc<Self>, sub_namespace: &str) -> Result<SubNode, RclrsError> {
+        validate_sub_namespace(sub_namespace)?;
+        Ok(SubNode {
+            node: Arc::clone(self),
+            sub_namespace: sub_namespace.to_owned(),
+        })
+    }
+}
+
+impl SubNode {
+    /// Creates a nested sub-node, whose sub-namespace is appended to this one.
+    pub fn create_sub_node(&self, sub_namespace: &str) -> Result<SubNode, RclrsError> {
+        validate_sub_namespace(sub_namespace)?;
+        Ok(SubNode {
+            node: Arc::clone(&self.node),
+            sub_namespace: format!("{}/{}", self.sub_namespace, sub_namespace),
+        })
+    }
+
+    /// Returns the node that this sub-node belongs to.
+    pub fn node(&self) -> &Arc<Node> {
+        &self.node
+    }
+
+    /// Returns the sub-namespace, relative to the namespace of the node.
+    pub fn sub_namespace(&self) -> &str {
+        &self.sub_namespace
+    }
+
+    /// Returns the namespace of the node, extended by the sub-namespace.
+    pub fn effective_namespace(&self) -> String {
+        let namespace = self.node.namespace();
+        format!("{}/{}", namespace.trim_end_matches('/'), self.sub_namespace)
+    }
+
+    /// Returns the fully qualified name of a topic, as it would be used by a publisher or
+    /// subscription created from this sub-node.
+    ///
+    /// See [`Node::resolve_topic_name()`] for details.
+    pub fn resolve_topic_name(&self, topic: &str) -> Result<String, RclrsError> {
+        self.node.resolve_topic_name(&self.extend_name(topic))
+    }
+
+    /// Returns the fully qualified name of a service, as it would be used by a client or service
+    /// created from this sub-node.
+    ///
+    /// See [`Node::resolve_service_name()`] for details.
+    pub fn resolve_service_name(&self, service: &str) -> Result<String, RclrsError> {
+        self.node.resolve_service_name(&self.extend_name(service))
+    }
+
+    /// Creates a [`Client`] in the sub-namespace, with the [`QOS_PROFILE_SERVICES_DEFAULT`] QoS
+    /// profile.
+    pub fn create_client<T>(&self, topic: &str) -> Result<Arc<Client<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+    {
+        self.create_client_with_qos(topic, QOS_PROFILE_SERVICES_DEFAULT)
+    }
+
+    /// Creates a [`Client`] in the sub-namespace, with the given QoS profile.
+    pub fn create_client_with_qos<T>(
+        &self,
+        topic: &str,
+        qos: QoSProfile,
+    ) -> Result<Arc<Client<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+    {
+        self.node
+            .create_client_with_qos(&self.extend_name(topic), qos)
+    }
+
+    /// Creates a [`Publisher`] in the sub-namespace.
+    pub fn create_publisher<T>(
+        &self,
+        topic: &str,
+        qos: QoSProfile,
+    ) -> Result<Arc<Publisher<T>>, RclrsError>
+    where
+        T: Message,
+    {
+        self.node.create_publisher(&self.extend_name(topic), qos)
+    }
+
+    /// Creates a [`Service`] in the sub-namespace, with the [`QOS_PROFILE_SERVICES_DEFAULT`] QoS
+    /// profile.
+    pub fn create_service<T, F>(
+        &self,
+        topic: &str,
+        callback: F,
+    ) -> Result<Arc<Service<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+        F: Fn(&rmw_request_id_t, T::Request) -> T::Response + 'static + Send,
+    {
+        self.create_service_with_qos(topic, QOS_PROFILE_SERVICES_DEFAULT, callback)
+    }
+
+    /// Creates a [`Service`] in the sub-namespace, with the given QoS profile.
+    pub fn create_service_with_qos<T, F>(
+        &self,
+        topic: &str,
+        qos: QoSProfile,
+        callback: F,
+    ) -> Result<Arc<Service<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+        F: Fn(&rmw_request_id_t, T::Request) -> T::Response + 'static + Send,
+    {
+        self.node
+            .create_service_with_qos(&self.extend_name(topic), qos, callback)
+    }
+
+    /// Creates a [`Service`] in the sub-namespace that can respond to requests after its
+    /// callback has returned, with the [`QOS_PROFILE_SERVICES_DEFAULT`] QoS profile.
+    ///
+    /// See [`Node::create_deferred_service()`] for details.
+    pub fn create_deferred_service<T, F>(
+        &self,
+        topic: &str,
+        callback: F,
+    ) -> Result<Arc<Service<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+        F: Fn(T::Request, ServiceResponder<T>) + 'static + Send,
+    {
+        self.create_deferred_service_with_qos(topic, QOS_PROFILE_SERVICES_DEFAULT, callback)
+    }
+
+    /// Creates a [`Service`] in the sub-namespace that can respond to requests after its
+    /// callback has returned, with the given QoS profile.
+    ///
+    /// See [`Node::create_deferred_service()`] for details.
+    pub fn create_deferred_service_with_qos<T, F>(
+        &self,
+        topic: &str,
+        qos: QoSProfile,
+        callback: F,
+    ) -> Result<Arc<Service<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+        F: Fn(T::Request, ServiceResponder<T>) + 'static + Send,
+    {
+        self.node
+            .create_deferred_service_with_qos(&self.extend_name(topic), qos, callback)
+    }
+
+    /// Creates a [`Subscription`] in the sub-namespace.
+    pub fn create_subscription<T, Args>(
+        &self,
+        topic: &str,
+        qos: QoSProfile,
+        callback: impl SubscriptionCallback<T, Args>,
+    ) -> Result<Arc<Subscription<T>>, RclrsError>
+    where
+        T: Message,
+    {
+        self.node
+            .create_subscription(&self.extend_name(topic), qos, callback)
+    }
+
+    // Prefixes relative names with the sub-namespace
+    fn extend_name(&self, name: &str) -> String {
+        if name.starts_with('/') || name.starts_with('~') {
+            name.to_owned()
+        } else {
+            format!("{}/{}", self.sub_namespace, name)
+        }
+    }
+}
+
+fn validate_sub_namespace(sub_namespace: &str) -> Result<(), RclrsError> {
+    if sub_namespace.is_empty()
+        || sub_namespace.starts_with('/')
+        || sub_namespace.ends_with('/')
+        || sub_namespace.contains("//")
+    {
+        return Err(RclrsError::InvalidSubNamespace {
+            sub_namespace: sub_namespace.to_owned(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Context;
+
+    #[test]
+    fn test_sub_node_names() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let node = Arc::new(Node::new(&context, "test_sub_node_names")?);
+        let sub_node = node.create_sub_node("a")?.create_sub_node("b/c")?;
+        assert_eq!(sub_node.sub_namespace(), "a/b/c");
+        assert_eq!(sub_node.effective_namespace(), "/a/b/c");
+        assert_eq!(sub_node.extend_name("topic"), "a/b/c/topic");
+        assert_eq!(sub_node.extend_name("/topic"), "/topic");
+        assert_eq!(sub_node.extend_name("~/topic"), "~/topic");
+        assert_eq!(
+            sub_node.resolve_service_name("~/service")?,
+            "/test_sub_node_names/service"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_sub_namespaces() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let node = Arc::new(Node::new(&context, "test_invalid_sub_namespaces")?);
+        for sub_namespace in ["", "/a", "a/", "a//b"] {
+            assert_eq!(
+                node.create_sub_node(sub_namespace).unwrap_err(),
+                RclrsError::InvalidSubNamespace {
+                    sub_namespace: sub_namespace.to_owned()
+                }
+            );
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,13 @@
+mod client;
+mod declared;
 mod override_map;
+mod service;
 mod value;
+mod yaml;
 
+pub use client::*;
+pub use declared::*;
 pub(crate) use override_map::*;
+pub(crate) use service::*;
 pub use value::*;
+pub use yaml::*;
@@ -0,0 +1,566 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::vendor::rcl_interfaces::msg::{self, SetParametersResult};
+use crate::vendor::rcl_interfaces::srv::*;
+use crate::{
+    Client, Node, ParameterDescriptor, ParameterKind, ParameterRange, ParameterValue, RclrsError,
+};
+
+/// The names returned by [`AsyncParametersClient::list_parameters()`] and
+/// [`SyncParametersClient::list_parameters()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ListParametersResult {
+    /// The names of the parameters that were found.
+    pub names: Vec<String>,
+    /// The prefixes of those names, i.e. the parts up to a `.` separator.
+    pub prefixes: Vec<String>,
+}
+
+// The clients for the parameter services of one remote node
+struct ParameterClients {
+    describe_parameters: Arc<Client<DescribeParameters>>,
+    get_parameter_types: Arc<Client<GetParameterTypes>>,
+    get_parameters: Arc<Client<GetParameters>>,
+    list_parameters: Arc<Client<ListParameters>>,
+    set_parameters: Arc<Client<SetParameters>>,
+    set_parameters_atomically: Arc<Client<SetParametersAtomically>>,
+}
+
+impl ParameterClients {
+    fn new(node: &Node, remote_node_name: &str) -> Result<Self, RclrsError> {
+        let service_name = |service: &str| format!("{}/{}", remote_node_name, service);
+        Ok(Self {
+            describe_parameters: node.create_client(&service_name("describe_parameters"))?,
+            get_parameter_types: node.create_client(&service_name("get_parameter_types"))?,
+            get_parameters: node.create_client(&service_name("get_parameters"))?,
+            list_parameters: node.create_client(&service_name("list_parameters"))?,
+            set_parameters: node.create_client(&service_name("set_parameters"))?,
+            set_parameters_atomically: node
+                .create_client(&service_name("set_parameters_atomically"))?,
+        })
+    }
+
+    fn service_is_ready(&self) -> Result<bool, RclrsError> {
+        Ok(self.describe_parameters.service_is_ready()?
+            && self.get_parameter_types.service_is_ready()?
+            && self.get_parameters.service_is_ready()?
+            && self.list_parameters.service_is_ready()?
+            && self.set_parameters.service_is_ready()?
+            && self.set_parameters_atomically.service_is_ready()?)
+    }
+}
+
+/// A client for the parameter services of another node, whose methods return futures.
+///
+/// This is the counterpart of the services that a node provides once it has declared
+/// parameters, and can do everything that `ros2 param` can do.
+/// The futures only complete while the node of the client is being spun.
+///
+/// See [`SyncParametersClient`] for a client that blocks until the responses arrive.
+///
+/// # Example
+/// ```no_run
+/// # use rclrs::{AsyncParametersClient, Context, ParameterValue, RclrsError};
+/// # let context = Context::new([])?;
+/// let node = rclrs::create_node(&context, "supervisor")?;
+/// let client = AsyncParametersClient::new(&node, "/controller")?;
+/// let _future = async move {
+///     let results = client
+///         .set_parameters(&[("gain", ParameterValue::Double(0.5))])
+///         .await?;
+///     if let Err(reason) = &results[0] {
+///         println!("Couldn't set the gain: {}", reason);
+///     }
+///     Ok::<(), RclrsError>(())
+/// };
+/// # Ok::<(), RclrsError>(())
+/// ```
+pub struct AsyncParametersClient {
+    clients: ParameterClients,
+}
+
+impl AsyncParametersClient {
+    /// Creates a client for the parameters of the node with the given name.
+    ///
+    /// A relative name, such as `"controller"`, is resolved in the namespace of `node`.
+    pub fn new(node: &Node, remote_node_name: &str) -> Result<Self, RclrsError> {
+        Ok(Self {
+            clients: ParameterClients::new(node, remote_node_name)?,
+        })
+    }
+
+    /// Checks whether the parameter services of the remote node are available.
+    pub fn service_is_ready(&self) -> Result<bool, RclrsError> {
+        self.clients.service_is_ready()
+    }
+
+    /// Gets the values of the given parameters.
+    ///
+    /// The value of a parameter is `None` if it is not declared on the remote node. Nodes using
+    /// `rclcpp` respond without any values if one of the parameters is not declared, so all
+    /// values are `None` then.
+    pub async fn get_parameters(
+        &self,
+        names: &[&str],
+    ) -> Result<Vec<Option<ParameterValue>>, RclrsError> {
+        let response = self
+            .clients
+            .get_parameters
+            .call_async(get_parameters_request(names))
+            .await?;
+        Ok(values_from_msg(response, names.len()))
+    }
+
+    /// Gets the types of the given parameters.
+    ///
+    /// The type of a parameter is `None` if it is not declared on the remote node. Like in
+    /// [`AsyncParametersClient::get_parameters()`], all types are `None` if the remote node
+    /// responds without any types.
+    pub async fn get_parameter_types(
+        &self,
+        names: &[&str],
+    ) -> Result<Vec<Option<ParameterKind>>, RclrsError> {
+        let response = self
+            .clients
+            .get_parameter_types
+            .call_async(get_parameter_types_request(names))
+            .await?;
+        Ok(kinds_from_msg(response, names.len()))
+    }
+
+    /// Sets each of the given parameters independently of the others.
+    ///
+    /// The result of each parameter contains the reason from the remote node if it couldn't be
+    /// set, e.g. because it is read-only or the value has the wrong type.
+    pub async fn set_parameters(
+        &self,
+        parameters: &[(&str, ParameterValue)],
+    ) -> Result<Vec<Result<(), String>>, RclrsError> {
+        let response = self
+            .clients
+            .set_parameters
+            .call_async(set_parameters_request(parameters))
+            .await?;
+        Ok(response.results.into_iter().map(result_from_msg).collect())
+    }
+
+    /// Sets either all or none of the given parameters.
+    ///
+    /// The inner result contains the reason from the remote node if the parameters couldn't be
+    /// set.
+    pub async fn set_parameters_atomically(
+        &self,
+        parameters: &[(&str, ParameterValue)],
+    ) -> Result<Result<(), String>, RclrsError> {
+        let response = self
+            .clients
+            .set_parameters_atomically
+            .call_async(set_parameters_atomically_request(parameters))
+            .await?;
+        Ok(result_from_msg(response.result))
+    }
+
+    /// Lists the parameters that start with one of the given prefixes, or all parameters if
+    /// there are no prefixes.
+    ///
+    /// Only parameters with at most `depth` `.`-separated parts after the prefix are listed, or
+    /// all of them if `depth` is zero.
+    pub async fn list_parameters(
+        &self,
+        prefixes: &[&str],
+        depth: u64,
+    ) -> Result<ListParametersResult, RclrsError> {
+        let response = self
+            .clients
+            .list_parameters
+            .call_async(list_parameters_request(prefixes, depth))
+            .await?;
+        Ok(list_result_from_msg(response))
+    }
+
+    /// Gets the descriptors of the given parameters.
+    ///
+    /// The descriptor of a parameter is `None` if it is not declared on the remote node. Like in
+    /// [`AsyncParametersClient::get_parameters()`], all descriptors are `None` if the remote node
+    /// responds without any descriptors.
+    pub async fn describe_parameters(
+        &self,
+        names: &[&str],
+    ) -> Result<Vec<Option<ParameterDescriptor>>, RclrsError> {
+        let response = self
+            .clients
+            .describe_parameters
+            .call_async(describe_parameters_request(names))
+            .await?;
+        Ok(descriptors_from_msg(response, names.len()))
+    }
+}
+
+/// A client for the parameter services of another node, whose methods block until the
+/// response arrives or the timeout elapses.
+///
+/// This has the same methods as [`AsyncParametersClient`]. They don't require the node of the
+/// client to be spun, and return [`ClientError::Timeout`][1] if no response arrives in time.
+///
+/// # Example
+/// ```no_run
+/// # use std::time::Duration;
+/// # use rclrs::{Context, ParameterValue, RclrsError, SyncParametersClient};
+/// # let context = Context::new([])?;
+/// let node = rclrs::create_node(&context, "supervisor")?;
+/// let client = SyncParametersClient::new(&node, "/controller")?;
+/// let timeout = Some(Duration::from_secs(1));
+/// if client.wait_for_service(timeout)? {
+///     let values = client.get_parameters(&["gain"], timeout)?;
+///     if let Some(ParameterValue::Double(gain)) = values[0] {
+///         println!("The gain is {}", gain);
+///     }
+/// }
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::ClientError::Timeout
+pub struct SyncParametersClient {
+    clients: ParameterClients,
+}
+
+impl SyncParametersClient {
+    /// Creates a client for the parameters of the node with the given name.
+    ///
+    /// A relative name, such as `"controller"`, is resolved in the namespace of `node`.
+    pub fn new(node: &Node, remote_node_name: &str) -> Result<Self, RclrsError> {
+        Ok(Self {
+            clients: ParameterClients::new(node, remote_node_name)?,
+        })
+    }
+
+    /// Checks whether the parameter services of the remote node are available.
+    pub fn service_is_ready(&self) -> Result<bool, RclrsError> {
+        self.clients.service_is_ready()
+    }
+
+    /// Waits until the parameter services of the remote node are available.
+    ///
+    /// Returns `false` if they are still unavailable after the timeout.
+    pub fn wait_for_service(&self, timeout: Option<Duration>) -> Result<bool, RclrsError> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(10);
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if self.clients.service_is_ready()? {
+                return Ok(true);
+            }
+            let sleep_duration = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(remaining) => remaining.min(POLL_INTERVAL),
+                    None => return Ok(false),
+                },
+                None => POLL_INTERVAL,
+            };
+            std::thread::sleep(sleep_duration);
+        }
+    }
+
+    /// See [`AsyncParametersClient::get_parameters()`].
+    pub fn get_parameters(
+        &self,
+        names: &[&str],
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Option<ParameterValue>>, RclrsError> {
+        let response = self
+            .clients
+            .get_parameters
+            .call(get_parameters_request(names), timeout)?;
+        Ok(values_from_msg(response, names.len()))
+    }
+
+    /// See [`AsyncParametersClient::get_parameter_types()`].
+    pub fn get_parameter_types(
+        &self,
+        names: &[&str],
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Option<ParameterKind>>, RclrsError> {
+        let response = self
+            .clients
+            .get_parameter_types
+            .call(get_parameter_types_request(names), timeout)?;
+        Ok(kinds_from_msg(response, names.len()))
+    }
+
+    /// See [`AsyncParametersClient::set_parameters()`].
+    pub fn set_parameters(
+        &self,
+        parameters: &[(&str, ParameterValue)],
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Result<(), String>>, RclrsError> {
+        let response = self
+            .clients
+            .set_parameters
+            .call(set_parameters_request(parameters), timeout)?;
+        Ok(response.results.into_iter().map(result_from_msg).collect())
+    }
+
+    /// See [`AsyncParametersClient::set_parameters_atomically()`].
+    pub fn set_parameters_atomically(
+        &self,
+        parameters: &[(&str, ParameterValue)],
+        timeout: Option<Duration>,
+    ) -> Result<Result<(), String>, RclrsError> {
+        let response = self
+            .clients
+            .set_parameters_atomically
+            .call(set_parameters_atomically_request(parameters), timeout)?;
+        Ok(result_from_msg(response.result))
+    }
+
+    /// See [`AsyncParametersClient::list_parameters()`].
+    pub fn list_parameters(
+        &self,
+        prefixes: &[&str],
+        depth: u64,
+        timeout: Option<Duration>,
+    ) -> Result<ListParametersResult, RclrsError> {
+        let response = self
+            .clients
+            .list_parameters
+            .call(list_parameters_request(prefixes, depth), timeout)?;
+        Ok(list_result_from_msg(response))
+    }
+
+    /// See [`AsyncParametersClient::describe_parameters()`].
+    pub fn describe_parameters(
+        &self,
+        names: &[&str],
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Option<ParameterDescriptor>>, RclrsError> {
+        let response = self
+            .clients
+            .describe_parameters
+            .call(describe_parameters_request(names), timeout)?;
+        Ok(descriptors_from_msg(response, names.len()))
+    }
+}
+
+fn to_strings(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| String::from(*name)).collect()
+}
+
+fn to_parameter_msgs(parameters: &[(&str, ParameterValue)]) -> Vec<msg::Parameter> {
+    parameters
+        .iter()
+        .map(|(name, value)| msg::Parameter {
+            name: String::from(*name),
+            value: value.clone().into(),
+        })
+        .collect()
+}
+
+fn get_parameters_request(names: &[&str]) -> GetParameters_Request {
+    GetParameters_Request {
+        names: to_strings(names),
+    }
+}
+
+fn get_parameter_types_request(names: &[&str]) -> GetParameterTypes_Request {
+    GetParameterTypes_Request {
+        names: to_strings(names),
+    }
+}
+
+fn set_parameters_request(parameters: &[(&str, ParameterValue)]) -> SetParameters_Request {
+    SetParameters_Request {
+        parameters: to_parameter_msgs(parameters),
+    }
+}
+
+fn set_parameters_atomically_request(
+    parameters: &[(&str, ParameterValue)],
+) -> SetParametersAtomically_Request {
+    SetParametersAtomically_Request {
+        parameters: to_parameter_msgs(parameters),
+    }
+}
+
+fn list_parameters_request(prefixes: &[&str], depth: u64) -> ListParameters_Request {
+    ListParameters_Request {
+        prefixes: to_strings(prefixes),
+        depth,
+    }
+}
+
+fn describe_parameters_request(names: &[&str]) -> DescribeParameters_Request {
+    DescribeParameters_Request {
+        names: to_strings(names),
+    }
+}
+
+// Returns the entries of a response if there is one for each requested name. Otherwise, e.g.
+// when rclcpp responds with an empty list because a parameter is not declared, no entry can be
+// matched to its name, and all of them are None.
+fn one_per_name<T>(
+    entries: impl ExactSizeIterator<Item = Option<T>>,
+    count: usize,
+) -> Vec<Option<T>> {
+    if entries.len() == count {
+        entries.collect()
+    } else {
+        (0..count).map(|_| None).collect()
+    }
+}
+
+// Undeclared parameters have the type PARAMETER_NOT_SET, which fails the conversion
+fn values_from_msg(response: GetParameters_Response, count: usize) -> Vec<Option<ParameterValue>> {
+    let values = response
+        .values
+        .into_iter()
+        .map(|value| ParameterValue::try_from(value).ok());
+    one_per_name(values, count)
+}
+
+fn kinds_from_msg(
+    response: GetParameterTypes_Response,
+    count: usize,
+) -> Vec<Option<ParameterKind>> {
+    let kinds = response
+        .types
+        .into_iter()
+        .map(ParameterKind::from_parameter_type);
+    one_per_name(kinds, count)
+}
+
+fn result_from_msg(result: SetParametersResult) -> Result<(), String> {
+    if result.successful {
+        Ok(())
+    } else {
+        Err(result.reason)
+    }
+}
+
+fn list_result_from_msg(response: ListParameters_Response) -> ListParametersResult {
+    ListParametersResult {
+        names: response.result.names,
+        prefixes: response.result.prefixes,
+    }
+}
+
+fn descriptors_from_msg(
+    response: DescribeParameters_Response,
+    count: usize,
+) -> Vec<Option<ParameterDescriptor>> {
+    let descriptors = response.descriptors.into_iter().map(descriptor_from_msg);
+    one_per_name(descriptors, count)
+}
+
+// The inverse of descriptor_to_msg() in the parameter service
+fn descriptor_from_msg(msg: msg::ParameterDescriptor) -> Option<ParameterDescriptor> {
+    let kind = ParameterKind::from_parameter_type(msg.type_)?;
+    let range = if let Some(range) = msg.integer_range.first() {
+        Some(ParameterRange::Integer {
+            from: range.from_value,
+            to: range.to_value,
+            step: range.step,
+        })
+    } else {
+        msg.floating_point_range
+            .first()
+            .map(|range| ParameterRange::Double {
+                from: range.from_value,
+                to: range.to_value,
+                step: range.step,
+            })
+    };
+    Some(ParameterDescriptor {
+        name: msg.name,
+        kind,
+        description: msg.description,
+        read_only: msg.read_only,
+        range,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vendor::rcl_interfaces::msg::ParameterType;
+
+    #[test]
+    fn test_descriptor_from_msg() {
+        let integer_msg = msg::ParameterDescriptor {
+            name: String::from("rate"),
+            type_: ParameterType::PARAMETER_INTEGER,
+            description: String::from("The rate in Hz"),
+            read_only: true,
+            integer_range: [msg::rmw::IntegerRange {
+                from_value: 1,
+                to_value: 100,
+                step: 0,
+            }]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        assert_eq!(
+            descriptor_from_msg(integer_msg),
+            Some(ParameterDescriptor {
+                name: String::from("rate"),
+                kind: ParameterKind::Integer,
+                description: String::from("The rate in Hz"),
+                read_only: true,
+                range: Some(ParameterRange::Integer {
+                    from: 1,
+                    to: 100,
+                    step: 0
+                }),
+            })
+        );
+        let double_msg = msg::ParameterDescriptor {
+            name: String::from("gain"),
+            type_: ParameterType::PARAMETER_DOUBLE,
+            floating_point_range: [msg::rmw::FloatingPointRange {
+                from_value: 0.0,
+                to_value: 1.0,
+                step: 0.25,
+            }]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        assert_eq!(
+            descriptor_from_msg(double_msg).and_then(|descriptor| descriptor.range),
+            Some(ParameterRange::Double {
+                from: 0.0,
+                to: 1.0,
+                step: 0.25
+            })
+        );
+        // Undeclared parameters are described with an empty descriptor
+        let undeclared_msg = msg::ParameterDescriptor {
+            name: String::from("foo"),
+            ..Default::default()
+        };
+        assert_eq!(descriptor_from_msg(undeclared_msg), None);
+    }
+
+    #[test]
+    fn test_values_from_msg() {
+        let response = GetParameters_Response {
+            values: vec![
+                ParameterValue::Bool(true).into(),
+                msg::ParameterValue::default(),
+            ],
+        };
+        assert_eq!(
+            values_from_msg(response, 2),
+            vec![Some(ParameterValue::Bool(true)), None]
+        );
+        // rclcpp doesn't respond with any values if a parameter is not declared
+        assert_eq!(
+            values_from_msg(GetParameters_Response::default(), 2),
+            vec![None, None]
+        );
+        let response = DescribeParameters_Response {
+            descriptors: vec![msg::ParameterDescriptor::default()],
+        };
+        assert_eq!(descriptors_from_msg(response, 3), vec![None, None, None]);
+    }
+}
@@ -0,0 +1,669 @@
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use crate::{ParameterKind, ParameterOverrideMap, ParameterValue, ParameterValueError, RclrsError};
+
+/// A Rust type that can be the value of a declared parameter.
+///
+/// This is implemented for the types that correspond to the variants of [`ParameterValue`].
+pub trait ParameterVariant: Sized {
+    /// The type of the parameter.
+    const KIND: ParameterKind;
+
+    /// Converts the value into a [`ParameterValue`].
+    fn to_parameter_value(&self) -> ParameterValue;
+
+    /// Converts a [`ParameterValue`] into a value of this type, if it has the right variant.
+    fn from_parameter_value(value: ParameterValue) -> Option<Self>;
+}
+
+macro_rules! impl_parameter_variant {
+    ($type:ty, $variant:ident) => {
+        impl ParameterVariant for $type {
+            const KIND: ParameterKind = ParameterKind::$variant;
+
+            fn to_parameter_value(&self) -> ParameterValue {
+                ParameterValue::from(self.clone())
+            }
+
+            fn from_parameter_value(value: ParameterValue) -> Option<Self> {
+                Self::try_from(value).ok()
+            }
+        }
+    };
+}
+
+impl_parameter_variant!(bool, Bool);
+impl_parameter_variant!(i64, Integer);
+impl_parameter_variant!(f64, Double);
+impl_parameter_variant!(String, String);
+impl_parameter_variant!(Vec<u8>, ByteArray);
+impl_parameter_variant!(Vec<bool>, BoolArray);
+impl_parameter_variant!(Vec<i64>, IntegerArray);
+impl_parameter_variant!(Vec<f64>, DoubleArray);
+impl_parameter_variant!(Vec<String>, StringArray);
+
+/// A parameter type that can be restricted to a range of values.
+pub trait NumericParameter: ParameterVariant {
+    /// Creates a range from `from` to `to`, both inclusive.
+    ///
+    /// Only values that are a multiple of `step` away from `from`, or equal to `to`, are in the
+    /// range. A step of zero allows any value between `from` and `to`.
+    fn range(from: Self, to: Self, step: Self) -> ParameterRange;
+}
+
+impl NumericParameter for i64 {
+    fn range(from: Self, to: Self, step: Self) -> ParameterRange {
+        ParameterRange::Integer {
+            from,
+            to,
+            step: step.unsigned_abs(),
+        }
+    }
+}
+
+impl NumericParameter for f64 {
+    fn range(from: Self, to: Self, step: Self) -> ParameterRange {
+        ParameterRange::Double {
+            from,
+            to,
+            step: step.abs(),
+        }
+    }
+}
+
+/// The allowed values of an integer or double parameter.
+///
+/// This corresponds to the `IntegerRange` and `FloatingPointRange` messages.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParameterRange {
+    /// A range of [`ParameterValue::Integer`] values.
+    Integer {
+        /// The lowest allowed value.
+        from: i64,
+        /// The highest allowed value.
+        to: i64,
+        /// The distance between allowed values, or zero to allow any value.
+        step: u64,
+    },
+    /// A range of [`ParameterValue::Double`] values.
+    Double {
+        /// The lowest allowed value.
+        from: f64,
+        /// The highest allowed value.
+        to: f64,
+        /// The distance between allowed values, or zero to allow any value.
+        step: f64,
+    },
+}
+
+impl ParameterRange {
+    fn validate(&self, value: &ParameterValue) -> Result<(), String> {
+        match (self, value) {
+            (ParameterRange::Integer { from, to, step }, ParameterValue::Integer(value)) => {
+                if value < from || value > to {
+                    return Err(format!(
+                        "Value {} is not in the range [{}, {}]",
+                        value, from, to
+                    ));
+                }
+                // The difference can't overflow in i128
+                let offset = (*value as i128 - *from as i128) as u128;
+                if *step != 0 && value != to && offset % u128::from(*step) > 0 {
+                    return Err(format!(
+                        "Value {} is not a multiple of {} away from {}",
+                        value, step, from
+                    ));
+                }
+                Ok(())
+            }
+            (ParameterRange::Double { from, to, step }, ParameterValue::Double(value)) => {
+                // Like in rclcpp, values close to the bounds are accepted
+                if doubles_are_equal(*value, *from) || doubles_are_equal(*value, *to) {
+                    return Ok(());
+                }
+                if value < from || value > to || value.is_nan() {
+                    return Err(format!(
+                        "Value {:?} is not in the range [{:?}, {:?}]",
+                        value, from, to
+                    ));
+                }
+                if *step != 0.0 {
+                    let steps = ((value - from) / step).round();
+                    if !doubles_are_equal(*value, from + steps * step) {
+                        return Err(format!(
+                            "Value {:?} is not a multiple of {:?} away from {:?}",
+                            value, step, from
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            // The kind is checked separately
+            _ => Ok(()),
+        }
+    }
+}
+
+// The same comparison as in rclcpp, which tolerates the rounding errors of a few operations
+fn doubles_are_equal(x: f64, y: f64) -> bool {
+    const ULP: f64 = 100.0;
+    (x - y).abs() <= f64::EPSILON * (x + y).abs() * ULP || (x - y).abs() < f64::MIN_POSITIVE
+}
+
+/// The description of a declared parameter.
+///
+/// This corresponds to the `ParameterDescriptor` message, which is returned by the
+/// `describe_parameters` service of a node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterDescriptor {
+    /// The name of the parameter.
+    pub name: String,
+    /// The type of the parameter, which can't be changed after the parameter is declared.
+    pub kind: ParameterKind,
+    /// A human-readable description of the parameter.
+    pub description: String,
+    /// Whether the parameter can only be set with a parameter override when it is declared.
+    pub read_only: bool,
+    /// The allowed values of an integer or double parameter.
+    pub range: Option<ParameterRange>,
+}
+
+impl ParameterDescriptor {
+    /// Checks that the value has the type of the parameter and is in its range.
+    ///
+    /// The [`read_only`][1] flag is not checked.
+    ///
+    /// [1]: ParameterDescriptor::read_only
+    pub fn validate(&self, value: &ParameterValue) -> Result<(), String> {
+        if value.kind() != self.kind {
+            let error = ParameterValueError::WrongType {
+                expected: self.kind,
+                actual: value.kind(),
+            };
+            return Err(error.to_string());
+        }
+        match &self.range {
+            Some(range) => range.validate(value),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A struct whose fields are node parameters.
+///
+/// This trait is usually implemented with `#[derive(Parameters)]`. Every field becomes a
+/// parameter named after the field, and its type must implement [`ParameterVariant`]. The
+/// following attributes are available for the fields:
+/// - `#[parameter(default = <expr>)]`: The value of the parameter if it is not overridden. The
+///   expression is converted into the field type with [`Into`], so e.g. a string literal can be
+///   the default of a `String` field. Without this attribute, the [`Default`] value of the field
+///   type is used.
+/// - `#[parameter(description = "...")]`: The description of the parameter. Without this
+///   attribute, the doc comment of the field is used.
+/// - `#[parameter(read_only)]`: The parameter can't be changed after it has been declared.
+/// - `#[parameter(range(from = <expr>, to = <expr>, step = <expr>))]`: The allowed values of an
+///   integer or double parameter. The `step` is optional.
+/// - `#[parameter(name = "...")]`: The name of the parameter, if it is different from the name of
+///   the field. For instance, the name may contain dots to group parameters.
+///
+/// The parameters are declared with [`Node::declare_parameters()`][1].
+///
+/// # Example
+/// ```
+/// # use rclrs::{Context, ParameterValue, Parameters, RclrsError};
+/// #[derive(Clone, Parameters)]
+/// struct ControllerParameters {
+///     /// The control loop frequency in Hz.
+///     #[parameter(default = 100.0, range(from = 1.0, to = 1000.0))]
+///     rate: f64,
+///     #[parameter(default = "base_link", read_only)]
+///     frame_id: String,
+///     #[parameter(name = "gains.p", default = 2)]
+///     p_gain: i64,
+/// }
+///
+/// let context = Context::new([])?;
+/// let node = rclrs::create_node_builder(&context, "controller")
+///     .parameter_overrides([(String::from("gains.p"), ParameterValue::Integer(5))])
+///     .build()?;
+/// let parameters = node.declare_parameters::<ControllerParameters>()?;
+/// assert_eq!(parameters.get().rate, 100.0);
+/// assert_eq!(parameters.get().p_gain, 5);
+///
+/// node.set_parameter("rate", ParameterValue::Double(50.0))?;
+/// assert_eq!(parameters.get().rate, 50.0);
+/// assert!(node.set_parameter("rate", ParameterValue::Double(0.0)).is_err());
+/// assert!(node.set_parameter("frame_id", ParameterValue::String("map".into())).is_err());
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::Node::declare_parameters
+pub trait Parameters: Sized + Send + 'static {
+    /// Creates the struct with the default value of each parameter.
+    fn with_defaults() -> Self;
+
+    /// Returns the descriptors of all parameters.
+    fn descriptors() -> Vec<ParameterDescriptor>;
+
+    /// Returns the names and current values of all parameters.
+    fn values(&self) -> Vec<(String, ParameterValue)>;
+
+    /// Sets the field of the parameter with the given name.
+    ///
+    /// The value has already been validated against the descriptor of the parameter.
+    fn set_value(&mut self, name: &str, value: ParameterValue) -> Result<(), String>;
+}
+
+/// A handle to the current values of parameters declared with
+/// [`Node::declare_parameters()`][1].
+///
+/// The values are updated when parameters are set, e.g. through the parameter services of the
+/// node or with [`Node::set_parameter()`][2].
+///
+/// [1]: crate::Node::declare_parameters
+/// [2]: crate::Node::set_parameter
+pub struct TypedParameters<P> {
+    parameters: Arc<Mutex<P>>,
+}
+
+impl<P> Clone for TypedParameters<P> {
+    fn clone(&self) -> Self {
+        Self {
+            parameters: Arc::clone(&self.parameters),
+        }
+    }
+}
+
+impl<P: Clone> TypedParameters<P> {
+    /// Returns a copy of the current values.
+    pub fn get(&self) -> P {
+        self.parameters.lock().unwrap().clone()
+    }
+}
+
+impl<P> TypedParameters<P> {
+    /// Calls the given function with the current values.
+    ///
+    /// The parameters can't be set while the function is running.
+    pub fn with<T>(&self, f: impl FnOnce(&P) -> T) -> T {
+        f(&self.parameters.lock().unwrap())
+    }
+}
+
+// A type-erased set of parameters that were declared together
+trait DeclaredParameters: Send {
+    fn values(&self) -> Vec<(String, ParameterValue)>;
+    fn set_value(&self, name: &str, value: ParameterValue) -> Result<(), String>;
+}
+
+impl<P: Parameters> DeclaredParameters for Arc<Mutex<P>> {
+    fn values(&self) -> Vec<(String, ParameterValue)> {
+        self.lock().unwrap().values()
+    }
+
+    fn set_value(&self, name: &str, value: ParameterValue) -> Result<(), String> {
+        self.lock().unwrap().set_value(name, value)
+    }
+}
+
+// The parameters that were declared on a node, shared with its parameter services
+#[derive(Default)]
+pub(crate) struct ParameterRegistry {
+    // Each entry is the index of a set and the descriptor of one of its parameters
+    descriptors: Vec<(usize, ParameterDescriptor)>,
+    sets: Vec<Box<dyn DeclaredParameters>>,
+}
+
+impl ParameterRegistry {
+    // Declares the parameters of P, with their values taken from the overrides if present
+    pub(crate) fn declare<P: Parameters>(
+        &mut self,
+        overrides: &ParameterOverrideMap,
+    ) -> Result<TypedParameters<P>, RclrsError> {
+        let descriptors = P::descriptors();
+        let mut parameters = P::with_defaults();
+        let mut names = BTreeSet::new();
+        for descriptor in &descriptors {
+            let invalid = |reason: String| RclrsError::InvalidParameter {
+                name: descriptor.name.clone(),
+                reason,
+            };
+            if self.describe(&descriptor.name).is_some() || !names.insert(&descriptor.name) {
+                return Err(invalid(String::from("The parameter is already declared")));
+            }
+            if let Some(value) = overrides.get(&descriptor.name) {
+                descriptor.validate(value).map_err(invalid)?;
+                parameters
+                    .set_value(&descriptor.name, value.clone())
+                    .map_err(invalid)?;
+            }
+        }
+        // The default values must be valid too
+        for (name, value) in parameters.values() {
+            if let Some(descriptor) = descriptors.iter().find(|d| d.name == name) {
+                descriptor
+                    .validate(&value)
+                    .map_err(|reason| RclrsError::InvalidParameter { name, reason })?;
+            }
+        }
+        let parameters = Arc::new(Mutex::new(parameters));
+        let index = self.sets.len();
+        self.sets.push(Box::new(Arc::clone(&parameters)));
+        self.descriptors.extend(
+            descriptors
+                .into_iter()
+                .map(|descriptor| (index, descriptor)),
+        );
+        Ok(TypedParameters { parameters })
+    }
+
+    pub(crate) fn describe(&self, name: &str) -> Option<&ParameterDescriptor> {
+        self.find(name).map(|(_, descriptor)| descriptor)
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<ParameterValue> {
+        let (index, _) = self.find(name)?;
+        self.sets[index]
+            .values()
+            .into_iter()
+            .find_map(|(other, value)| (other == name).then_some(value))
+    }
+
+    // Returns the names and values of all declared parameters
+    pub(crate) fn values(&self) -> Vec<(String, ParameterValue)> {
+        self.sets.iter().flat_map(|set| set.values()).collect()
+    }
+
+    // Returns the names of the parameters that match the prefixes, and the prefixes of these
+    // names, like the list_parameters service of rclcpp. A depth of zero means any depth.
+    pub(crate) fn list(&self, prefixes: &[String], depth: u64) -> (Vec<String>, Vec<String>) {
+        let depth_matches = |name: &str| depth == 0 || (name.matches('.').count() as u64) < depth;
+        let mut names = vec![];
+        let mut name_prefixes: Vec<String> = vec![];
+        for (_, descriptor) in &self.descriptors {
+            let name = descriptor.name.as_str();
+            let matches = if prefixes.is_empty() {
+                depth_matches(name)
+            } else {
+                prefixes.iter().any(|prefix| {
+                    name == prefix
+                        || (name.starts_with(prefix.as_str())
+                            && name[prefix.len()..].starts_with('.')
+                            && depth_matches(&name[prefix.len()..]))
+                })
+            };
+            if !matches {
+                continue;
+            }
+            names.push(name.to_owned());
+            if let Some((name_prefix, _)) = name.rsplit_once('.') {
+                if !name_prefixes.iter().any(|other| other == name_prefix) {
+                    name_prefixes.push(name_prefix.to_owned());
+                }
+            }
+        }
+        (names, name_prefixes)
+    }
+
+    pub(crate) fn set(&self, name: &str, value: ParameterValue) -> Result<(), String> {
+        let index = self.validate(name, &value)?;
+        self.sets[index].set_value(name, value)
+    }
+
+    // Sets either all or none of the parameters
+    pub(crate) fn set_atomically(
+        &self,
+        parameters: Vec<(String, ParameterValue)>,
+    ) -> Result<(), String> {
+        let with_name = |name: &str, reason: String| format!("Parameter '{}': {}", name, reason);
+        let indices = parameters
+            .iter()
+            .map(|(name, value)| self.validate(name, value).map_err(|r| with_name(name, r)))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (index, (name, value)) in indices.into_iter().zip(parameters) {
+            self.sets[index]
+                .set_value(&name, value)
+                .map_err(|reason| with_name(&name, reason))?;
+        }
+        Ok(())
+    }
+
+    // Checks that a declared parameter can be set to the value, and returns the index of its set
+    fn validate(&self, name: &str, value: &ParameterValue) -> Result<usize, String> {
+        let (index, descriptor) = self
+            .find(name)
+            .ok_or_else(|| String::from("The parameter is not declared"))?;
+        if descriptor.read_only {
+            return Err(String::from("The parameter is read-only"));
+        }
+        descriptor.validate(value)?;
+        Ok(index)
+    }
+
+    fn find(&self, name: &str) -> Option<(usize, &ParameterDescriptor)> {
+        self.descriptors
+            .iter()
+            .find(|(_, descriptor)| descriptor.name == name)
+            .map(|(index, descriptor)| (*index, descriptor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct TestParameters {
+        rate: f64,
+        mode: String,
+        count: i64,
+    }
+
+    // What #[derive(Parameters)] generates
+    impl Parameters for TestParameters {
+        fn with_defaults() -> Self {
+            Self {
+                rate: 1.0,
+                mode: String::from("fast"),
+                count: Default::default(),
+            }
+        }
+
+        fn descriptors() -> Vec<ParameterDescriptor> {
+            vec![
+                ParameterDescriptor {
+                    name: String::from("rate"),
+                    kind: <f64 as ParameterVariant>::KIND,
+                    description: String::from("The rate"),
+                    read_only: false,
+                    range: Some(<f64 as NumericParameter>::range(0.0, 1.0, 0.1)),
+                },
+                ParameterDescriptor {
+                    name: String::from("mode"),
+                    kind: <String as ParameterVariant>::KIND,
+                    description: String::new(),
+                    read_only: true,
+                    range: None,
+                },
+                ParameterDescriptor {
+                    name: String::from("group.count"),
+                    kind: <i64 as ParameterVariant>::KIND,
+                    description: String::new(),
+                    read_only: false,
+                    range: Some(<i64 as NumericParameter>::range(-4, 5, 2)),
+                },
+            ]
+        }
+
+        fn values(&self) -> Vec<(String, ParameterValue)> {
+            vec![
+                (String::from("rate"), self.rate.to_parameter_value()),
+                (String::from("mode"), self.mode.to_parameter_value()),
+                (String::from("group.count"), self.count.to_parameter_value()),
+            ]
+        }
+
+        fn set_value(&mut self, name: &str, value: ParameterValue) -> Result<(), String> {
+            let wrong_type = || format!("Wrong parameter type for '{}'", name);
+            match name {
+                "rate" => self.rate = f64::from_parameter_value(value).ok_or_else(wrong_type)?,
+                "mode" => self.mode = String::from_parameter_value(value).ok_or_else(wrong_type)?,
+                "group.count" => {
+                    self.count = i64::from_parameter_value(value).ok_or_else(wrong_type)?
+                }
+                _ => return Err(format!("Parameter '{}' is not declared", name)),
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_ranges() {
+        let integer_range = i64::range(-4, 5, 2);
+        for value in [-4, -2, 0, 4, 5] {
+            assert!(integer_range
+                .validate(&ParameterValue::Integer(value))
+                .is_ok());
+        }
+        for value in [-5, -3, 3, 6] {
+            assert!(integer_range
+                .validate(&ParameterValue::Integer(value))
+                .is_err());
+        }
+        let full_range = i64::range(i64::MIN, i64::MAX, 1);
+        assert!(full_range.validate(&ParameterValue::Integer(0)).is_ok());
+
+        let double_range = f64::range(0.0, 1.0, 0.1);
+        for value in [0.0, 0.3, 0.7, 1.0, 1.0 + 1e-14] {
+            assert!(double_range
+                .validate(&ParameterValue::Double(value))
+                .is_ok());
+        }
+        for value in [-0.1, 0.35, 1.1, f64::NAN] {
+            assert!(double_range
+                .validate(&ParameterValue::Double(value))
+                .is_err());
+        }
+        assert!(f64::range(0.0, 1.0, 0.0)
+            .validate(&ParameterValue::Double(0.123))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_declare_with_overrides() -> Result<(), RclrsError> {
+        let mut registry = ParameterRegistry::default();
+        let overrides = ParameterOverrideMap::from([
+            (String::from("mode"), ParameterValue::String("slow".into())),
+            (String::from("group.count"), ParameterValue::Integer(2)),
+            (String::from("undeclared"), ParameterValue::Bool(true)),
+        ]);
+        let parameters = registry.declare::<TestParameters>(&overrides)?;
+        assert_eq!(
+            parameters.get(),
+            TestParameters {
+                rate: 1.0,
+                mode: String::from("slow"),
+                count: 2,
+            }
+        );
+        assert_eq!(
+            registry.get("mode"),
+            Some(ParameterValue::String("slow".into()))
+        );
+        assert_eq!(registry.get("undeclared"), None);
+        assert_eq!(registry.values().len(), 3);
+        assert!(registry.describe("mode").unwrap().read_only);
+
+        // The same parameters can't be declared twice
+        assert!(matches!(
+            registry.declare::<TestParameters>(&ParameterOverrideMap::new()),
+            Err(RclrsError::InvalidParameter { name, .. }) if name == "rate"
+        ));
+
+        // Invalid overrides are an error
+        let mut registry = ParameterRegistry::default();
+        let overrides = ParameterOverrideMap::from([(
+            String::from("group.count"),
+            ParameterValue::Double(2.0),
+        )]);
+        assert_eq!(
+            registry
+                .declare::<TestParameters>(&overrides)
+                .err()
+                .unwrap()
+                .to_string(),
+            "Invalid parameter 'group.count': Wrong parameter type, expected 'integer' but got 'double'"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_parameters() {
+        let mut registry = ParameterRegistry::default();
+        let parameters = registry
+            .declare::<TestParameters>(&ParameterOverrideMap::new())
+            .unwrap();
+        assert_eq!(
+            registry.set("group.count", ParameterValue::Integer(4)),
+            Ok(())
+        );
+        assert_eq!(parameters.with(|p| p.count), 4);
+        assert_eq!(
+            registry.set("mode", ParameterValue::String("slow".into())),
+            Err(String::from("The parameter is read-only"))
+        );
+        assert_eq!(
+            registry.set("other", ParameterValue::Integer(1)),
+            Err(String::from("The parameter is not declared"))
+        );
+        assert!(registry.set("rate", ParameterValue::Integer(1)).is_err());
+
+        // A single invalid value prevents all values from being set
+        let result = registry.set_atomically(vec![
+            (String::from("rate"), ParameterValue::Double(0.5)),
+            (String::from("group.count"), ParameterValue::Integer(3)),
+        ]);
+        assert_eq!(
+            result,
+            Err(String::from(
+                "Parameter 'group.count': Value 3 is not a multiple of 2 away from -4"
+            ))
+        );
+        assert_eq!(parameters.get().rate, 1.0);
+        let result = registry.set_atomically(vec![
+            (String::from("rate"), ParameterValue::Double(0.5)),
+            (String::from("group.count"), ParameterValue::Integer(0)),
+        ]);
+        assert_eq!(result, Ok(()));
+        assert_eq!(parameters.get().rate, 0.5);
+        assert_eq!(parameters.get().count, 0);
+    }
+
+    #[test]
+    fn test_list_parameters() -> Result<(), RclrsError> {
+        let mut registry = ParameterRegistry::default();
+        registry.declare::<TestParameters>(&ParameterOverrideMap::new())?;
+        let all = (
+            vec![
+                String::from("rate"),
+                String::from("mode"),
+                String::from("group.count"),
+            ],
+            vec![String::from("group")],
+        );
+        assert_eq!(registry.list(&[], 0), all);
+        assert_eq!(
+            registry.list(&[], 1),
+            (vec![String::from("rate"), String::from("mode")], vec![])
+        );
+        let group = (
+            vec![String::from("group.count")],
+            vec![String::from("group")],
+        );
+        assert_eq!(registry.list(&[String::from("group")], 0), group);
+        assert_eq!(registry.list(&[String::from("group")], 2), group);
+        assert_eq!(registry.list(&[String::from("gr")], 0), (vec![], vec![]));
+        Ok(())
+    }
+}
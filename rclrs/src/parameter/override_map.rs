@@ -6,13 +6,13 @@ use crate::rcl_bindings::*;
 use crate::{ParameterValue, RclrsError, ToResult};
 
 // Internal helper struct, iterator for rcl_params_t
-struct RclParamsIter<'a> {
+pub(crate) struct RclParamsIter<'a> {
     node_name_ptrs: &'a [*mut c_char],
     rcl_node_params: &'a [rcl_node_params_t],
 }
 
 // Internal helper struct, iterator for rcl_node_params_t
-struct RclNodeParamsIter<'a> {
+pub(crate) struct RclNodeParamsIter<'a> {
     param_name_ptrs: &'a [*mut c_char],
     rcl_variants: &'a [rcl_variant_t],
 }
@@ -136,6 +136,37 @@ pub(crate) unsafe fn resolve_parameter_overrides(
     Ok(map)
 }
 
+/// Formats the parameters as a parameter file that applies to any node.
+///
+/// The parameters are written with their full names, e.g. `a.b: 1` instead of a nested `b: 1`
+/// under `a:`, which is equivalent for rcl. Empty arrays are written as comments, since an empty
+/// sequence has no type and can't be loaded from a parameter file. Byte arrays are written as
+/// comments too, since rcl loads every sequence of integers as an integer array.
+pub(crate) fn parameters_to_yaml(map: &ParameterOverrideMap) -> String {
+    let mut yaml = String::from("/**:\n  ros__parameters:\n");
+    for (name, value) in map {
+        let skipped = match value {
+            ParameterValue::ByteArray(_) => Some("byte arrays"),
+            ParameterValue::BoolArray(values) if values.is_empty() => Some("empty arrays"),
+            ParameterValue::IntegerArray(values) if values.is_empty() => Some("empty arrays"),
+            ParameterValue::DoubleArray(values) if values.is_empty() => Some("empty arrays"),
+            ParameterValue::StringArray(values) if values.is_empty() => Some("empty arrays"),
+            _ => None,
+        };
+        if let Some(skipped) = skipped {
+            yaml.push_str(&format!(
+                "    # {}: skipped, {} can't be loaded ({})\n",
+                name,
+                skipped,
+                value.kind()
+            ));
+        } else {
+            yaml.push_str(&format!("    {}: {}\n", name, value.to_yaml()));
+        }
+    }
+    yaml
+}
+
 #[cfg(test)]
 mod tests {
     use std::error::Error;
@@ -259,4 +290,50 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_parameters_to_yaml_round_trip() -> Result<(), Box<dyn Error>> {
+        let map: ParameterOverrideMap = [
+            ("flag", ParameterValue::Bool(true)),
+            ("gain.p", ParameterValue::Double(1.0)),
+            ("gain.i", ParameterValue::Double(f64::INFINITY)),
+            ("count", ParameterValue::Integer(-3)),
+            (
+                "name",
+                ParameterValue::String(String::from("42 \"quoted\"\n")),
+            ),
+            ("ids", ParameterValue::IntegerArray(vec![1, 2])),
+            (
+                "frames",
+                ParameterValue::StringArray(vec![String::from("yes"), String::from("a\\b")]),
+            ),
+            ("no_ids", ParameterValue::IntegerArray(vec![])),
+            ("no_frames", ParameterValue::StringArray(vec![])),
+            ("no_bytes", ParameterValue::ByteArray(vec![1, 2])),
+        ]
+        .into_iter()
+        .map(|(name, value)| (String::from(name), value))
+        .collect();
+        let yaml = parameters_to_yaml(&map);
+        assert!(yaml.starts_with("/**:\n  ros__parameters:\n    count: -3\n"));
+        assert!(yaml
+            .contains("\n    # no_ids: skipped, empty arrays can't be loaded (integer_array)\n"));
+        assert!(
+            yaml.contains("\n    # no_bytes: skipped, byte arrays can't be loaded (byte_array)\n")
+        );
+
+        let mut rcl_node_arguments = convert_to_rcl_arguments(&yaml)?;
+        let mut rcl_global_arguments = convert_to_rcl_arguments(&yaml)?;
+        let parsed_map = unsafe {
+            resolve_parameter_overrides("/my_node", &rcl_node_arguments, &rcl_global_arguments)?
+        };
+        unsafe {
+            rcl_arguments_fini(&mut rcl_node_arguments);
+            rcl_arguments_fini(&mut rcl_global_arguments);
+        }
+        let mut expected_map = map;
+        expected_map.retain(|name, _| !name.starts_with("no_"));
+        assert_eq!(parsed_map, expected_map);
+        Ok(())
+    }
 }
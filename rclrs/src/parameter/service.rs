@@ -1,89 +1,187 @@
-use std::sync::{Arc, Weak, Mutex};
+use std::sync::{Arc, Mutex};
 
-use crate::vendor::rcl_interfaces::srv::rmw::*;
-use crate::vendor::rcl_interfaces::msg::rmw::*;
-use rosidl_runtime_rs::{Sequence, seq};
+use crate::vendor::rcl_interfaces::msg::{
+    self, ListParametersResult, ParameterType, SetParametersResult,
+};
+use crate::vendor::rcl_interfaces::srv::*;
+use crate::{
+    rmw_request_id_t, Node, ParameterDescriptor, ParameterRange, ParameterRegistry, ParameterValue,
+    RclrsError, Service,
+};
 
-use crate::{rmw_request_id_t, Node, RclrsError, Service, ServiceBase};
-use crate::rcl_bindings::rcl_node_t;
-
-pub struct ParameterService {
-    describe_parameters_service: Arc<Service<DescribeParameters>>,
-    get_parameter_types_service: Arc<Service<GetParameterTypes>>,
-    get_parameters_service: Arc<Service<GetParameters>>,
-    list_parameters_service: Arc<Service<ListParameters>>,
-    set_parameters_service: Arc<Service<SetParameters>>,
-    set_parameters_atomically_service: Arc<Service<SetParametersAtomically>>,
+// The services that let other nodes get, set, list and describe the parameters of a node, like
+// `ros2 param` does. They are created when the first parameters are declared on the node.
+pub(crate) struct ParameterService {
+    _describe_parameters_service: Arc<Service<DescribeParameters>>,
+    _get_parameter_types_service: Arc<Service<GetParameterTypes>>,
+    _get_parameters_service: Arc<Service<GetParameters>>,
+    _list_parameters_service: Arc<Service<ListParameters>>,
+    _set_parameters_service: Arc<Service<SetParameters>>,
+    _set_parameters_atomically_service: Arc<Service<SetParametersAtomically>>,
 }
 
 impl ParameterService {
-    pub fn new(rcl_node_mtx: Arc<Mutex<rcl_node_t>>) -> Result<Self, RclrsError> {
-        let describe_parameters_service = Service::new(Arc::clone(&rcl_node_mtx),
-            "describe_parameters",
-            |req_id: &rmw_request_id_t, req: DescribeParameters_Request| {
-                DescribeParameters_Response {
-                    descriptors: seq![]
-                }
+    pub(crate) fn new(
+        node: &Node,
+        registry: &Arc<Mutex<ParameterRegistry>>,
+    ) -> Result<Self, RclrsError> {
+        let registry_clone = Arc::clone(registry);
+        let describe_parameters_service = node.create_service(
+            "~/describe_parameters",
+            move |_: &rmw_request_id_t, req: DescribeParameters_Request| {
+                let registry = registry_clone.lock().unwrap();
+                let descriptors = req
+                    .names
+                    .iter()
+                    .map(|name| match registry.describe(name) {
+                        Some(descriptor) => descriptor_to_msg(descriptor),
+                        None => msg::ParameterDescriptor {
+                            name: name.clone(),
+                            ..Default::default()
+                        },
+                    })
+                    .collect();
+                DescribeParameters_Response { descriptors }
             },
         )?;
-        let get_parameter_types_service = Service::new(Arc::clone(&rcl_node_mtx),
-            "get_parameter_types",
-            |req_id: &rmw_request_id_t, req: GetParameterTypes_Request| {
-                GetParameterTypes_Response {
-                    types: seq![]
-                }
+        let registry_clone = Arc::clone(registry);
+        let get_parameter_types_service = node.create_service(
+            "~/get_parameter_types",
+            move |_: &rmw_request_id_t, req: GetParameterTypes_Request| {
+                let registry = registry_clone.lock().unwrap();
+                let types = req
+                    .names
+                    .iter()
+                    .map(|name| match registry.describe(name) {
+                        Some(descriptor) => descriptor.kind.to_parameter_type(),
+                        None => ParameterType::PARAMETER_NOT_SET,
+                    })
+                    .collect();
+                GetParameterTypes_Response { types }
             },
         )?;
-        let get_parameters_service = Service::new(Arc::clone(&rcl_node_mtx),
-            "get_parameters",
-            |req_id: &rmw_request_id_t, req: GetParameters_Request| {
-                GetParameters_Response {
-                    values: seq![]
-                }
+        let registry_clone = Arc::clone(registry);
+        let get_parameters_service = node.create_service(
+            "~/get_parameters",
+            move |_: &rmw_request_id_t, req: GetParameters_Request| {
+                let registry = registry_clone.lock().unwrap();
+                let values = req
+                    .names
+                    .iter()
+                    .map(|name| match registry.get(name) {
+                        Some(value) => value.into(),
+                        None => msg::ParameterValue::default(),
+                    })
+                    .collect();
+                GetParameters_Response { values }
             },
         )?;
-        let list_parameters_service = Service::new(Arc::clone(&rcl_node_mtx),
-            "list_parameters",
-            |req_id: &rmw_request_id_t, req: ListParameters_Request| {
+        let registry_clone = Arc::clone(registry);
+        let list_parameters_service = node.create_service(
+            "~/list_parameters",
+            move |_: &rmw_request_id_t, req: ListParameters_Request| {
+                let registry = registry_clone.lock().unwrap();
+                let (names, prefixes) = registry.list(&req.prefixes, req.depth);
                 ListParameters_Response {
-                    result: ListParametersResult::default()
+                    result: ListParametersResult { names, prefixes },
                 }
             },
         )?;
-        let set_parameters_service = Service::new(Arc::clone(&rcl_node_mtx),
-            "set_parameters",
-            |req_id: &rmw_request_id_t, req: SetParameters_Request| {
-                SetParameters_Response {
-                    results: seq![]
-                }
+        let registry_clone = Arc::clone(registry);
+        let set_parameters_service = node.create_service(
+            "~/set_parameters",
+            move |_: &rmw_request_id_t, req: SetParameters_Request| {
+                let registry = registry_clone.lock().unwrap();
+                let results = req
+                    .parameters
+                    .into_iter()
+                    .map(|parameter| {
+                        let result = value_from_msg(parameter.value)
+                            .and_then(|value| registry.set(&parameter.name, value));
+                        result_to_msg(result)
+                    })
+                    .collect();
+                SetParameters_Response { results }
             },
         )?;
-        let set_parameters_atomically_service = Service::new(Arc::clone(&rcl_node_mtx),
-            "set_parameters_atomically",
-            |req_id: &rmw_request_id_t, req: SetParametersAtomically_Request| {
+        let registry_clone = Arc::clone(registry);
+        let set_parameters_atomically_service = node.create_service(
+            "~/set_parameters_atomically",
+            move |_: &rmw_request_id_t, req: SetParametersAtomically_Request| {
+                let registry = registry_clone.lock().unwrap();
+                let result = req
+                    .parameters
+                    .into_iter()
+                    .map(|parameter| Ok((parameter.name, value_from_msg(parameter.value)?)))
+                    .collect::<Result<Vec<_>, String>>()
+                    .and_then(|parameters| registry.set_atomically(parameters));
                 SetParametersAtomically_Response {
-                    result: SetParametersResult::default()
+                    result: result_to_msg(result),
                 }
             },
         )?;
         Ok(Self {
-            describe_parameters_service: Arc::new(describe_parameters_service),
-            get_parameter_types_service: Arc::new(get_parameter_types_service),
-            get_parameters_service: Arc::new(get_parameters_service),
-            list_parameters_service: Arc::new(list_parameters_service),
-            set_parameters_service: Arc::new(set_parameters_service),
-            set_parameters_atomically_service: Arc::new(set_parameters_atomically_service),
+            _describe_parameters_service: describe_parameters_service,
+            _get_parameter_types_service: get_parameter_types_service,
+            _get_parameters_service: get_parameters_service,
+            _list_parameters_service: list_parameters_service,
+            _set_parameters_service: set_parameters_service,
+            _set_parameters_atomically_service: set_parameters_atomically_service,
         })
     }
+}
+
+// Setting a parameter to PARAMETER_NOT_SET means undeclaring it in rclcpp
+fn value_from_msg(msg: msg::ParameterValue) -> Result<ParameterValue, String> {
+    if msg.type_ == ParameterType::PARAMETER_NOT_SET {
+        return Err(String::from(
+            "Parameters can't be undeclared, since they are struct fields",
+        ));
+    }
+    ParameterValue::try_from(msg).map_err(|error| error.to_string())
+}
+
+fn descriptor_to_msg(descriptor: &ParameterDescriptor) -> msg::ParameterDescriptor {
+    let mut msg = msg::ParameterDescriptor {
+        name: descriptor.name.clone(),
+        type_: descriptor.kind.to_parameter_type(),
+        description: descriptor.description.clone(),
+        read_only: descriptor.read_only,
+        ..Default::default()
+    };
+    match descriptor.range {
+        Some(ParameterRange::Integer { from, to, step }) => {
+            msg.integer_range = [msg::rmw::IntegerRange {
+                from_value: from,
+                to_value: to,
+                step,
+            }]
+            .into_iter()
+            .collect();
+        }
+        Some(ParameterRange::Double { from, to, step }) => {
+            msg.floating_point_range = [msg::rmw::FloatingPointRange {
+                from_value: from,
+                to_value: to,
+                step,
+            }]
+            .into_iter()
+            .collect();
+        }
+        None => {}
+    }
+    msg
+}
 
-    pub fn services(&self) -> Vec<Weak<dyn ServiceBase>> {
-        vec![
-            Arc::downgrade(&self.describe_parameters_service) as Weak<dyn ServiceBase>,
-            Arc::downgrade(&self.get_parameter_types_service) as Weak<dyn ServiceBase>,
-            Arc::downgrade(&self.get_parameters_service) as Weak<dyn ServiceBase>,
-            Arc::downgrade(&self.list_parameters_service) as Weak<dyn ServiceBase>,
-            Arc::downgrade(&self.set_parameters_service) as Weak<dyn ServiceBase>,
-            Arc::downgrade(&self.set_parameters_atomically_service) as Weak<dyn ServiceBase>,
-        ]
+fn result_to_msg(result: Result<(), String>) -> SetParametersResult {
+    match result {
+        Ok(()) => SetParametersResult {
+            successful: true,
+            reason: String::new(),
+        },
+        Err(reason) => SetParametersResult {
+            successful: false,
+            reason,
+        },
     }
 }
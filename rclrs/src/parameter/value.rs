@@ -1,6 +1,10 @@
+use std::error::Error;
 use std::ffi::CStr;
+use std::fmt::{self, Display};
+use std::sync::Arc;
 
 use crate::rcl_bindings::*;
+use crate::vendor::rcl_interfaces::msg::{self, ParameterType};
 
 /// A parameter value.
 ///
@@ -49,12 +53,259 @@ pub enum ParameterValue {
     StringArray(Vec<String>),
 }
 
+/// The type of a [`ParameterValue`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ParameterKind {
+    /// The type of [`ParameterValue::Bool`].
+    Bool,
+    /// The type of [`ParameterValue::Integer`].
+    Integer,
+    /// The type of [`ParameterValue::Double`].
+    Double,
+    /// The type of [`ParameterValue::String`].
+    String,
+    /// The type of [`ParameterValue::ByteArray`].
+    ByteArray,
+    /// The type of [`ParameterValue::BoolArray`].
+    BoolArray,
+    /// The type of [`ParameterValue::IntegerArray`].
+    IntegerArray,
+    /// The type of [`ParameterValue::DoubleArray`].
+    DoubleArray,
+    /// The type of [`ParameterValue::StringArray`].
+    StringArray,
+}
+
+impl Display for ParameterKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The same names as in the ros2 param command line tool
+        let name = match self {
+            ParameterKind::Bool => "bool",
+            ParameterKind::Integer => "integer",
+            ParameterKind::Double => "double",
+            ParameterKind::String => "string",
+            ParameterKind::ByteArray => "byte_array",
+            ParameterKind::BoolArray => "bool_array",
+            ParameterKind::IntegerArray => "integer_array",
+            ParameterKind::DoubleArray => "double_array",
+            ParameterKind::StringArray => "string_array",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl ParameterKind {
+    // The corresponding constant of the ParameterType message
+    pub(crate) fn to_parameter_type(self) -> u8 {
+        match self {
+            ParameterKind::Bool => ParameterType::PARAMETER_BOOL,
+            ParameterKind::Integer => ParameterType::PARAMETER_INTEGER,
+            ParameterKind::Double => ParameterType::PARAMETER_DOUBLE,
+            ParameterKind::String => ParameterType::PARAMETER_STRING,
+            ParameterKind::ByteArray => ParameterType::PARAMETER_BYTE_ARRAY,
+            ParameterKind::BoolArray => ParameterType::PARAMETER_BOOL_ARRAY,
+            ParameterKind::IntegerArray => ParameterType::PARAMETER_INTEGER_ARRAY,
+            ParameterKind::DoubleArray => ParameterType::PARAMETER_DOUBLE_ARRAY,
+            ParameterKind::StringArray => ParameterType::PARAMETER_STRING_ARRAY,
+        }
+    }
+
+    // The inverse of to_parameter_type(), which is None for PARAMETER_NOT_SET and unknown types
+    pub(crate) fn from_parameter_type(type_id: u8) -> Option<Self> {
+        let kind = match type_id {
+            ParameterType::PARAMETER_BOOL => ParameterKind::Bool,
+            ParameterType::PARAMETER_INTEGER => ParameterKind::Integer,
+            ParameterType::PARAMETER_DOUBLE => ParameterKind::Double,
+            ParameterType::PARAMETER_STRING => ParameterKind::String,
+            ParameterType::PARAMETER_BYTE_ARRAY => ParameterKind::ByteArray,
+            ParameterType::PARAMETER_BOOL_ARRAY => ParameterKind::BoolArray,
+            ParameterType::PARAMETER_INTEGER_ARRAY => ParameterKind::IntegerArray,
+            ParameterType::PARAMETER_DOUBLE_ARRAY => ParameterKind::DoubleArray,
+            ParameterType::PARAMETER_STRING_ARRAY => ParameterKind::StringArray,
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+/// An error when converting a [`ParameterValue`] from or into another type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParameterValueError {
+    /// The value has a different type than the requested one.
+    WrongType {
+        /// The requested type.
+        expected: ParameterKind,
+        /// The type of the value.
+        actual: ParameterKind,
+    },
+    /// A `ParameterValue` message has the type `PARAMETER_NOT_SET` or an unknown type.
+    InvalidType {
+        /// The type field of the message.
+        type_id: u8,
+    },
+    /// A YAML value or parameter file could not be parsed.
+    InvalidYaml {
+        /// Why the YAML is invalid, as reported by `rcl_yaml_param_parser`.
+        reason: String,
+    },
+}
+
+impl Display for ParameterValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParameterValueError::WrongType { expected, actual } => write!(
+                f,
+                "Wrong parameter type, expected '{}' but got '{}'",
+                expected, actual
+            ),
+            ParameterValueError::InvalidType { type_id } => {
+                if *type_id == ParameterType::PARAMETER_NOT_SET {
+                    write!(f, "The parameter value is not set")
+                } else {
+                    write!(f, "Unknown parameter type {}", type_id)
+                }
+            }
+            ParameterValueError::InvalidYaml { reason } => write!(f, "Invalid YAML: {}", reason),
+        }
+    }
+}
+
+impl Error for ParameterValueError {}
+
+macro_rules! impl_conversions {
+    ($type:ty, $variant:ident) => {
+        impl From<$type> for ParameterValue {
+            fn from(value: $type) -> Self {
+                ParameterValue::$variant(value)
+            }
+        }
+
+        impl TryFrom<ParameterValue> for $type {
+            type Error = ParameterValueError;
+
+            fn try_from(value: ParameterValue) -> Result<Self, Self::Error> {
+                match value {
+                    ParameterValue::$variant(value) => Ok(value),
+                    other => Err(ParameterValueError::WrongType {
+                        expected: ParameterKind::$variant,
+                        actual: other.kind(),
+                    }),
+                }
+            }
+        }
+    };
+    // Arrays can also be converted from and into shared slices
+    ($element:ty, $variant:ident, array) => {
+        impl_conversions!(Vec<$element>, $variant);
+
+        impl From<Arc<[$element]>> for ParameterValue {
+            fn from(values: Arc<[$element]>) -> Self {
+                ParameterValue::$variant(values.to_vec())
+            }
+        }
+
+        impl TryFrom<ParameterValue> for Arc<[$element]> {
+            type Error = ParameterValueError;
+
+            fn try_from(value: ParameterValue) -> Result<Self, Self::Error> {
+                Vec::<$element>::try_from(value).map(Arc::from)
+            }
+        }
+    };
+}
+
+impl_conversions!(bool, Bool);
+impl_conversions!(i64, Integer);
+impl_conversions!(f64, Double);
+impl_conversions!(String, String);
+impl_conversions!(u8, ByteArray, array);
+impl_conversions!(bool, BoolArray, array);
+impl_conversions!(i64, IntegerArray, array);
+impl_conversions!(f64, DoubleArray, array);
+impl_conversions!(String, StringArray, array);
+
+impl From<&str> for ParameterValue {
+    fn from(value: &str) -> Self {
+        ParameterValue::String(value.to_owned())
+    }
+}
+
+impl From<ParameterValue> for msg::ParameterValue {
+    fn from(value: ParameterValue) -> Self {
+        let mut msg = msg::ParameterValue {
+            type_: value.kind().to_parameter_type(),
+            ..Default::default()
+        };
+        match value {
+            ParameterValue::Bool(value) => msg.bool_value = value,
+            ParameterValue::Integer(value) => msg.integer_value = value,
+            ParameterValue::Double(value) => msg.double_value = value,
+            ParameterValue::String(value) => msg.string_value = value,
+            ParameterValue::ByteArray(values) => msg.byte_array_value = values,
+            ParameterValue::BoolArray(values) => msg.bool_array_value = values,
+            ParameterValue::IntegerArray(values) => msg.integer_array_value = values,
+            ParameterValue::DoubleArray(values) => msg.double_array_value = values,
+            ParameterValue::StringArray(values) => msg.string_array_value = values,
+        }
+        msg
+    }
+}
+
+impl TryFrom<msg::ParameterValue> for ParameterValue {
+    type Error = ParameterValueError;
+
+    // Only the field selected by the type is used, like in rclcpp
+    fn try_from(msg: msg::ParameterValue) -> Result<Self, Self::Error> {
+        let value = match msg.type_ {
+            ParameterType::PARAMETER_BOOL => ParameterValue::Bool(msg.bool_value),
+            ParameterType::PARAMETER_INTEGER => ParameterValue::Integer(msg.integer_value),
+            ParameterType::PARAMETER_DOUBLE => ParameterValue::Double(msg.double_value),
+            ParameterType::PARAMETER_STRING => ParameterValue::String(msg.string_value),
+            ParameterType::PARAMETER_BYTE_ARRAY => ParameterValue::ByteArray(msg.byte_array_value),
+            ParameterType::PARAMETER_BOOL_ARRAY => ParameterValue::BoolArray(msg.bool_array_value),
+            ParameterType::PARAMETER_INTEGER_ARRAY => {
+                ParameterValue::IntegerArray(msg.integer_array_value)
+            }
+            ParameterType::PARAMETER_DOUBLE_ARRAY => {
+                ParameterValue::DoubleArray(msg.double_array_value)
+            }
+            ParameterType::PARAMETER_STRING_ARRAY => {
+                ParameterValue::StringArray(msg.string_array_value)
+            }
+            type_id => return Err(ParameterValueError::InvalidType { type_id }),
+        };
+        Ok(value)
+    }
+}
+
 impl ParameterValue {
+    /// Returns the type of the value.
+    pub fn kind(&self) -> ParameterKind {
+        match self {
+            ParameterValue::Bool(_) => ParameterKind::Bool,
+            ParameterValue::Integer(_) => ParameterKind::Integer,
+            ParameterValue::Double(_) => ParameterKind::Double,
+            ParameterValue::String(_) => ParameterKind::String,
+            ParameterValue::ByteArray(_) => ParameterKind::ByteArray,
+            ParameterValue::BoolArray(_) => ParameterKind::BoolArray,
+            ParameterValue::IntegerArray(_) => ParameterKind::IntegerArray,
+            ParameterValue::DoubleArray(_) => ParameterKind::DoubleArray,
+            ParameterValue::StringArray(_) => ParameterKind::StringArray,
+        }
+    }
+
     // Panics if the rcl_variant_t does not have exactly one field set.
     //
     // This function is unsafe because it is possible to pass in an rcl_variant_t
     // containing dangling pointers, or incorrect array sizes.
     pub(crate) unsafe fn from_rcl_variant(var: &rcl_variant_t) -> Self {
+        Self::try_from_rcl_variant(var).expect("The rcl_variant_t must have exactly one value")
+    }
+
+    // Returns None if the rcl_variant_t does not have exactly one field set.
+    //
+    // This function is unsafe for the same reasons as from_rcl_variant().
+    pub(crate) unsafe fn try_from_rcl_variant(var: &rcl_variant_t) -> Option<Self> {
         let num_active: u8 = [
             !var.bool_value.is_null(),
             !var.integer_value.is_null(),
@@ -69,7 +320,9 @@ impl ParameterValue {
         .into_iter()
         .map(u8::from)
         .sum();
-        assert_eq!(num_active, 1);
+        if num_active != 1 {
+            return None;
+        }
         // Note: This code has no unsafe blocks because it is inside an unsafe function.
         // In general, the following operations are as safe as they can be, because
         // only non-null pointers are dereferenced, and strings and arrays are copied immediately,
@@ -78,7 +331,7 @@ impl ParameterValue {
         // Of course, a pointer being not null is not a guarantee that it points to a valid value.
         // However, it cannot be checked that it points to a valid value. Similarly for array sizes.
         // This is why this function must be unsafe itself.
-        if !var.bool_value.is_null() {
+        let value = if !var.bool_value.is_null() {
             ParameterValue::Bool(*var.bool_value)
         } else if !var.integer_value.is_null() {
             ParameterValue::Integer(*var.integer_value)
@@ -120,8 +373,68 @@ impl ParameterValue {
             ParameterValue::StringArray(strings)
         } else {
             unreachable!()
+        };
+        Some(value)
+    }
+
+    /// Formats the value as YAML.
+    ///
+    /// The YAML is parsed back into the same value by [`ParameterValue::from_yaml()`], i.e. by
+    /// `rcl`, except for byte arrays, which are written as integer arrays, and empty arrays, which
+    /// have no type.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::ParameterValue;
+    /// let value = ParameterValue::from(vec![String::from("a"), String::from("b")]);
+    /// assert_eq!(value.to_yaml(), r#"["a", "b"]"#);
+    /// ```
+    pub fn to_yaml(&self) -> String {
+        fn array<T>(values: &[T], to_yaml: impl Fn(&T) -> String) -> String {
+            let values: Vec<_> = values.iter().map(to_yaml).collect();
+            format!("[{}]", values.join(", "))
+        }
+        match self {
+            ParameterValue::Bool(value) => value.to_string(),
+            ParameterValue::Integer(value) => value.to_string(),
+            ParameterValue::Double(value) => double_to_yaml(value),
+            ParameterValue::String(value) => string_to_yaml(value),
+            ParameterValue::ByteArray(values) => array(values, u8::to_string),
+            ParameterValue::BoolArray(values) => array(values, bool::to_string),
+            ParameterValue::IntegerArray(values) => array(values, i64::to_string),
+            ParameterValue::DoubleArray(values) => array(values, double_to_yaml),
+            ParameterValue::StringArray(values) => array(values, |value| string_to_yaml(value)),
+        }
+    }
+}
+
+fn double_to_yaml(value: &f64) -> String {
+    if value.is_nan() {
+        String::from(".nan")
+    } else if value.is_infinite() {
+        String::from(if *value > 0.0 { ".inf" } else { "-.inf" })
+    } else {
+        // The debug representation always contains a decimal point or an exponent, so the value
+        // is not parsed as an integer
+        format!("{:?}", value)
+    }
+}
+
+fn string_to_yaml(value: &str) -> String {
+    // A double-quoted string is never parsed as another type
+    let mut yaml = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => yaml.push_str("\\\""),
+            '\\' => yaml.push_str("\\\\"),
+            '\n' => yaml.push_str("\\n"),
+            '\t' => yaml.push_str("\\t"),
+            c if c.is_control() => yaml.push_str(&format!("\\u{:04X}", c as u32)),
+            c => yaml.push(c),
         }
     }
+    yaml.push('"');
+    yaml
 }
 
 #[cfg(test)]
@@ -171,4 +484,80 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(ParameterValue::from(true), ParameterValue::Bool(true));
+        assert_eq!(
+            ParameterValue::from("a"),
+            ParameterValue::String(String::from("a"))
+        );
+        let shared: Arc<[i64]> = Arc::from([1, 2]);
+        assert_eq!(
+            ParameterValue::from(shared.clone()),
+            ParameterValue::IntegerArray(vec![1, 2])
+        );
+
+        assert_eq!(i64::try_from(ParameterValue::Integer(3)), Ok(3));
+        assert_eq!(
+            Arc::<[i64]>::try_from(ParameterValue::IntegerArray(vec![1, 2])),
+            Ok(shared)
+        );
+        assert_eq!(
+            Vec::<u8>::try_from(ParameterValue::ByteArray(vec![7])),
+            Ok(vec![7])
+        );
+        assert_eq!(
+            f64::try_from(ParameterValue::Integer(3)),
+            Err(ParameterValueError::WrongType {
+                expected: ParameterKind::Double,
+                actual: ParameterKind::Integer,
+            })
+        );
+        assert_eq!(
+            String::try_from(ParameterValue::StringArray(vec![]))
+                .unwrap_err()
+                .to_string(),
+            "Wrong parameter type, expected 'string' but got 'string_array'"
+        );
+    }
+
+    #[test]
+    fn test_message_conversions() {
+        let values = [
+            ParameterValue::Bool(true),
+            ParameterValue::Integer(-3),
+            ParameterValue::Double(0.5),
+            ParameterValue::String(String::from("foo")),
+            ParameterValue::ByteArray(vec![1, 2]),
+            ParameterValue::BoolArray(vec![false]),
+            ParameterValue::IntegerArray(vec![4, 5]),
+            ParameterValue::DoubleArray(vec![1.5]),
+            ParameterValue::StringArray(vec![String::from("bar")]),
+        ];
+        for value in values {
+            let msg = msg::ParameterValue::from(value.clone());
+            assert_eq!(msg.type_, value.kind().to_parameter_type());
+            assert_eq!(
+                ParameterKind::from_parameter_type(msg.type_),
+                Some(value.kind())
+            );
+            assert_eq!(ParameterValue::try_from(msg), Ok(value));
+        }
+        assert_eq!(
+            ParameterKind::from_parameter_type(ParameterType::PARAMETER_NOT_SET),
+            None
+        );
+        assert_eq!(
+            ParameterValue::try_from(msg::ParameterValue::default()),
+            Err(ParameterValueError::InvalidType {
+                type_id: ParameterType::PARAMETER_NOT_SET
+            })
+        );
+        let msg = msg::ParameterValue {
+            type_: 42,
+            ..Default::default()
+        };
+        assert!(ParameterValue::try_from(msg).is_err());
+    }
 }
@@ -0,0 +1,297 @@
+use std::collections::BTreeMap;
+use std::ffi::CString;
+use std::path::Path;
+
+use crate::error::take_error_message;
+use crate::rcl_bindings::*;
+use crate::{ParameterValue, ParameterValueError, RclNodeParamsIter, RclParamsIter};
+
+// YAML is parsed by rcl_yaml_param_parser, so that values and files are typed exactly like the
+// parameters given on the command line. It does not need a context, so it can be used without
+// a node.
+
+impl ParameterValue {
+    /// Parses a value from YAML, like the value of a `--param name:=value` argument.
+    ///
+    /// The type of the value is determined like in `rcl`: Quoted strings are always strings,
+    /// unquoted values are booleans, integers or doubles if they can be parsed as such, and
+    /// strings otherwise. Arrays are written as sequences, e.g. `[1, 2]`, and all their
+    /// elements must have the same type. Byte arrays can't be expressed in YAML.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::ParameterValue;
+    /// assert_eq!(ParameterValue::from_yaml("yes"), Ok(ParameterValue::Bool(true)));
+    /// assert_eq!(ParameterValue::from_yaml("'0x10'"), Ok(ParameterValue::from("0x10")));
+    /// assert_eq!(
+    ///     ParameterValue::from_yaml("[1.5, .inf]"),
+    ///     Ok(ParameterValue::DoubleArray(vec![1.5, f64::INFINITY]))
+    /// );
+    /// assert!(ParameterValue::from_yaml("[1, 2.0]").is_err());
+    /// ```
+    pub fn from_yaml(yaml: &str) -> Result<Self, ParameterValueError> {
+        // Any node and parameter name will do, the value is taken out again right away
+        let node_name = CString::new("/**").unwrap();
+        let param_name = CString::new("value").unwrap();
+        let yaml = to_cstring(yaml)?;
+        let params = RclParams::new()?;
+        unsafe {
+            // SAFETY: The strings are valid and only read by this function, and the params were
+            // initialized.
+            if !rcl_parse_yaml_value(
+                node_name.as_ptr(),
+                param_name.as_ptr(),
+                yaml.as_ptr(),
+                params.0,
+            ) {
+                return Err(parser_error());
+            }
+            // SAFETY: As above. The returned variant is owned by the params.
+            let variant =
+                rcl_yaml_node_struct_get(node_name.as_ptr(), param_name.as_ptr(), params.0);
+            // SAFETY: The variant was filled in by the parser, and is copied before the params
+            // are dropped.
+            variant
+                .as_ref()
+                .and_then(|variant| ParameterValue::try_from_rcl_variant(variant))
+                .ok_or_else(|| ParameterValueError::InvalidYaml {
+                    reason: String::from("The YAML does not contain a parameter value"),
+                })
+        }
+    }
+}
+
+/// Parses a parameter file into the parameters of each node.
+///
+/// The parameter file has the same format as for the `--params-file` command line argument, and
+/// as returned by [`Node::dump_parameters_yaml()`][1]. The keys of the returned map are the
+/// fully qualified node names, e.g. `/my_ns/my_node`, or `/**` for parameters that apply to all
+/// nodes. Nested parameters are flattened into names separated by dots, e.g. `a.b`.
+///
+/// # Example
+/// ```
+/// # use rclrs::{parse_parameters_file, ParameterValue};
+/// let yaml = r#"
+/// /my_ns:
+///   my_node:
+///     ros__parameters:
+///       rate: 10.0
+///       camera:
+///         frame_id: "camera_link"
+///         exposures:
+///           - 10
+///           - 20
+/// "#;
+/// let path = std::env::temp_dir().join("parse_parameters_file_example.yaml");
+/// std::fs::write(&path, yaml)?;
+/// let parameters = parse_parameters_file(&path)?;
+/// let node_parameters = &parameters["/my_ns/my_node"];
+/// assert_eq!(node_parameters["rate"], ParameterValue::Double(10.0));
+/// assert_eq!(node_parameters["camera.frame_id"], ParameterValue::from("camera_link"));
+/// assert_eq!(
+///     node_parameters["camera.exposures"],
+///     ParameterValue::IntegerArray(vec![10, 20])
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [1]: crate::Node::dump_parameters_yaml
+pub fn parse_parameters_file(
+    path: impl AsRef<Path>,
+) -> Result<BTreeMap<String, BTreeMap<String, ParameterValue>>, ParameterValueError> {
+    let path = to_cstring(&path.as_ref().to_string_lossy())?;
+    let params = RclParams::new()?;
+    let mut nodes = BTreeMap::new();
+    unsafe {
+        // SAFETY: The path is valid and only read by this function, and the params were
+        // initialized.
+        if !rcl_parse_yaml_file(path.as_ptr(), params.0) {
+            return Err(parser_error());
+        }
+        // SAFETY: The params were filled in by the parser, and are copied before they are
+        // dropped.
+        for (node_name, node_params) in RclParamsIter::new(params.0) {
+            let node_parameters: &mut BTreeMap<_, _> = nodes.entry(node_name).or_default();
+            for (param_name, variant) in RclNodeParamsIter::new(node_params) {
+                node_parameters.insert(param_name, ParameterValue::from_rcl_variant(variant));
+            }
+        }
+    }
+    Ok(nodes)
+}
+
+// Owns the parameters that are filled in by the parser
+struct RclParams(*mut rcl_params_t);
+
+impl RclParams {
+    fn new() -> Result<Self, ParameterValueError> {
+        // SAFETY: No preconditions for these functions.
+        let params = unsafe { rcl_yaml_node_struct_init(rcutils_get_default_allocator()) };
+        if params.is_null() {
+            return Err(parser_error());
+        }
+        Ok(Self(params))
+    }
+}
+
+impl Drop for RclParams {
+    fn drop(&mut self) {
+        // SAFETY: The params were initialized, and are not used anymore.
+        unsafe { rcl_yaml_node_struct_fini(self.0) };
+    }
+}
+
+fn to_cstring(s: &str) -> Result<CString, ParameterValueError> {
+    CString::new(s).map_err(|_| ParameterValueError::InvalidYaml {
+        reason: String::from("The YAML contains a nul byte"),
+    })
+}
+
+fn parser_error() -> ParameterValueError {
+    ParameterValueError::InvalidYaml {
+        reason: take_error_message().unwrap_or_else(|| String::from("Unknown error")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    fn parse_string(
+        yaml: &str,
+    ) -> Result<BTreeMap<String, BTreeMap<String, ParameterValue>>, ParameterValueError> {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "{}", yaml).unwrap();
+        parse_parameters_file(file.path())
+    }
+
+    #[test]
+    fn test_parse_value() {
+        let input_output_pairs = [
+            ("true", ParameterValue::Bool(true)),
+            ("1", ParameterValue::Integer(1)),
+            ("-30", ParameterValue::Integer(-30)),
+            ("1.0", ParameterValue::Double(1.0)),
+            ("-.inf", ParameterValue::Double(f64::NEG_INFINITY)),
+            ("'1.0'", ParameterValue::from("1.0")),
+            ("\"a\\\"b\"", ParameterValue::from("a\"b")),
+            ("björk", ParameterValue::from("björk")),
+            (
+                "[true, false]",
+                ParameterValue::BoolArray(vec![true, false]),
+            ),
+            ("[-3, 2]", ParameterValue::IntegerArray(vec![-3, 2])),
+            ("[-3.0, 2.0]", ParameterValue::DoubleArray(vec![-3.0, 2.0])),
+            (
+                "['yes', \"a, b\"]",
+                ParameterValue::StringArray(vec![String::from("yes"), String::from("a, b")]),
+            ),
+        ];
+        for (yaml, value) in input_output_pairs {
+            assert_eq!(ParameterValue::from_yaml(yaml), Ok(value), "{}", yaml);
+        }
+
+        for yaml in ["", "[1, 2.0]", "a\0b"] {
+            assert!(
+                matches!(
+                    ParameterValue::from_yaml(yaml),
+                    Err(ParameterValueError::InvalidYaml { .. })
+                ),
+                "{}",
+                yaml
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_parameter_file() -> Result<(), ParameterValueError> {
+        let yaml = r#"
+/**:
+  ros__parameters:
+    use_sim_time: true
+/my_ns:
+  my_node:
+    ros__parameters:
+      a: 1
+      b:
+        c: "c # not a comment"
+        d:
+        - 1.5
+        - 2.5   # a comment
+other_node:
+    ros__parameters:
+        f: 'f'
+"#;
+        let parameters = parse_string(yaml)?;
+        let expected = BTreeMap::from([
+            (
+                String::from("/**"),
+                BTreeMap::from([(String::from("use_sim_time"), ParameterValue::Bool(true))]),
+            ),
+            (
+                String::from("/my_ns/my_node"),
+                BTreeMap::from([
+                    (String::from("a"), ParameterValue::Integer(1)),
+                    (
+                        String::from("b.c"),
+                        ParameterValue::from("c # not a comment"),
+                    ),
+                    (
+                        String::from("b.d"),
+                        ParameterValue::DoubleArray(vec![1.5, 2.5]),
+                    ),
+                ]),
+            ),
+            (
+                String::from("/other_node"),
+                BTreeMap::from([(String::from("f"), ParameterValue::from("f"))]),
+            ),
+        ]);
+        assert_eq!(parameters, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_parameter_files() {
+        for yaml in [
+            "node:\n  ros__parameters:\n    a: [1, x]\n",
+            "node:\n  ros__parameters:\n    a: [\n",
+        ] {
+            assert!(
+                matches!(
+                    parse_string(yaml),
+                    Err(ParameterValueError::InvalidYaml { .. })
+                ),
+                "{}",
+                yaml
+            );
+        }
+        assert!(parse_parameters_file("/nonexistent/params.yaml").is_err());
+    }
+
+    #[test]
+    fn test_to_yaml_round_trip() -> Result<(), ParameterValueError> {
+        let values = [
+            ParameterValue::Bool(false),
+            ParameterValue::Integer(i64::MIN),
+            ParameterValue::Double(1e-300),
+            ParameterValue::Double(-0.5),
+            ParameterValue::Double(f64::INFINITY),
+            ParameterValue::from("yes"),
+            ParameterValue::from("quote \" backslash \\ tab \t newline \n bell \u{7}"),
+            ParameterValue::from("# not a comment"),
+            ParameterValue::BoolArray(vec![true]),
+            ParameterValue::IntegerArray(vec![1, -2]),
+            ParameterValue::DoubleArray(vec![1.0, 2.5]),
+            ParameterValue::StringArray(vec![String::from("1"), String::from("a, 'b'")]),
+        ];
+        for value in values {
+            assert_eq!(ParameterValue::from_yaml(&value.to_yaml())?, value);
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use std::any::TypeId;
 use std::borrow::Cow;
 use std::ffi::CStr;
 use std::ffi::CString;
@@ -7,8 +8,10 @@ use std::sync::{Arc, Mutex};
 use rosidl_runtime_rs::{Message, RmwMessage};
 
 use crate::error::{RclrsError, ToResult};
+use crate::intra_process::{Endpoint, IntraProcessManager, IntraProcessPublisher};
 use crate::qos::QoSProfile;
 use crate::rcl_bindings::*;
+use crate::PublisherGid;
 
 mod loaned_message;
 pub use loaned_message::*;
@@ -24,9 +27,13 @@ unsafe impl Send for rcl_publisher_t {}
 /// The underlying RMW will decide on the concrete delivery mechanism (network stack, shared
 /// memory, or intraprocess).
 ///
+/// When the publisher's node uses intra-process communication, messages are passed directly to
+/// subscriptions in the same context, see [`NodeBuilder::use_intra_process_comms()`][2].
+///
 /// Sending messages does not require calling [`spin`][1] on the publisher's node.
 ///
 /// [1]: crate::spin
+/// [2]: crate::NodeBuilder::use_intra_process_comms
 pub struct Publisher<T>
 where
     T: Message,
@@ -36,6 +43,8 @@ where
     // The data pointed to by type_support_ptr has static lifetime;
     // it is global data in the type support library.
     type_support_ptr: *const rosidl_message_type_support_t,
+    intra_process: Option<Arc<IntraProcessPublisher>>,
+    qos: QoSProfile,
     message: PhantomData<T>,
 }
 
@@ -108,10 +117,40 @@ where
             rcl_publisher_mtx: Mutex::new(rcl_publisher),
             rcl_node_mtx,
             type_support_ptr,
+            intra_process: None,
+            qos,
             message: PhantomData,
         })
     }
 
+    /// Registers the publisher for intra-process communication.
+    pub(crate) fn enable_intra_process(
+        &mut self,
+        manager: &Arc<IntraProcessManager>,
+    ) -> Result<(), RclrsError> {
+        let mut rmw_gid = rmw_gid_t {
+            implementation_identifier: std::ptr::null(),
+            data: [0; RMW_GID_STORAGE_SIZE],
+        };
+        unsafe {
+            // SAFETY: The rcl_publisher is valid, so its rmw handle is valid as well.
+            let rmw_publisher =
+                rcl_publisher_get_rmw_handle(&*self.rcl_publisher_mtx.lock().unwrap());
+            rmw_get_gid_for_publisher(rmw_publisher, &mut rmw_gid).ok()?;
+        }
+        let endpoint = Endpoint {
+            topic: self.topic_name(),
+            type_id: TypeId::of::<T>(),
+            qos: self.qos,
+        };
+        let gid = PublisherGid {
+            data: rmw_gid.data,
+            implementation_identifier: rmw_gid.implementation_identifier,
+        };
+        self.intra_process = Some(IntraProcessPublisher::new(manager, endpoint, gid));
+        Ok(())
+    }
+
     /// Returns the topic name of the publisher.
     ///
     /// This returns the topic name after remapping, so it is not necessarily the
@@ -141,11 +180,56 @@ where
     /// Hence, when a message will not be needed anymore after publishing, pass it by value.
     /// When a message will be needed again after publishing, pass it by reference, instead of cloning and passing by value.
     ///
+    /// Messages for subscriptions that receive them intra-process are not serialized. An owned
+    /// message is moved to these subscriptions, while a borrowed message is cloned once.
+    ///
     /// Calling `publish()` is a potentially blocking call, see [this issue][1] for details.
     ///
     /// [1]: https://github.com/ros2/ros2/issues/255
     pub fn publish<'a, M: MessageCow<'a, T>>(&self, message: M) -> Result<(), RclrsError> {
-        let rmw_message = T::into_rmw_message(message.into_cow());
+        let message = message.into_cow();
+        let intra_process = match &self.intra_process {
+            Some(intra_process) => intra_process,
+            None => return self.publish_rmw(message),
+        };
+        // Subscriptions can't register until the message is published to the middleware, see
+        // IntraProcessSubscription::is_duplicate()
+        let _registrations = intra_process.block_registrations();
+        let intra_process_subscriptions = intra_process.subscriptions::<T>();
+        if intra_process_subscriptions.is_empty() {
+            return self.publish_rmw(message);
+        }
+        // The middleware also counts the intra-process subscriptions, but they may be matched
+        // after other subscriptions, so the counts can't tell whether there are other
+        // subscriptions. The intra-process subscriptions ignore the copies from the middleware.
+        let has_matched_subscriptions = self.get_subscription_count()? > 0;
+        let message = Arc::new(message.into_owned());
+        if has_matched_subscriptions {
+            self.publish_rmw(Cow::Borrowed(&*message))?;
+        }
+        intra_process.deliver(message, &intra_process_subscriptions)
+    }
+
+    /// Returns the number of subscriptions matched to this publisher.
+    ///
+    /// This includes subscriptions in the same process, whether or not they receive messages
+    /// intra-process.
+    pub fn get_subscription_count(&self) -> Result<usize, RclrsError> {
+        let mut subscription_count = 0;
+        unsafe {
+            // SAFETY: No preconditions for this function.
+            rcl_publisher_get_subscription_count(
+                &*self.rcl_publisher_mtx.lock().unwrap(),
+                &mut subscription_count,
+            )
+            .ok()?;
+        }
+        Ok(subscription_count)
+    }
+
+    fn publish_rmw(&self, message: Cow<'_, T>) -> Result<(), RclrsError> {
+        let rmw_message =
+            T::try_into_rmw_message(message).map_err(|err| RclrsError::InvalidMessage { err })?;
         let rcl_publisher = &mut *self.rcl_publisher_mtx.lock().unwrap();
         unsafe {
             // SAFETY: The message type is guaranteed to match the publisher type by the type system.
@@ -0,0 +1,96 @@
+use std::borrow::Cow;
+use std::os::raw::c_void;
+
+use rosidl_runtime_rs::{Message, RmwMessage};
+
+use crate::error::ToResult;
+use crate::rcl_bindings::*;
+use crate::RclrsError;
+
+/// A buffer for serialized messages that frees its memory when dropped.
+struct SerializedMessage {
+    rmw_serialized_message: rmw_serialized_message_t,
+}
+
+impl Drop for SerializedMessage {
+    fn drop(&mut self) {
+        // SAFETY: The array was initialized in SerializedMessage::new().
+        unsafe {
+            rcutils_uint8_array_fini(&mut self.rmw_serialized_message);
+        }
+    }
+}
+
+impl SerializedMessage {
+    fn new(capacity: usize) -> Result<Self, RclrsError> {
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut rmw_serialized_message = unsafe { rcutils_get_zero_initialized_uint8_array() };
+        // SAFETY: No preconditions for this function.
+        let allocator = unsafe { rcutils_get_default_allocator() };
+        // Allocating an empty buffer fails with some allocators
+        let capacity = capacity.max(1);
+        // SAFETY: The array is zero-initialized as expected by this function.
+        unsafe { rcutils_uint8_array_init(&mut rmw_serialized_message, capacity, &allocator) }
+            .ok()?;
+        Ok(Self {
+            rmw_serialized_message,
+        })
+    }
+}
+
+/// Serializes a message with the serialization format of the ROS middleware.
+///
+/// For the default middlewares, this is CDR with an encapsulation header, the same format as
+/// produced by `rosidl_shared::cdr::to_vec()`.
+pub fn serialize_message<T: Message>(message: &T) -> Result<Vec<u8>, RclrsError> {
+    let rmw_message = T::try_into_rmw_message(Cow::Borrowed(message))
+        .map_err(|err| RclrsError::InvalidMessage { err })?;
+    let type_support =
+        <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t;
+    // The buffer is grown by the middleware as needed
+    let mut serialized_message = SerializedMessage::new(0)?;
+    unsafe {
+        // SAFETY: The message and type support match, and the serialized message is initialized.
+        rmw_serialize(
+            rmw_message.as_ref() as *const T::RmwMsg as *const c_void,
+            type_support,
+            &mut serialized_message.rmw_serialized_message,
+        )
+        .ok()?;
+    }
+    let rmw_serialized_message = &serialized_message.rmw_serialized_message;
+    // SAFETY: The buffer contains buffer_length initialized bytes.
+    let bytes = unsafe {
+        std::slice::from_raw_parts(
+            rmw_serialized_message.buffer,
+            rmw_serialized_message.buffer_length,
+        )
+    };
+    Ok(bytes.to_vec())
+}
+
+/// Deserializes a message that was serialized with the serialization format of the ROS middleware.
+///
+/// This is the inverse of [`serialize_message()`].
+pub fn deserialize_message<T: Message>(bytes: &[u8]) -> Result<T, RclrsError> {
+    let mut serialized_message = SerializedMessage::new(bytes.len())?;
+    let rmw_serialized_message = &mut serialized_message.rmw_serialized_message;
+    // SAFETY: The buffer has a capacity of at least bytes.len() bytes.
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), rmw_serialized_message.buffer, bytes.len());
+    }
+    rmw_serialized_message.buffer_length = bytes.len();
+    let type_support =
+        <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t;
+    let mut rmw_message = <T as Message>::RmwMsg::default();
+    unsafe {
+        // SAFETY: The message and type support match, and the serialized message is initialized.
+        rmw_deserialize(
+            rmw_serialized_message,
+            type_support,
+            &mut rmw_message as *mut T::RmwMsg as *mut c_void,
+        )
+        .ok()?;
+    }
+    T::try_from_rmw_message(rmw_message).map_err(|err| RclrsError::InvalidMessage { err })
+}
@@ -1,12 +1,15 @@
 use std::boxed::Box;
 use std::ffi::CString;
+use std::marker::PhantomData;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex, MutexGuard};
 
 use rosidl_runtime_rs::Message;
 
+#[cfg(not(ros_distro = "humble"))]
+use crate::clock::ClockHandle;
 use crate::error::{RclReturnCode, ToResult};
-use crate::{rcl_bindings::*, MessageCow, RclrsError};
+use crate::{rcl_bindings::*, MessageCow, QoSProfile, RclrsError};
 
 // SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
 // they are running in. Therefore, this type can be safely sent to another thread.
@@ -17,6 +20,10 @@ pub struct ServiceHandle {
     rcl_service_mtx: Mutex<rcl_service_t>,
     rcl_node_mtx: Arc<Mutex<rcl_node_t>>,
     pub(crate) in_use_by_wait_set: Arc<AtomicBool>,
+    // The clock used to stamp service events, created when introspection is first configured.
+    // It is dropped after the service, which holds a pointer to it.
+    #[cfg(not(ros_distro = "humble"))]
+    introspection_clock: Mutex<Option<ClockHandle>>,
 }
 
 impl ServiceHandle {
@@ -46,8 +53,108 @@ pub trait ServiceBase: Send + Sync {
     fn execute(&self) -> Result<(), RclrsError>;
 }
 
-type ServiceCallback<Request, Response> =
-    Box<dyn Fn(&rmw_request_id_t, Request) -> Response + 'static + Send>;
+/// The amount of information published by service introspection.
+///
+/// When enabled, an event is published on the `<service>/_service_event` topic for every request
+/// and response going through a service or client. These events can be inspected with
+/// `ros2 service echo`.
+///
+/// Service introspection is not available on ROS 2 Humble.
+#[cfg(not(ros_distro = "humble"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServiceIntrospectionState {
+    /// No service events are published.
+    Off,
+    /// Only the metadata of requests and responses is published, not their contents.
+    Metadata,
+    /// Both the metadata and the contents of requests and responses are published.
+    Contents,
+}
+
+#[cfg(not(ros_distro = "humble"))]
+impl From<ServiceIntrospectionState> for rcl_service_introspection_state_t {
+    fn from(state: ServiceIntrospectionState) -> Self {
+        match state {
+            ServiceIntrospectionState::Off => {
+                rcl_service_introspection_state_t::RCL_SERVICE_INTROSPECTION_OFF
+            }
+            ServiceIntrospectionState::Metadata => {
+                rcl_service_introspection_state_t::RCL_SERVICE_INTROSPECTION_METADATA
+            }
+            ServiceIntrospectionState::Contents => {
+                rcl_service_introspection_state_t::RCL_SERVICE_INTROSPECTION_CONTENTS
+            }
+        }
+    }
+}
+
+/// The callback of a [`Service`].
+pub enum AnyServiceCallback<T>
+where
+    T: rosidl_runtime_rs::Service,
+{
+    /// A callback that computes the response right away.
+    Regular(Box<dyn Fn(&rmw_request_id_t, T::Request) -> T::Response + 'static + Send>),
+    /// A callback that receives a [`ServiceResponder`] to send the response with later.
+    Deferred(Box<dyn Fn(T::Request, ServiceResponder<T>) + 'static + Send>),
+}
+
+/// A handle for responding to a single request after the service callback has returned.
+///
+/// A `ServiceResponder` is passed to the callback of a service created with
+/// [`Node::create_deferred_service()`][1]. It can be sent to another thread, e.g. to compute
+/// the response in a worker thread or to first call another service, and the response is
+/// sent when [`ServiceResponder::send()`] is called.
+///
+/// If the responder is dropped without sending a response, the client will not receive one.
+///
+/// [1]: crate::Node::create_deferred_service
+pub struct ServiceResponder<T>
+where
+    T: rosidl_runtime_rs::Service,
+{
+    handle: Arc<ServiceHandle>,
+    request_id: rmw_request_id_t,
+    response: PhantomData<T::Response>,
+}
+
+impl<T> ServiceResponder<T>
+where
+    T: rosidl_runtime_rs::Service,
+{
+    /// Returns the ID of the request that this responder answers.
+    pub fn request_id(&self) -> &rmw_request_id_t {
+        &self.request_id
+    }
+
+    /// Sends the response to the client that made the request.
+    pub fn send(mut self, response: T::Response) -> Result<(), RclrsError> {
+        send_response::<T>(&self.handle, &mut self.request_id, response)
+    }
+}
+
+// Helper used by Service::send_response() and ServiceResponder::send().
+fn send_response<T>(
+    handle: &ServiceHandle,
+    request_id: &mut rmw_request_id_t,
+    response: T::Response,
+) -> Result<(), RclrsError>
+where
+    T: rosidl_runtime_rs::Service,
+{
+    let rmw_message = <T::Response as Message>::try_into_rmw_message(response.into_cow())
+        .map_err(|err| RclrsError::InvalidMessage { err })?;
+    let handle = &*handle.lock();
+    unsafe {
+        // SAFETY: The response type is guaranteed to match the service type by the type system.
+        rcl_send_response(
+            handle,
+            request_id,
+            rmw_message.as_ref() as *const <T::Response as Message>::RmwMsg as *mut _,
+        )
+    }
+    .ok()
+}
 
 /// Main class responsible for responding to requests sent by ROS clients.
 ///
@@ -62,7 +169,7 @@ where
 {
     pub(crate) handle: Arc<ServiceHandle>,
     /// The callback function that runs when a request was received.
-    pub callback: Mutex<ServiceCallback<T::Request, T::Response>>,
+    pub callback: Mutex<AnyServiceCallback<T>>,
 }
 
 impl<T> Service<T>
@@ -70,16 +177,16 @@ where
     T: rosidl_runtime_rs::Service,
 {
     /// Creates a new service.
-    pub(crate) fn new<F>(
+    pub(crate) fn new(
         rcl_node_mtx: Arc<Mutex<rcl_node_t>>,
         topic: &str,
-        callback: F,
+        qos: QoSProfile,
+        callback: AnyServiceCallback<T>,
     ) -> Result<Self, RclrsError>
     // This uses pub(crate) visibility to avoid instantiating this struct outside
     // [`Node::create_service`], see the struct's documentation for the rationale
     where
         T: rosidl_runtime_rs::Service,
-        F: Fn(&rmw_request_id_t, T::Request) -> T::Response + 'static + Send,
     {
         // SAFETY: Getting a zero-initialized value is always safe.
         let mut rcl_service = unsafe { rcl_get_zero_initialized_service() };
@@ -91,7 +198,8 @@ where
         })?;
 
         // SAFETY: No preconditions for this function.
-        let service_options = unsafe { rcl_service_get_default_options() };
+        let mut service_options = unsafe { rcl_service_get_default_options() };
+        service_options.qos = qos.into();
 
         unsafe {
             // SAFETY: The rcl_service is zero-initialized as expected by this function.
@@ -112,11 +220,13 @@ where
             rcl_service_mtx: Mutex::new(rcl_service),
             rcl_node_mtx,
             in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
+            #[cfg(not(ros_distro = "humble"))]
+            introspection_clock: Mutex::new(None),
         });
 
         Ok(Self {
             handle,
-            callback: Mutex::new(Box::new(callback)),
+            callback: Mutex::new(callback),
         })
     }
 
@@ -160,7 +270,62 @@ where
             )
         }
         .ok()?;
-        Ok((T::Request::from_rmw_message(request_out), request_id_out))
+        let request = T::Request::try_from_rmw_message(request_out)
+            .map_err(|err| RclrsError::InvalidMessage { err })?;
+        Ok((request, request_id_out))
+    }
+
+    /// Sends a response to the request with the given ID.
+    ///
+    /// Together with [`Service::take_request()`], this allows answering requests manually, e.g.
+    /// in a custom loop that does not use an executor.
+    pub fn send_response(
+        &self,
+        mut request_id: rmw_request_id_t,
+        response: T::Response,
+    ) -> Result<(), RclrsError> {
+        send_response::<T>(&self.handle, &mut request_id, response)
+    }
+
+    /// Configures service introspection for this service.
+    ///
+    /// Service events are published on the `<service>/_service_event` topic with the given QoS
+    /// profile, see [`ServiceIntrospectionState`] for the available levels of detail.
+    /// Events are stamped with the system time.
+    ///
+    /// This can be called repeatedly, e.g. to turn introspection off again.
+    #[cfg(not(ros_distro = "humble"))]
+    pub fn configure_introspection(
+        &self,
+        state: ServiceIntrospectionState,
+        qos: QoSProfile,
+    ) -> Result<(), RclrsError> {
+        let type_support = <T as rosidl_runtime_rs::Service>::get_type_support()
+            as *const rosidl_service_type_support_t;
+        // SAFETY: No preconditions for this function.
+        let mut publisher_options = unsafe { rcl_publisher_get_default_options() };
+        publisher_options.qos = qos.into();
+
+        let mut introspection_clock = self.handle.introspection_clock.lock().unwrap();
+        if introspection_clock.is_none() {
+            *introspection_clock = Some(ClockHandle::new(rcl_clock_type_t::RCL_SYSTEM_TIME)?);
+        }
+        let clock = introspection_clock.as_mut().unwrap();
+        let rcl_service = &mut *self.handle.lock();
+        let rcl_node = &mut *self.handle.rcl_node_mtx.lock().unwrap();
+        unsafe {
+            // SAFETY: The service and node are valid. The clock is owned by the service handle
+            // and outlives the service. The publisher options are copied by this function.
+            rcl_service_configure_service_introspection(
+                rcl_service,
+                rcl_node,
+                clock.as_mut_ptr(),
+                type_support,
+                publisher_options,
+                state.into(),
+            )
+            .ok()
+        }
     }
 }
 
@@ -173,7 +338,7 @@ where
     }
 
     fn execute(&self) -> Result<(), RclrsError> {
-        let (req, mut req_id) = match self.take_request() {
+        let (req, req_id) = match self.take_request() {
             Ok((req, req_id)) => (req, req_id),
             Err(RclrsError::RclError {
                 code: RclReturnCode::ServiceTakeFailed,
@@ -185,17 +350,20 @@ where
             }
             Err(e) => return Err(e),
         };
-        let res = (*self.callback.lock().unwrap())(&req_id, req);
-        let rmw_message = <T::Response as Message>::into_rmw_message(res.into_cow());
-        let handle = &*self.handle.lock();
-        unsafe {
-            // SAFETY: The response type is guaranteed to match the service type by the type system.
-            rcl_send_response(
-                handle,
-                &mut req_id,
-                rmw_message.as_ref() as *const <T::Response as Message>::RmwMsg as *mut _,
-            )
+        match &*self.callback.lock().unwrap() {
+            AnyServiceCallback::Regular(callback) => {
+                let res = callback(&req_id, req);
+                self.send_response(req_id, res)
+            }
+            AnyServiceCallback::Deferred(callback) => {
+                let responder = ServiceResponder {
+                    handle: Arc::clone(&self.handle),
+                    request_id: req_id,
+                    response: PhantomData,
+                };
+                callback(req, responder);
+                Ok(())
+            }
         }
-        .ok()
     }
 }
@@ -1,21 +1,28 @@
+use std::any::TypeId;
 use std::ffi::CStr;
 use std::ffi::CString;
 use std::marker::PhantomData;
 use std::sync::atomic::AtomicBool;
-use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::time::SystemTime;
 
 use rosidl_runtime_rs::{Message, RmwMessage};
 
 use crate::error::{RclReturnCode, ToResult};
+use crate::intra_process::{Endpoint, IntraProcessManager, IntraProcessSubscription};
 use crate::qos::QoSProfile;
-use crate::{rcl_bindings::*, RclrsError};
+use crate::{rcl_bindings::*, GuardCondition, RclrsError};
 
 mod callback;
 mod message_info;
 mod readonly_loaned_message;
+mod sequence;
+mod statistics;
 pub use callback::*;
 pub use message_info::*;
 pub use readonly_loaned_message::*;
+pub use sequence::*;
+pub use statistics::*;
 
 // SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
 // they are running in. Therefore, this type can be safely sent to another thread.
@@ -51,6 +58,12 @@ pub trait SubscriptionBase: Send + Sync {
     fn handle(&self) -> &SubscriptionHandle;
     /// Tries to take a new message and run the callback with it.
     fn execute(&self) -> Result<(), RclrsError>;
+    /// Returns whether messages were delivered intra-process and are waiting to be executed.
+    ///
+    /// These messages do not make the `rcl` handle ready in a wait set.
+    fn has_intra_process_messages(&self) -> bool {
+        false
+    }
 }
 
 /// Struct for receiving messages of type `T`.
@@ -76,19 +89,39 @@ where
     pub(crate) handle: Arc<SubscriptionHandle>,
     /// The callback function that runs when a message was received.
     pub callback: Mutex<AnySubscriptionCallback<T>>,
+    intra_process: Option<Arc<IntraProcessSubscription<T>>>,
+    // The collectors of the TopicStatistics created for this subscription
+    statistics_collectors_mtx: Mutex<Vec<Weak<StatisticsCollector>>>,
+    // Only set when sequence tracking is enabled
+    sequence_tracker_mtx: Mutex<Option<SequenceTracker>>,
+    qos: QoSProfile,
     message: PhantomData<T>,
 }
 
+/// What a subscription needs to receive messages intra-process.
+pub(crate) struct IntraProcessOptions<'a> {
+    pub(crate) manager: &'a Arc<IntraProcessManager>,
+    /// The topic name after remapping.
+    pub(crate) topic: String,
+    /// Triggered whenever a message is delivered intra-process.
+    pub(crate) guard_condition: Arc<GuardCondition>,
+}
+
 impl<T> Subscription<T>
 where
     T: Message,
 {
     /// Creates a new subscription.
+    ///
+    /// If intra-process options are given, the subscription is registered for intra-process
+    /// communication. Subscriptions with a loaned message callback are not registered, since they
+    /// can only receive messages owned by the middleware.
     pub(crate) fn new<Args>(
         rcl_node_mtx: Arc<Mutex<rcl_node_t>>,
         topic: &str,
         qos: QoSProfile,
         callback: impl SubscriptionCallback<T, Args>,
+        intra_process: Option<IntraProcessOptions<'_>>,
     ) -> Result<Self, RclrsError>
     // This uses pub(crate) visibility to avoid instantiating this struct outside
     // [`Node::create_subscription`], see the struct's documentation for the rationale
@@ -104,6 +137,27 @@ where
             s: topic.into(),
         })?;
 
+        let callback = callback.into_callback();
+        // The subscription registers before it is created in the middleware, so that it takes
+        // only messages that were published after the registration, see
+        // IntraProcessSubscription::is_duplicate()
+        let intra_process = intra_process
+            .filter(|_| {
+                !matches!(
+                    callback,
+                    AnySubscriptionCallback::Loaned(_)
+                        | AnySubscriptionCallback::LoanedWithMessageInfo(_)
+                )
+            })
+            .map(|options| {
+                let endpoint = Endpoint {
+                    topic: options.topic,
+                    type_id: TypeId::of::<T>(),
+                    qos,
+                };
+                IntraProcessSubscription::new(options.manager, endpoint, options.guard_condition)
+            });
+
         // SAFETY: No preconditions for this function.
         let mut subscription_options = unsafe { rcl_subscription_get_default_options() };
         subscription_options.qos = qos.into();
@@ -129,13 +183,95 @@ where
             in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
         });
 
+        let sequence_tracker = matches!(
+            callback,
+            AnySubscriptionCallback::RegularWithSequenceStatus(_)
+        )
+        .then(SequenceTracker::new);
         Ok(Self {
             handle,
-            callback: Mutex::new(callback.into_callback()),
+            callback: Mutex::new(callback),
+            intra_process,
+            statistics_collectors_mtx: Mutex::new(Vec::new()),
+            sequence_tracker_mtx: Mutex::new(sequence_tracker),
+            qos,
             message: PhantomData,
         })
     }
 
+    /// Adds every message that is taken from now on to the statistics of the collector.
+    pub(crate) fn add_statistics_collector(&self, collector: &Arc<StatisticsCollector>) {
+        { self.statistics_collectors_mtx.lock().unwrap() }.push(Arc::downgrade(collector));
+    }
+
+    /// Starts tracking the sequence numbers of the publishers of this subscription.
+    ///
+    /// Every message that is taken from now on, including with [`Subscription::take()`], is
+    /// checked for gaps, duplicates and reordering. The counts are returned by
+    /// [`Subscription::sequence_statistics()`].
+    ///
+    /// Sequence tracking is enabled automatically for callbacks that take a [`SequenceStatus`]
+    /// argument. Enabling it again has no effect.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{MessageInfo, Node, RclrsError, SequenceStatus, QOS_PROFILE_DEFAULT};
+    /// fn subscribe<T: rosidl_runtime_rs::Message>(node: &Node) -> Result<(), RclrsError> {
+    ///     let subscription = node.create_subscription(
+    ///         "teleop",
+    ///         QOS_PROFILE_DEFAULT,
+    ///         |_msg: T, _info: MessageInfo, status: SequenceStatus| {
+    ///             if let SequenceStatus::Gap { lost } = status {
+    ///                 println!("Lost {} messages", lost);
+    ///             }
+    ///         },
+    ///     )?;
+    ///     assert!(subscription.sequence_statistics().is_some());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn enable_sequence_tracking(&self) {
+        self.sequence_tracker_mtx
+            .lock()
+            .unwrap()
+            .get_or_insert_with(SequenceTracker::new);
+    }
+
+    /// Returns the counts of lost, out-of-order and duplicate messages of all publishers, or
+    /// `None` if [sequence tracking][1] is not enabled.
+    ///
+    /// [1]: Subscription::enable_sequence_tracking
+    pub fn sequence_statistics(&self) -> Option<SequenceStatistics> {
+        self.sequence_tracker_mtx
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(SequenceTracker::statistics)
+    }
+
+    // Records a taken message in the statistics collectors that are still alive and in the
+    // sequence tracker, which returns the sequence status if it is enabled.
+    fn record_message(&self, message_info: &MessageInfo) -> Option<SequenceStatus> {
+        {
+            let collectors = &mut *self.statistics_collectors_mtx.lock().unwrap();
+            if !collectors.is_empty() {
+                let now = SystemTime::now();
+                collectors.retain(|collector| match collector.upgrade() {
+                    Some(collector) => {
+                        collector.record(message_info, now);
+                        true
+                    }
+                    None => false,
+                });
+            }
+        }
+        self.sequence_tracker_mtx
+            .lock()
+            .unwrap()
+            .as_mut()
+            .map(|tracker| tracker.track(message_info))
+    }
+
     /// Returns the topic name of the subscription.
     ///
     /// This returns the topic name after remapping, so it is not necessarily the
@@ -156,6 +292,9 @@ where
     /// When there is no new message, this will return a
     /// [`SubscriptionTakeFailed`][1].
     ///
+    /// Messages that were delivered intra-process are only passed to the callback, and are not
+    /// returned by this function.
+    ///
     /// [1]: crate::RclrsError
     //
     // ```text
@@ -174,9 +313,19 @@ where
     // +-------------+
     // ```
     pub fn take(&self) -> Result<(T, MessageInfo), RclrsError> {
+        let (msg, message_info, _) = self.take_with_sequence_status()?;
+        Ok((msg, message_info))
+    }
+
+    // Like take(), but also returns the sequence status if sequence tracking is enabled.
+    fn take_with_sequence_status(
+        &self,
+    ) -> Result<(T, MessageInfo, Option<SequenceStatus>), RclrsError> {
         let mut rmw_message = <T as Message>::RmwMsg::default();
-        let message_info = self.take_inner(&mut rmw_message)?;
-        Ok((T::from_rmw_message(rmw_message), message_info))
+        let (message_info, sequence_status) = self.take_inner(&mut rmw_message)?;
+        let message = T::try_from_rmw_message(rmw_message)
+            .map_err(|err| RclrsError::InvalidMessage { err })?;
+        Ok((message, message_info, sequence_status))
     }
 
     /// This is a version of take() that returns a boxed message.
@@ -184,10 +333,13 @@ where
     /// This can be more efficient for messages containing large arrays.
     pub fn take_boxed(&self) -> Result<(Box<T>, MessageInfo), RclrsError> {
         let mut rmw_message = Box::<<T as Message>::RmwMsg>::default();
-        let message_info = self.take_inner(&mut *rmw_message)?;
+        let (message_info, _) = self.take_inner(&mut *rmw_message)?;
         // TODO: This will still use the stack in general. Change signature of
         // from_rmw_message to allow placing the result in a Box directly.
-        let message = Box::new(T::from_rmw_message(*rmw_message));
+        let message = Box::new(
+            T::try_from_rmw_message(*rmw_message)
+                .map_err(|err| RclrsError::InvalidMessage { err })?,
+        );
         Ok((message, message_info))
     }
 
@@ -195,22 +347,66 @@ where
     fn take_inner(
         &self,
         rmw_message: &mut <T as Message>::RmwMsg,
-    ) -> Result<MessageInfo, RclrsError> {
-        let mut message_info = unsafe { rmw_get_zero_initialized_message_info() };
-        let rcl_subscription = &mut *self.handle.lock();
-        unsafe {
-            // SAFETY: The first two pointers are valid/initialized, and do not need to be valid
-            // beyond the function call.
-            // The latter two pointers are explicitly allowed to be NULL.
-            rcl_take(
-                rcl_subscription,
-                rmw_message as *mut <T as Message>::RmwMsg as *mut _,
-                &mut message_info,
-                std::ptr::null_mut(),
-            )
-            .ok()?
-        };
-        Ok(MessageInfo::from_rmw_message_info(&message_info))
+    ) -> Result<(MessageInfo, Option<SequenceStatus>), RclrsError> {
+        loop {
+            let mut message_info = unsafe { rmw_get_zero_initialized_message_info() };
+            let rcl_subscription = &mut *self.handle.lock();
+            unsafe {
+                // SAFETY: The first two pointers are valid/initialized, and do not need to be valid
+                // beyond the function call.
+                // The latter two pointers are explicitly allowed to be NULL.
+                rcl_take(
+                    rcl_subscription,
+                    rmw_message as *mut <T as Message>::RmwMsg as *mut _,
+                    &mut message_info,
+                    std::ptr::null_mut(),
+                )
+                .ok()?
+            };
+            let message_info = MessageInfo::from_rmw_message_info(&message_info);
+            match &self.intra_process {
+                // The message was already delivered intra-process
+                Some(intra_process) if intra_process.is_duplicate(&message_info) => continue,
+                _ => {
+                    let sequence_status = self.record_message(&message_info);
+                    return Ok((message_info, sequence_status));
+                }
+            }
+        }
+    }
+
+    // Runs the callback for all messages that were delivered intra-process.
+    fn execute_intra_process(&self, intra_process: &IntraProcessSubscription<T>) {
+        // Moves the message out of the Arc if no other subscription holds it anymore
+        fn into_owned<T: Message>(msg: Arc<T>) -> T {
+            Arc::try_unwrap(msg).unwrap_or_else(|msg| (*msg).clone())
+        }
+        let callback = &mut *self.callback.lock().unwrap();
+        while let Some((msg, msg_info)) = intra_process.pop() {
+            let sequence_status = self.record_message(&msg_info);
+            match callback {
+                AnySubscriptionCallback::Regular(cb) => cb(into_owned(msg)),
+                AnySubscriptionCallback::RegularWithMessageInfo(cb) => {
+                    cb(into_owned(msg), msg_info)
+                }
+                AnySubscriptionCallback::RegularWithSequenceStatus(cb) => {
+                    // Sequence tracking is only disabled if the callback was replaced after the
+                    // subscription was created
+                    let sequence_status = sequence_status.unwrap_or(SequenceStatus::Unsupported);
+                    cb(into_owned(msg), msg_info, sequence_status)
+                }
+                AnySubscriptionCallback::Boxed(cb) => cb(Box::new(into_owned(msg))),
+                AnySubscriptionCallback::BoxedWithMessageInfo(cb) => {
+                    cb(Box::new(into_owned(msg)), msg_info)
+                }
+                AnySubscriptionCallback::Shared(cb) => cb(msg),
+                AnySubscriptionCallback::SharedWithMessageInfo(cb) => cb(msg, msg_info),
+                // The callback was replaced after the subscription was created. Loaned messages
+                // can only be taken from the middleware.
+                AnySubscriptionCallback::Loaned(_)
+                | AnySubscriptionCallback::LoanedWithMessageInfo(_) => {}
+            }
+        }
     }
 
     /// Obtains a read-only handle to a message owned by the middleware.
@@ -237,14 +433,13 @@ where
             )
             .ok()?;
         }
+        let message_info = MessageInfo::from_rmw_message_info(&message_info);
+        self.record_message(&message_info);
         let read_only_loaned_msg = ReadOnlyLoanedMessage {
             msg_ptr: msg_ptr as *const T::RmwMsg,
             subscription: self,
         };
-        Ok((
-            read_only_loaned_msg,
-            MessageInfo::from_rmw_message_info(&message_info),
-        ))
+        Ok((read_only_loaned_msg, message_info))
     }
 }
 
@@ -257,6 +452,11 @@ where
     }
 
     fn execute(&self) -> Result<(), RclrsError> {
+        // Intra-process messages don't make the rcl subscription ready, so they are all executed
+        // here, and any message from the middleware is taken afterwards
+        if let Some(intra_process) = &self.intra_process {
+            self.execute_intra_process(intra_process);
+        }
         // Immediately evaluated closure, to handle SubscriptionTakeFailed
         // outside this match
         match (|| {
@@ -269,6 +469,12 @@ where
                     let (msg, msg_info) = self.take()?;
                     cb(msg, msg_info)
                 }
+                AnySubscriptionCallback::RegularWithSequenceStatus(cb) => {
+                    let (msg, msg_info, sequence_status) = self.take_with_sequence_status()?;
+                    // See execute_intra_process()
+                    let sequence_status = sequence_status.unwrap_or(SequenceStatus::Unsupported);
+                    cb(msg, msg_info, sequence_status)
+                }
                 AnySubscriptionCallback::Boxed(cb) => {
                     let (msg, _) = self.take_boxed()?;
                     cb(msg)
@@ -277,6 +483,14 @@ where
                     let (msg, msg_info) = self.take_boxed()?;
                     cb(msg, msg_info)
                 }
+                AnySubscriptionCallback::Shared(cb) => {
+                    let (msg, _) = self.take()?;
+                    cb(Arc::new(msg))
+                }
+                AnySubscriptionCallback::SharedWithMessageInfo(cb) => {
+                    let (msg, msg_info) = self.take()?;
+                    cb(Arc::new(msg), msg_info)
+                }
                 AnySubscriptionCallback::Loaned(cb) => {
                     let (msg, _) = self.take_loaned()?;
                     cb(msg)
@@ -299,4 +513,8 @@ where
             other => other,
         }
     }
+
+    fn has_intra_process_messages(&self) -> bool {
+        matches!(&self.intra_process, Some(intra_process) if intra_process.has_messages())
+    }
 }
@@ -1,6 +1,8 @@
+use std::sync::Arc;
+
 use rosidl_runtime_rs::Message;
 
-use super::MessageInfo;
+use super::{MessageInfo, SequenceStatus};
 use crate::ReadOnlyLoanedMessage;
 
 /// A trait for allowed callbacks for subscriptions.
@@ -27,10 +29,22 @@ where
     Regular(Box<dyn FnMut(T) + Send>),
     /// A callback with the message and the message info as arguments.
     RegularWithMessageInfo(Box<dyn FnMut(T, MessageInfo) + Send>),
+    /// A callback with the message, the message info and the sequence status as arguments.
+    ///
+    /// Using this callback enables [sequence tracking][1] for the subscription.
+    ///
+    /// [1]: crate::Subscription::enable_sequence_tracking
+    RegularWithSequenceStatus(Box<dyn FnMut(T, MessageInfo, SequenceStatus) + Send>),
     /// A callback with only the boxed message as an argument.
     Boxed(Box<dyn FnMut(Box<T>) + Send>),
     /// A callback with the boxed message and the message info as arguments.
     BoxedWithMessageInfo(Box<dyn FnMut(Box<T>, MessageInfo) + Send>),
+    /// A callback with only the shared message as an argument.
+    ///
+    /// Messages delivered intra-process are shared with other subscriptions instead of copied.
+    Shared(Box<dyn FnMut(Arc<T>) + Send>),
+    /// A callback with the shared message and the message info as arguments.
+    SharedWithMessageInfo(Box<dyn FnMut(Arc<T>, MessageInfo) + Send>),
     /// A callback with only the loaned message as an argument.
     #[allow(clippy::type_complexity)]
     Loaned(Box<dyn for<'a> FnMut(ReadOnlyLoanedMessage<'a, T>) + Send>),
@@ -62,6 +76,17 @@ where
     }
 }
 
+impl<T, A0, A1, A2, Func> SubscriptionCallback<T, (A0, A1, A2)> for Func
+where
+    Func: FnMut(A0, A1, A2) + Send + 'static,
+    (A0, A1, A2): ArgTuple<T, Func>,
+    T: Message,
+{
+    fn into_callback(self) -> AnySubscriptionCallback<T> {
+        <(A0, A1, A2) as ArgTuple<T, Func>>::into_callback_with_args(self)
+    }
+}
+
 // Helper trait for SubscriptionCallback.
 //
 // For each tuple of args, it provides conversion from a function with
@@ -93,6 +118,16 @@ where
     }
 }
 
+impl<T, Func> ArgTuple<T, Func> for (T, MessageInfo, SequenceStatus)
+where
+    T: Message,
+    Func: FnMut(T, MessageInfo, SequenceStatus) + Send + 'static,
+{
+    fn into_callback_with_args(func: Func) -> AnySubscriptionCallback<T> {
+        AnySubscriptionCallback::RegularWithSequenceStatus(Box::new(func))
+    }
+}
+
 impl<T, Func> ArgTuple<T, Func> for (Box<T>,)
 where
     T: Message,
@@ -113,6 +148,26 @@ where
     }
 }
 
+impl<T, Func> ArgTuple<T, Func> for (Arc<T>,)
+where
+    T: Message,
+    Func: FnMut(Arc<T>) + Send + 'static,
+{
+    fn into_callback_with_args(func: Func) -> AnySubscriptionCallback<T> {
+        AnySubscriptionCallback::Shared(Box::new(func))
+    }
+}
+
+impl<T, Func> ArgTuple<T, Func> for (Arc<T>, MessageInfo)
+where
+    T: Message,
+    Func: FnMut(Arc<T>, MessageInfo) + Send + 'static,
+{
+    fn into_callback_with_args(func: Func) -> AnySubscriptionCallback<T> {
+        AnySubscriptionCallback::SharedWithMessageInfo(Box::new(func))
+    }
+}
+
 impl<T, Func> ArgTuple<T, Func> for (ReadOnlyLoanedMessage<'_, T>,)
 where
     T: Message,
@@ -80,6 +80,10 @@ pub struct MessageInfo {
     ///   received messages.
     ///   Those might have already been taken by other messages that were received in between or lost.
     ///   `psn2 - psn1 - 1 = 0` if and only if the messages were sent by the publisher consecutively.
+    ///
+    /// A [`SequenceTracker`][1] uses this to detect lost, out-of-order and duplicate messages.
+    ///
+    /// [1]: crate::SequenceTracker
     pub publication_sequence_number: u64,
     /// Sequence number of the received message set by the subscription.
     ///
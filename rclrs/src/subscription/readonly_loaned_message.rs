@@ -3,7 +3,7 @@ use std::ops::Deref;
 use rosidl_runtime_rs::Message;
 
 use crate::rcl_bindings::*;
-use crate::{Subscription, ToResult};
+use crate::{RclrsError, Subscription, ToResult};
 
 /// A message that is owned by the middleware, loaned out for reading.
 ///
@@ -33,6 +33,20 @@ where
     }
 }
 
+impl<'a, T> ReadOnlyLoanedMessage<'a, T>
+where
+    T: Message,
+{
+    /// Converts the loaned message into an idiomatic message.
+    ///
+    /// This does not need to clone the message first, unlike converting the dereferenced
+    /// message with [`Message::try_from_rmw_message`].
+    /// Returns an error if a field doesn't fit into the idiomatic message.
+    pub fn to_idiomatic(&self) -> Result<T, RclrsError> {
+        T::try_from_rmw_message_ref(self).map_err(|err| RclrsError::InvalidMessage { err })
+    }
+}
+
 impl<'a, T> Drop for ReadOnlyLoanedMessage<'a, T>
 where
     T: Message,
@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use crate::rcl_bindings::RMW_GID_STORAGE_SIZE;
+use crate::{MessageInfo, PublisherGid};
+
+// The number of sequence numbers below the highest one that are remembered per publisher, to
+// tell duplicates from messages that arrive out of order
+const WINDOW_SIZE: u64 = 64;
+
+/// How a received message relates to the messages received before from the same publisher.
+///
+/// This is determined from the [`publication_sequence_number`][1] of the message.
+///
+/// [1]: MessageInfo::publication_sequence_number
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SequenceStatus {
+    /// The first message received from the publisher.
+    First,
+    /// The message directly follows the previous message from the publisher.
+    InOrder,
+    /// Messages were skipped between the previous message from the publisher and this one.
+    ///
+    /// The skipped messages were lost, unless they arrive later out of order.
+    Gap {
+        /// The number of skipped messages.
+        lost: u64,
+    },
+    /// A newer message from the publisher was received before this one.
+    OutOfOrder,
+    /// The same message from the publisher was received before.
+    Duplicate,
+    /// The middleware doesn't support sequence numbers.
+    Unsupported,
+}
+
+/// Counts of the messages received from one or all publishers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SequenceStatistics {
+    /// The number of received messages, including duplicates.
+    pub received: u64,
+    /// The number of messages that were skipped and didn't arrive later.
+    pub lost: u64,
+    /// The number of messages that arrived after a newer message from the same publisher.
+    pub out_of_order: u64,
+    /// The number of messages that were received more than once.
+    pub duplicates: u64,
+}
+
+impl SequenceStatistics {
+    fn add(&mut self, other: &SequenceStatistics) {
+        self.received += other.received;
+        self.lost += other.lost;
+        self.out_of_order += other.out_of_order;
+        self.duplicates += other.duplicates;
+    }
+}
+
+/// Tracks the sequence numbers of each publisher to detect lost, out-of-order and duplicate
+/// messages.
+///
+/// Subscriptions use this when [sequence tracking is enabled][1], but it can also be used
+/// directly with the [`MessageInfo`]s of messages that were taken manually.
+///
+/// To tell duplicates from messages that arrive out of order, the last 64 sequence numbers of
+/// each publisher are remembered. Older messages are always reported as out of order.
+///
+/// # Example
+/// ```
+/// # use rclrs::{MessageInfo, SequenceStatus, SequenceTracker};
+/// fn check(tracker: &mut SequenceTracker, message_info: &MessageInfo) {
+///     if let SequenceStatus::Gap { lost } = tracker.track(message_info) {
+///         println!("Lost {} messages", lost);
+///     }
+/// }
+/// ```
+///
+/// [1]: crate::Subscription::enable_sequence_tracking
+#[derive(Clone, Debug, Default)]
+pub struct SequenceTracker {
+    publishers: HashMap<[u8; RMW_GID_STORAGE_SIZE], PublisherSequence>,
+    unsupported: u64,
+}
+
+#[derive(Clone, Debug)]
+struct PublisherSequence {
+    highest: u64,
+    // Bit i is set if the sequence number highest - i was received
+    received_window: u64,
+    statistics: SequenceStatistics,
+}
+
+impl SequenceTracker {
+    /// Creates a tracker that hasn't seen any messages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a received message and returns how it relates to the previous messages from its
+    /// publisher.
+    pub fn track(&mut self, message_info: &MessageInfo) -> SequenceStatus {
+        let sequence_number = message_info.publication_sequence_number;
+        if sequence_number == u64::MAX {
+            self.unsupported += 1;
+            return SequenceStatus::Unsupported;
+        }
+        let publisher = match self.publishers.get_mut(&message_info.publisher_gid.data) {
+            Some(publisher) => publisher,
+            None => {
+                let statistics = SequenceStatistics {
+                    received: 1,
+                    ..Default::default()
+                };
+                self.publishers.insert(
+                    message_info.publisher_gid.data,
+                    PublisherSequence {
+                        highest: sequence_number,
+                        received_window: 1,
+                        statistics,
+                    },
+                );
+                return SequenceStatus::First;
+            }
+        };
+        publisher.statistics.received += 1;
+        if sequence_number > publisher.highest {
+            let shift = sequence_number - publisher.highest;
+            publisher.received_window = if shift < WINDOW_SIZE {
+                (publisher.received_window << shift) | 1
+            } else {
+                1
+            };
+            publisher.highest = sequence_number;
+            let lost = shift - 1;
+            publisher.statistics.lost += lost;
+            return if lost == 0 {
+                SequenceStatus::InOrder
+            } else {
+                SequenceStatus::Gap { lost }
+            };
+        }
+        let offset = publisher.highest - sequence_number;
+        if offset < WINDOW_SIZE && publisher.received_window & (1 << offset) > 0 {
+            publisher.statistics.duplicates += 1;
+            return SequenceStatus::Duplicate;
+        }
+        // A message that was counted as lost arrived after all
+        if offset < WINDOW_SIZE {
+            publisher.received_window |= 1 << offset;
+            publisher.statistics.lost = publisher.statistics.lost.saturating_sub(1);
+        }
+        publisher.statistics.out_of_order += 1;
+        SequenceStatus::OutOfOrder
+    }
+
+    /// Returns the counts of the messages from all publishers.
+    ///
+    /// Messages without a sequence number are only counted as received.
+    pub fn statistics(&self) -> SequenceStatistics {
+        let mut statistics = SequenceStatistics {
+            received: self.unsupported,
+            ..Default::default()
+        };
+        for publisher in self.publishers.values() {
+            statistics.add(&publisher.statistics);
+        }
+        statistics
+    }
+
+    /// Returns the counts of the messages from the given publisher, or `None` if no message
+    /// with a sequence number was received from it.
+    pub fn publisher_statistics(&self, publisher_gid: &PublisherGid) -> Option<SequenceStatistics> {
+        self.publishers
+            .get(&publisher_gid.data)
+            .map(|publisher| publisher.statistics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message_info(publisher: u8, publication_sequence_number: u64) -> MessageInfo {
+        let mut data = [0; RMW_GID_STORAGE_SIZE];
+        data[0] = publisher;
+        MessageInfo {
+            source_timestamp: None,
+            received_timestamp: None,
+            publication_sequence_number,
+            reception_sequence_number: u64::MAX,
+            publisher_gid: PublisherGid {
+                data,
+                implementation_identifier: std::ptr::null(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_sequence_status() {
+        let mut tracker = SequenceTracker::new();
+        let mut track =
+            |publisher, sequence_number| tracker.track(&message_info(publisher, sequence_number));
+        assert_eq!(track(1, 10), SequenceStatus::First);
+        assert_eq!(track(1, 11), SequenceStatus::InOrder);
+        assert_eq!(track(1, 11), SequenceStatus::Duplicate);
+        assert_eq!(track(1, 15), SequenceStatus::Gap { lost: 3 });
+        assert_eq!(track(1, 13), SequenceStatus::OutOfOrder);
+        assert_eq!(track(1, 13), SequenceStatus::Duplicate);
+        assert_eq!(track(1, 10), SequenceStatus::Duplicate);
+        // Publishers are tracked independently
+        assert_eq!(track(2, 1), SequenceStatus::First);
+        assert_eq!(track(2, 2), SequenceStatus::InOrder);
+        assert_eq!(track(1, 16), SequenceStatus::InOrder);
+        assert_eq!(track(3, u64::MAX), SequenceStatus::Unsupported);
+
+        assert_eq!(
+            tracker.publisher_statistics(&message_info(1, 0).publisher_gid),
+            Some(SequenceStatistics {
+                received: 8,
+                lost: 2,
+                out_of_order: 1,
+                duplicates: 3,
+            })
+        );
+        assert_eq!(
+            tracker.statistics(),
+            SequenceStatistics {
+                received: 11,
+                lost: 2,
+                out_of_order: 1,
+                duplicates: 3,
+            }
+        );
+        assert_eq!(
+            tracker.publisher_statistics(&message_info(3, 0).publisher_gid),
+            None
+        );
+    }
+
+    #[test]
+    fn test_window() {
+        let mut tracker = SequenceTracker::new();
+        let mut track = |sequence_number| tracker.track(&message_info(1, sequence_number));
+        assert_eq!(track(1), SequenceStatus::First);
+        assert_eq!(track(100), SequenceStatus::Gap { lost: 98 });
+        // Too old to be remembered, so it can't be told apart from a new message
+        assert_eq!(track(1), SequenceStatus::OutOfOrder);
+        assert_eq!(track(100 - WINDOW_SIZE + 1), SequenceStatus::OutOfOrder);
+        assert_eq!(track(100 - WINDOW_SIZE + 1), SequenceStatus::Duplicate);
+        assert_eq!(track(200), SequenceStatus::Gap { lost: 99 });
+        assert_eq!(track(200), SequenceStatus::Duplicate);
+        assert_eq!(tracker.statistics().lost, 98 - 1 + 99);
+    }
+}
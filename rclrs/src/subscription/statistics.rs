@@ -0,0 +1,344 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::vendor::builtin_interfaces::msg::Time;
+use crate::vendor::statistics_msgs::msg::{MetricsMessage, StatisticDataPoint, StatisticDataType};
+use crate::{MessageInfo, QoSProfile, SequenceStatus, SequenceTracker, Timer, QOS_PROFILE_DEFAULT};
+
+/// Options for [`Node::create_topic_statistics()`][1].
+///
+/// The default options match the defaults of `rclcpp`.
+///
+/// [1]: crate::Node::create_topic_statistics
+#[derive(Clone, Debug)]
+pub struct TopicStatisticsOptions {
+    /// The topic that the statistics are published on.
+    pub publish_topic: String,
+    /// The period of publishing the statistics, which is also the length of the window that the
+    /// statistics are computed over.
+    pub publish_period: Duration,
+    /// The QoS profile of the statistics publisher.
+    pub qos: QoSProfile,
+}
+
+impl Default for TopicStatisticsOptions {
+    fn default() -> Self {
+        Self {
+            publish_topic: String::from("/statistics"),
+            publish_period: Duration::from_secs(1),
+            qos: QOS_PROFILE_DEFAULT,
+        }
+    }
+}
+
+/// Periodically publishes statistics about the messages received by a subscription.
+///
+/// This is the equivalent of topic statistics in `rclcpp`. At the end of each window, the
+/// following `statistics_msgs/msg/MetricsMessage`s are published, with the node name as the
+/// `measurement_source_name`:
+/// - `message_age`: The time in milliseconds between publishing and taking each message, with
+///   the average, minimum, maximum, standard deviation and sample count. Messages without a
+///   source timestamp are not included.
+/// - `message_period`: The time in milliseconds between taking consecutive messages, with the
+///   same statistics.
+/// - `messages_received`: The number of messages taken in the window.
+/// - `messages_dropped`: The number of messages that were not received, as detected from gaps in
+///   the publication sequence numbers of each publisher. Middlewares that don't support sequence
+///   numbers always report zero.
+///
+/// Statistics are only published while the node is spun, and stop when this handle is dropped.
+///
+/// The only available way to instantiate topic statistics is via
+/// [`Node::create_topic_statistics()`][1].
+///
+/// # Example
+/// ```
+/// # use std::time::Duration;
+/// # use rclrs::{Node, RclrsError, Subscription, TopicStatistics, TopicStatisticsOptions};
+/// fn enable_statistics<T: rosidl_runtime_rs::Message>(
+///     node: &Node,
+///     subscription: &Subscription<T>,
+/// ) -> Result<TopicStatistics, RclrsError> {
+///     let options = TopicStatisticsOptions {
+///         publish_period: Duration::from_secs(10),
+///         ..Default::default()
+///     };
+///     node.create_topic_statistics(subscription, options)
+/// }
+/// ```
+///
+/// [1]: crate::Node::create_topic_statistics
+pub struct TopicStatistics {
+    // Subscriptions only hold a weak reference to the collector
+    _collector: Arc<StatisticsCollector>,
+    _timer: Arc<Timer>,
+}
+
+impl TopicStatistics {
+    pub(crate) fn new(collector: Arc<StatisticsCollector>, timer: Arc<Timer>) -> Self {
+        Self {
+            _collector: collector,
+            _timer: timer,
+        }
+    }
+}
+
+/// Collects the statistics of one window, which are shared between a subscription and the timer
+/// that publishes them.
+pub(crate) struct StatisticsCollector {
+    node_name: String,
+    state_mtx: Mutex<StatisticsState>,
+}
+
+struct StatisticsState {
+    window_start: SystemTime,
+    message_age: Accumulator,
+    message_period: Accumulator,
+    received: u64,
+    dropped: u64,
+    // These are kept across windows
+    last_received: Option<SystemTime>,
+    sequence_tracker: SequenceTracker,
+}
+
+impl StatisticsCollector {
+    pub(crate) fn new(node_name: String, now: SystemTime) -> Self {
+        Self {
+            node_name,
+            state_mtx: Mutex::new(StatisticsState {
+                window_start: now,
+                message_age: Accumulator::default(),
+                message_period: Accumulator::default(),
+                received: 0,
+                dropped: 0,
+                last_received: None,
+                sequence_tracker: SequenceTracker::new(),
+            }),
+        }
+    }
+
+    /// Adds a message that was taken at the given time.
+    pub(crate) fn record(&self, message_info: &MessageInfo, now: SystemTime) {
+        let state = &mut *self.state_mtx.lock().unwrap();
+        state.received += 1;
+        // A source timestamp after the current time is only caused by unsynchronized clocks
+        if let Some(age) = message_info
+            .source_timestamp
+            .and_then(|source_timestamp| now.duration_since(source_timestamp).ok())
+        {
+            state.message_age.add(as_millis(age));
+        }
+        if let Some(period) = state
+            .last_received
+            .and_then(|last_received| now.duration_since(last_received).ok())
+        {
+            state.message_period.add(as_millis(period));
+        }
+        state.last_received = Some(now);
+        if let SequenceStatus::Gap { lost } = state.sequence_tracker.track(message_info) {
+            state.dropped += lost;
+        }
+    }
+
+    /// Returns the metrics of the current window and starts a new one.
+    pub(crate) fn finish_window(&self, now: SystemTime) -> Vec<MetricsMessage> {
+        let state = &mut *self.state_mtx.lock().unwrap();
+        let metric = |metrics_source: &str, unit: &str, statistics| MetricsMessage {
+            measurement_source_name: self.node_name.clone(),
+            metrics_source: String::from(metrics_source),
+            unit: String::from(unit),
+            window_start: to_time_msg(state.window_start),
+            window_stop: to_time_msg(now),
+            statistics,
+        };
+        let count = |count: u64| {
+            vec![StatisticDataPoint {
+                data_type: StatisticDataType::STATISTICS_DATA_TYPE_SAMPLE_COUNT,
+                data: count as f64,
+            }]
+        };
+        let metrics = vec![
+            metric("message_age", "ms", state.message_age.data_points()),
+            metric("message_period", "ms", state.message_period.data_points()),
+            metric("messages_received", "count", count(state.received)),
+            metric("messages_dropped", "count", count(state.dropped)),
+        ];
+        state.window_start = now;
+        state.message_age = Accumulator::default();
+        state.message_period = Accumulator::default();
+        state.received = 0;
+        state.dropped = 0;
+        metrics
+    }
+}
+
+// Computes the mean and variance incrementally with Welford's algorithm
+#[derive(Default)]
+struct Accumulator {
+    count: u64,
+    mean: f64,
+    sum_of_squared_deviations: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Accumulator {
+    fn add(&mut self, sample: f64) {
+        self.count += 1;
+        if self.count == 1 {
+            self.min = sample;
+            self.max = sample;
+        } else {
+            self.min = self.min.min(sample);
+            self.max = self.max.max(sample);
+        }
+        let deviation = sample - self.mean;
+        self.mean += deviation / self.count as f64;
+        self.sum_of_squared_deviations += deviation * (sample - self.mean);
+    }
+
+    // Like in rclcpp, the statistics of an empty window are NaN
+    fn data_points(&self) -> Vec<StatisticDataPoint> {
+        let (average, min, max, stddev) = if self.count == 0 {
+            (f64::NAN, f64::NAN, f64::NAN, f64::NAN)
+        } else {
+            let variance = self.sum_of_squared_deviations / self.count as f64;
+            (self.mean, self.min, self.max, variance.sqrt())
+        };
+        [
+            (StatisticDataType::STATISTICS_DATA_TYPE_AVERAGE, average),
+            (StatisticDataType::STATISTICS_DATA_TYPE_MINIMUM, min),
+            (StatisticDataType::STATISTICS_DATA_TYPE_MAXIMUM, max),
+            (StatisticDataType::STATISTICS_DATA_TYPE_STDDEV, stddev),
+            (
+                StatisticDataType::STATISTICS_DATA_TYPE_SAMPLE_COUNT,
+                self.count as f64,
+            ),
+        ]
+        .into_iter()
+        .map(|(data_type, data)| StatisticDataPoint { data_type, data })
+        .collect()
+    }
+}
+
+fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn to_time_msg(time: SystemTime) -> Time {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    Time {
+        sec: since_epoch.as_secs() as i32,
+        nanosec: since_epoch.subsec_nanos(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rcl_bindings::RMW_GID_STORAGE_SIZE;
+    use crate::PublisherGid;
+
+    fn message_info(
+        publisher: u8,
+        publication_sequence_number: u64,
+        source_timestamp: Option<SystemTime>,
+    ) -> MessageInfo {
+        let mut data = [0; RMW_GID_STORAGE_SIZE];
+        data[0] = publisher;
+        MessageInfo {
+            source_timestamp,
+            received_timestamp: None,
+            publication_sequence_number,
+            reception_sequence_number: u64::MAX,
+            publisher_gid: PublisherGid {
+                data,
+                implementation_identifier: std::ptr::null(),
+            },
+        }
+    }
+
+    fn data(metric: &MetricsMessage) -> Vec<(u8, f64)> {
+        metric
+            .statistics
+            .iter()
+            .map(|point| (point.data_type, point.data))
+            .collect()
+    }
+
+    fn assert_data_eq(metric: &MetricsMessage, expected: &[(u8, f64)]) {
+        let actual = data(metric);
+        assert_eq!(actual.len(), expected.len());
+        for ((data_type, data), (expected_type, expected_data)) in actual.iter().zip(expected) {
+            assert_eq!(data_type, expected_type);
+            assert!(
+                (data - expected_data).abs() < 1e-9,
+                "{} != {}",
+                data,
+                expected_data
+            );
+        }
+    }
+
+    #[test]
+    fn test_statistics() {
+        let start = UNIX_EPOCH + Duration::from_secs(100);
+        let ms = Duration::from_millis;
+        let collector = StatisticsCollector::new(String::from("listener"), start);
+        collector.record(&message_info(1, 1, Some(start)), start + ms(10));
+        collector.record(&message_info(1, 2, Some(start)), start + ms(30));
+        // Two messages of this publisher were lost
+        collector.record(&message_info(1, 5, None), start + ms(60));
+        // The first message of another publisher is never counted as a gap
+        collector.record(&message_info(2, 7, None), start + ms(100));
+        let metrics = collector.finish_window(start + ms(1000));
+
+        let names: Vec<_> = metrics.iter().map(|m| m.metrics_source.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "message_age",
+                "message_period",
+                "messages_received",
+                "messages_dropped"
+            ]
+        );
+        assert!(metrics
+            .iter()
+            .all(|m| m.measurement_source_name == "listener"
+                && m.window_start == to_time_msg(start)
+                && m.window_stop.sec == 101));
+        assert_data_eq(
+            &metrics[0],
+            &[(1, 20.0), (2, 10.0), (3, 30.0), (4, 10.0), (5, 2.0)],
+        );
+        assert_data_eq(
+            &metrics[1],
+            &[
+                (1, 30.0),
+                (2, 20.0),
+                (3, 40.0),
+                (4, (200.0f64 / 3.0).sqrt()),
+                (5, 3.0),
+            ],
+        );
+        assert_data_eq(&metrics[2], &[(5, 4.0)]);
+        assert_data_eq(&metrics[3], &[(5, 2.0)]);
+    }
+
+    #[test]
+    fn test_empty_window() {
+        let start = UNIX_EPOCH + Duration::from_secs(100);
+        let collector = StatisticsCollector::new(String::from("listener"), start);
+        collector.record(&message_info(1, u64::MAX, None), start);
+        collector.finish_window(start + Duration::from_secs(1));
+        let metrics = collector.finish_window(start + Duration::from_secs(2));
+        assert_eq!(metrics[0].window_start.sec, 101);
+        assert!(metrics[0].statistics[..4]
+            .iter()
+            .all(|point| point.data.is_nan()));
+        assert_eq!(data(&metrics[0])[4], (5, 0.0));
+        assert_data_eq(&metrics[2], &[(5, 0.0)]);
+        assert_data_eq(&metrics[3], &[(5, 0.0)]);
+    }
+}
@@ -0,0 +1,200 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::clock::ClockHandle;
+use crate::error::{RclReturnCode, ToResult};
+use crate::{rcl_bindings::*, RclrsError};
+
+// SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
+// they are running in. Therefore, this type can be safely sent to another thread.
+unsafe impl Send for rcl_timer_t {}
+
+/// Internal struct used by timers.
+pub struct TimerHandle {
+    rcl_timer_mtx: Mutex<rcl_timer_t>,
+    // The timer only stores a pointer to its clock, so the clock must outlive the timer.
+    // It is dropped after the timer, since fields are dropped after Drop::drop() runs.
+    _clock_mtx: Mutex<ClockHandle>,
+    // Used to ensure the context is alive while the timer is alive.
+    _rcl_context_mtx: Arc<Mutex<rcl_context_t>>,
+    pub(crate) in_use_by_wait_set: Arc<AtomicBool>,
+}
+
+impl TimerHandle {
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_timer_t> {
+        self.rcl_timer_mtx.lock().unwrap()
+    }
+}
+
+impl Drop for TimerHandle {
+    fn drop(&mut self) {
+        let rcl_timer = self.rcl_timer_mtx.get_mut().unwrap();
+        // SAFETY: No preconditions for this function (besides the arguments being valid).
+        unsafe {
+            rcl_timer_fini(rcl_timer);
+        }
+    }
+}
+
+/// Trait to be implemented by concrete [`Timer`]s.
+pub trait TimerBase: Send + Sync {
+    /// Internal function to get a reference to the `rcl` handle.
+    fn handle(&self) -> &TimerHandle;
+    /// Runs the callback if the timer is ready.
+    fn execute(&self) -> Result<(), RclrsError>;
+}
+
+/// Struct for calling a callback periodically.
+///
+/// The timer uses the steady clock, so it is not affected by changes of the system time.
+///
+/// Calling the callback requires calling [`spin_once`][1] or [`spin`][2] on the timer's node.
+/// The timer runs as soon as it is created.
+///
+/// The only available way to instantiate timers is via [`Node::create_timer()`][3], this
+/// is to ensure that [`Node`][4]s can track all the timers that have been created.
+///
+/// [1]: crate::spin_once
+/// [2]: crate::spin
+/// [3]: crate::Node::create_timer
+/// [4]: crate::Node
+pub struct Timer {
+    pub(crate) handle: Arc<TimerHandle>,
+    callback: Mutex<Box<dyn FnMut() + Send>>,
+}
+
+impl TimerBase for Timer {
+    fn handle(&self) -> &TimerHandle {
+        &self.handle
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        let mut is_ready = false;
+        // SAFETY: The timer is valid and the output pointer is valid.
+        unsafe { rcl_timer_is_ready(&*self.handle.lock(), &mut is_ready) }.ok()?;
+        if !is_ready {
+            return Ok(());
+        }
+        // SAFETY: The timer is valid. It has no rcl callback, so this only updates the time of
+        // the next call.
+        match unsafe { rcl_timer_call(&mut *self.handle.lock()) }.ok() {
+            Ok(()) => {}
+            // The timer was canceled after it became ready
+            Err(RclrsError::RclError {
+                code: RclReturnCode::TimerCanceled,
+                ..
+            }) => return Ok(()),
+            Err(e) => return Err(e),
+        }
+        (*self.callback.lock().unwrap())();
+        Ok(())
+    }
+}
+
+impl Timer {
+    /// Creates a new timer.
+    pub(crate) fn new<F>(
+        rcl_context_mtx: Arc<Mutex<rcl_context_t>>,
+        period: Duration,
+        callback: F,
+    ) -> Result<Self, RclrsError>
+    // This uses pub(crate) visibility to avoid instantiating this struct outside
+    // [`Node::create_timer`], see the struct's documentation for the rationale
+    where
+        F: FnMut() + 'static + Send,
+    {
+        let period_ns = duration_to_nanoseconds(period)?;
+        let mut clock = ClockHandle::new(rcl_clock_type_t::RCL_STEADY_TIME)?;
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut rcl_timer = unsafe { rcl_get_zero_initialized_timer() };
+        // SAFETY: No preconditions for this function.
+        let allocator = unsafe { rcutils_get_default_allocator() };
+        unsafe {
+            // SAFETY: The rcl_timer is zero-initialized as expected by this function.
+            // The clock is owned by the timer handle, which keeps it alive for as long as the
+            // timer. The context is kept alive because it is co-owned by the timer.
+            // The callback is called by this struct instead of rcl, so none is passed.
+            #[cfg(any(ros_distro = "humble", ros_distro = "iron"))]
+            rcl_timer_init(
+                &mut rcl_timer,
+                clock.as_mut_ptr(),
+                &mut *rcl_context_mtx.lock().unwrap(),
+                period_ns,
+                None,
+                allocator,
+            )
+            .ok()?;
+            #[cfg(not(any(ros_distro = "humble", ros_distro = "iron")))]
+            rcl_timer_init2(
+                &mut rcl_timer,
+                clock.as_mut_ptr(),
+                &mut *rcl_context_mtx.lock().unwrap(),
+                period_ns,
+                None,
+                allocator,
+                true,
+            )
+            .ok()?;
+        }
+
+        let handle = Arc::new(TimerHandle {
+            rcl_timer_mtx: Mutex::new(rcl_timer),
+            _clock_mtx: Mutex::new(clock),
+            _rcl_context_mtx: rcl_context_mtx,
+            in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
+        });
+
+        Ok(Self {
+            handle,
+            callback: Mutex::new(Box::new(callback)),
+        })
+    }
+
+    /// Returns the period of the timer.
+    pub fn period(&self) -> Result<Duration, RclrsError> {
+        let mut period_ns = 0;
+        // SAFETY: The timer is valid and the output pointer is valid.
+        unsafe { rcl_timer_get_period(&*self.handle.lock(), &mut period_ns) }.ok()?;
+        Ok(Duration::from_nanos(period_ns.max(0) as u64))
+    }
+
+    /// Returns the time until the callback is due to be called.
+    ///
+    /// This is zero if the callback is overdue.
+    pub fn time_until_next_call(&self) -> Result<Duration, RclrsError> {
+        let mut time_ns = 0;
+        // SAFETY: The timer is valid and the output pointer is valid.
+        unsafe { rcl_timer_get_time_until_next_call(&*self.handle.lock(), &mut time_ns) }.ok()?;
+        Ok(Duration::from_nanos(time_ns.max(0) as u64))
+    }
+
+    /// Stops calling the callback until the timer is reset.
+    pub fn cancel(&self) -> Result<(), RclrsError> {
+        // SAFETY: The timer is valid.
+        unsafe { rcl_timer_cancel(&mut *self.handle.lock()) }.ok()
+    }
+
+    /// Returns whether the timer is canceled.
+    pub fn is_canceled(&self) -> Result<bool, RclrsError> {
+        let mut is_canceled = false;
+        // SAFETY: The timer is valid and the output pointer is valid.
+        unsafe { rcl_timer_is_canceled(&*self.handle.lock(), &mut is_canceled) }.ok()?;
+        Ok(is_canceled)
+    }
+
+    /// Restarts the timer, so that the next call is one period from now.
+    ///
+    /// This also resumes a canceled timer.
+    pub fn reset(&self) -> Result<(), RclrsError> {
+        // SAFETY: The timer is valid.
+        unsafe { rcl_timer_reset(&mut *self.handle.lock()) }.ok()
+    }
+}
+
+fn duration_to_nanoseconds(duration: Duration) -> Result<i64, RclrsError> {
+    i64::try_from(duration.as_nanos()).map_err(|_| RclrsError::RclError {
+        code: RclReturnCode::InvalidArgument,
+        msg: None,
+    })
+}
@@ -15,13 +15,13 @@
 // DISTRIBUTION A. Approved for public release; distribution unlimited.
 // OPSEC #4584.
 
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
 use std::time::Duration;
 use std::vec::Vec;
 
 use crate::error::{to_rclrs_result, RclReturnCode, RclrsError, ToResult};
 use crate::rcl_bindings::*;
-use crate::{ClientBase, Context, Node, ServiceBase, SubscriptionBase};
+use crate::{ClientBase, Context, EventBase, Node, ServiceBase, SubscriptionBase, TimerBase};
 
 mod exclusivity_guard;
 mod guard_condition;
@@ -29,10 +29,14 @@ use exclusivity_guard::*;
 pub use guard_condition::*;
 
 /// A struct for waiting on subscriptions and other waitable entities to become ready.
+///
+/// A wait set can be waited on repeatedly. The entities stay in the wait set until it is
+/// cleared, and the buffers for the ready entities are reused, so waiting on the same entities
+/// again does not allocate.
 pub struct WaitSet {
     rcl_wait_set: rcl_wait_set_t,
     // Used to ensure the context is alive while the wait set is alive.
-    _rcl_context_mtx: Arc<Mutex<rcl_context_t>>,
+    rcl_context_mtx: Arc<Mutex<rcl_context_t>>,
     // The subscriptions that are currently registered in the wait set.
     // This correspondence is an invariant that must be maintained by all functions,
     // even in the error case.
@@ -41,9 +45,16 @@ pub struct WaitSet {
     // The guard conditions that are currently registered in the wait set.
     guard_conditions: Vec<ExclusivityGuard<Arc<GuardCondition>>>,
     services: Vec<ExclusivityGuard<Arc<dyn ServiceBase>>>,
+    timers: Vec<ExclusivityGuard<Arc<dyn TimerBase>>>,
+    events: Vec<ExclusivityGuard<Arc<dyn EventBase>>>,
+    // rcl_wait() sets the entries of entities that are not ready to null, so they need to be
+    // added to the rcl_wait_set again before the next wait.
+    needs_restore: bool,
+    ready_indices: ReadyIndices,
 }
 
 /// A list of entities that are ready, returned by [`WaitSet::wait`].
+#[derive(Default)]
 pub struct ReadyEntities {
     /// A list of subscriptions that have potentially received messages.
     pub subscriptions: Vec<Arc<dyn SubscriptionBase>>,
@@ -53,6 +64,31 @@ pub struct ReadyEntities {
     pub guard_conditions: Vec<Arc<GuardCondition>>,
     /// A list of services that have potentially received requests.
     pub services: Vec<Arc<dyn ServiceBase>>,
+    /// A list of timers that are potentially due.
+    pub timers: Vec<Arc<dyn TimerBase>>,
+    /// A list of events that have potentially occurred.
+    pub events: Vec<Arc<dyn EventBase>>,
+}
+
+/// The indices of the entities that are ready, returned by [`WaitSet::wait_for_ready_indices`].
+///
+/// Each index refers to the position of the entity among the entities of the same kind, in the
+/// order in which they were added to the wait set. The entity itself can be obtained from e.g.
+/// [`WaitSet::subscription`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReadyIndices {
+    /// The indices of subscriptions that have potentially received messages.
+    pub subscriptions: Vec<usize>,
+    /// The indices of clients that have potentially received responses.
+    pub clients: Vec<usize>,
+    /// The indices of guard conditions that have been triggered.
+    pub guard_conditions: Vec<usize>,
+    /// The indices of services that have potentially received requests.
+    pub services: Vec<usize>,
+    /// The indices of timers that are potentially due.
+    pub timers: Vec<usize>,
+    /// The indices of events that have potentially occurred.
+    pub events: Vec<usize>,
 }
 
 impl Drop for rcl_wait_set_t {
@@ -87,6 +123,29 @@ impl WaitSet {
         number_of_services: usize,
         number_of_events: usize,
         context: &Context,
+    ) -> Result<Self, RclrsError> {
+        Self::new_with_rcl_context(
+            number_of_subscriptions,
+            number_of_guard_conditions,
+            number_of_timers,
+            number_of_clients,
+            number_of_services,
+            number_of_events,
+            &context.rcl_context_mtx,
+        )
+    }
+
+    /// Creates a new wait set for the given rcl context.
+    ///
+    /// This is used by entities that only store the rcl context instead of the [`Context`].
+    pub(crate) fn new_with_rcl_context(
+        number_of_subscriptions: usize,
+        number_of_guard_conditions: usize,
+        number_of_timers: usize,
+        number_of_clients: usize,
+        number_of_services: usize,
+        number_of_events: usize,
+        rcl_context_mtx: &Arc<Mutex<rcl_context_t>>,
     ) -> Result<Self, RclrsError> {
         let rcl_wait_set = unsafe {
             // SAFETY: Getting a zero-initialized value is always safe
@@ -101,7 +160,7 @@ impl WaitSet {
                 number_of_clients,
                 number_of_services,
                 number_of_events,
-                &mut *context.rcl_context_mtx.lock().unwrap(),
+                &mut *rcl_context_mtx.lock().unwrap(),
                 rcutils_get_default_allocator(),
             )
             .ok()?;
@@ -109,11 +168,15 @@ impl WaitSet {
         };
         Ok(Self {
             rcl_wait_set,
-            _rcl_context_mtx: context.rcl_context_mtx.clone(),
+            rcl_context_mtx: Arc::clone(rcl_context_mtx),
             subscriptions: Vec::new(),
             guard_conditions: Vec::new(),
             clients: Vec::new(),
             services: Vec::new(),
+            timers: Vec::new(),
+            events: Vec::new(),
+            needs_restore: false,
+            ready_indices: ReadyIndices::default(),
         })
     }
 
@@ -121,39 +184,87 @@ impl WaitSet {
     ///
     /// The wait set is sized to fit the node exactly, so there is no capacity for adding other entities.
     pub fn new_for_node(node: &Node) -> Result<Self, RclrsError> {
-        let live_subscriptions = node.live_subscriptions();
-        let live_clients = node.live_clients();
-        let live_guard_conditions = node.live_guard_conditions();
-        let live_services = node.live_services();
-        let ctx = Context {
-            rcl_context_mtx: node.rcl_context_mtx.clone(),
-        };
-        let mut wait_set = WaitSet::new(
-            live_subscriptions.len(),
-            live_guard_conditions.len(),
-            0,
-            live_clients.len(),
-            live_services.len(),
-            0,
-            &ctx,
-        )?;
+        let mut wait_set = WaitSet::new_with_rcl_context(0, 0, 0, 0, 0, 0, &node.rcl_context_mtx)?;
+        wait_set.rebuild_for_node(node)?;
+        Ok(wait_set)
+    }
+
+    /// Replaces the entities in the wait set with all waitable entities in the node.
+    ///
+    /// The wait set is only resized when its capacity is too small for the node, so rebuilding
+    /// it for the same node repeatedly does not allocate.
+    ///
+    /// # Errors
+    /// - If the node does not belong to the same context as the wait set,
+    ///   [`InvalidArgument`][1] will be returned
+    /// - If one of the node's entities was already added to another wait set,
+    ///   [`AlreadyAddedToWaitSet`][2] will be returned
+    ///
+    /// [1]: crate::RclReturnCode
+    /// [2]: crate::RclrsError
+    pub fn rebuild_for_node(&mut self, node: &Node) -> Result<(), RclrsError> {
+        if !Arc::ptr_eq(&self.rcl_context_mtx, &node.rcl_context_mtx) {
+            return Err(RclrsError::RclError {
+                code: RclReturnCode::InvalidArgument,
+                msg: None,
+            });
+        }
+        self.clear();
+        // Holding all locks ensures that the number of entities can't change while adding them
+        let subscriptions = node.subscriptions_mtx.lock().unwrap();
+        let guard_conditions = node.guard_conditions_mtx.lock().unwrap();
+        let timers = node.timers_mtx.lock().unwrap();
+        let clients = node.clients_mtx.lock().unwrap();
+        let services = node.services_mtx.lock().unwrap();
+        let events = node.events_mtx.lock().unwrap();
+
+        let number_of_subscriptions = count_live(&subscriptions);
+        let number_of_guard_conditions = count_live(&guard_conditions);
+        let number_of_timers = count_live(&timers);
+        let number_of_clients = count_live(&clients);
+        let number_of_services = count_live(&services);
+        let number_of_events = count_live(&events);
+        if number_of_subscriptions > self.rcl_wait_set.size_of_subscriptions
+            || number_of_guard_conditions > self.rcl_wait_set.size_of_guard_conditions
+            || number_of_timers > self.rcl_wait_set.size_of_timers
+            || number_of_clients > self.rcl_wait_set.size_of_clients
+            || number_of_services > self.rcl_wait_set.size_of_services
+            || number_of_events > self.rcl_wait_set.size_of_events
+        {
+            self.resize(
+                number_of_subscriptions,
+                number_of_guard_conditions,
+                number_of_timers,
+                number_of_clients,
+                number_of_services,
+                number_of_events,
+            )?;
+        }
 
-        for live_subscription in &live_subscriptions {
-            wait_set.add_subscription(live_subscription.clone())?;
+        for subscription in subscriptions.iter().filter_map(Weak::upgrade) {
+            self.add_subscription(subscription)?;
         }
 
-        for live_client in &live_clients {
-            wait_set.add_client(live_client.clone())?;
+        for guard_condition in guard_conditions.iter().filter_map(Weak::upgrade) {
+            self.add_guard_condition(guard_condition)?;
         }
 
-        for live_guard_condition in &live_guard_conditions {
-            wait_set.add_guard_condition(live_guard_condition.clone())?;
+        for timer in timers.iter().filter_map(Weak::upgrade) {
+            self.add_timer(timer)?;
         }
 
-        for live_service in &live_services {
-            wait_set.add_service(live_service.clone())?;
+        for client in clients.iter().filter_map(Weak::upgrade) {
+            self.add_client(client)?;
         }
-        Ok(wait_set)
+
+        for service in services.iter().filter_map(Weak::upgrade) {
+            self.add_service(service)?;
+        }
+
+        for event in events.iter().filter_map(Weak::upgrade) {
+            self.add_event(event)?;
+        }
+        Ok(())
     }
 
     /// Removes all entities from the wait set.
@@ -165,12 +276,40 @@ impl WaitSet {
         self.guard_conditions.clear();
         self.clients.clear();
         self.services.clear();
-        // This cannot fail – the rcl_wait_set_clear function only checks that the input handle is
-        // valid, which it always is in our case. Hence, only debug_assert instead of returning
-        // Result.
-        // SAFETY: No preconditions for this function (besides passing in a valid wait set).
-        let ret = unsafe { rcl_wait_set_clear(&mut self.rcl_wait_set) };
-        debug_assert_eq!(ret, 0);
+        self.timers.clear();
+        self.events.clear();
+        self.clear_rcl_wait_set();
+        self.needs_restore = false;
+        self.ready_indices.clear();
+    }
+
+    /// Changes the capacity of the wait set.
+    ///
+    /// The arguments have the same meaning as in [`WaitSet::new`].
+    /// This removes all entities from the wait set.
+    pub fn resize(
+        &mut self,
+        number_of_subscriptions: usize,
+        number_of_guard_conditions: usize,
+        number_of_timers: usize,
+        number_of_clients: usize,
+        number_of_services: usize,
+        number_of_events: usize,
+    ) -> Result<(), RclrsError> {
+        self.clear();
+        unsafe {
+            // SAFETY: The wait set is valid and no longer refers to any entities.
+            rcl_wait_set_resize(
+                &mut self.rcl_wait_set,
+                number_of_subscriptions,
+                number_of_guard_conditions,
+                number_of_timers,
+                number_of_clients,
+                number_of_services,
+                number_of_events,
+            )
+            .ok()
+        }
     }
 
     /// Adds a subscription to the wait set.
@@ -191,17 +330,8 @@ impl WaitSet {
             Arc::clone(&subscription),
             Arc::clone(&subscription.handle().in_use_by_wait_set),
         )?;
-        unsafe {
-            // SAFETY: I'm not sure if it's required, but the subscription pointer will remain valid
-            // for as long as the wait set exists, because it's stored in self.subscriptions.
-            // Passing in a null pointer for the third argument is explicitly allowed.
-            rcl_wait_set_add_subscription(
-                &mut self.rcl_wait_set,
-                &*subscription.handle().lock(),
-                std::ptr::null_mut(),
-            )
-        }
-        .ok()?;
+        self.restore_if_needed()?;
+        add_rcl_subscription(&mut self.rcl_wait_set, &*subscription)?;
         self.subscriptions.push(exclusive_subscription);
         Ok(())
     }
@@ -224,20 +354,33 @@ impl WaitSet {
             Arc::clone(&guard_condition),
             Arc::clone(&guard_condition.in_use_by_wait_set),
         )?;
-
-        unsafe {
-            // SAFETY: Safe if the wait set and guard condition are initialized
-            rcl_wait_set_add_guard_condition(
-                &mut self.rcl_wait_set,
-                &*guard_condition.rcl_guard_condition.lock().unwrap(),
-                std::ptr::null_mut(),
-            )
-            .ok()?;
-        }
+        self.restore_if_needed()?;
+        add_rcl_guard_condition(&mut self.rcl_wait_set, &guard_condition)?;
         self.guard_conditions.push(exclusive_guard_condition);
         Ok(())
     }
 
+    /// Adds a timer to the wait set.
+    ///
+    /// # Errors
+    /// - If the timer was already added to this wait set or another one,
+    ///   [`AlreadyAddedToWaitSet`][1] will be returned
+    /// - If the number of timers in the wait set is larger than the
+    ///   capacity set in [`WaitSet::new`], [`WaitSetFull`][2] will be returned
+    ///
+    /// [1]: crate::RclrsError
+    /// [2]: crate::RclReturnCode
+    pub fn add_timer(&mut self, timer: Arc<dyn TimerBase>) -> Result<(), RclrsError> {
+        let exclusive_timer = ExclusivityGuard::new(
+            Arc::clone(&timer),
+            Arc::clone(&timer.handle().in_use_by_wait_set),
+        )?;
+        self.restore_if_needed()?;
+        add_rcl_timer(&mut self.rcl_wait_set, &*timer)?;
+        self.timers.push(exclusive_timer);
+        Ok(())
+    }
+
     /// Adds a client to the wait set.
     ///
     /// # Errors
@@ -253,17 +396,8 @@ impl WaitSet {
             Arc::clone(&client),
             Arc::clone(&client.handle().in_use_by_wait_set),
         )?;
-        unsafe {
-            // SAFETY: I'm not sure if it's required, but the client pointer will remain valid
-            // for as long as the wait set exists, because it's stored in self.clients.
-            // Passing in a null pointer for the third argument is explicitly allowed.
-            rcl_wait_set_add_client(
-                &mut self.rcl_wait_set,
-                &*client.handle().lock() as *const _,
-                core::ptr::null_mut(),
-            )
-        }
-        .ok()?;
+        self.restore_if_needed()?;
+        add_rcl_client(&mut self.rcl_wait_set, &*client)?;
         self.clients.push(exclusive_client);
         Ok(())
     }
@@ -283,21 +417,33 @@ impl WaitSet {
             Arc::clone(&service),
             Arc::clone(&service.handle().in_use_by_wait_set),
         )?;
-        unsafe {
-            // SAFETY: I'm not sure if it's required, but the service pointer will remain valid
-            // for as long as the wait set exists, because it's stored in self.services.
-            // Passing in a null pointer for the third argument is explicitly allowed.
-            rcl_wait_set_add_service(
-                &mut self.rcl_wait_set,
-                &*service.handle().lock() as *const _,
-                core::ptr::null_mut(),
-            )
-        }
-        .ok()?;
+        self.restore_if_needed()?;
+        add_rcl_service(&mut self.rcl_wait_set, &*service)?;
         self.services.push(exclusive_service);
         Ok(())
     }
 
+    /// Adds an event to the wait set.
+    ///
+    /// # Errors
+    /// - If the event was already added to this wait set or another one,
+    ///   [`AlreadyAddedToWaitSet`][1] will be returned
+    /// - If the number of events in the wait set is larger than the
+    ///   capacity set in [`WaitSet::new`], [`WaitSetFull`][2] will be returned
+    ///
+    /// [1]: crate::RclrsError
+    /// [2]: crate::RclReturnCode
+    pub fn add_event(&mut self, event: Arc<dyn EventBase>) -> Result<(), RclrsError> {
+        let exclusive_event = ExclusivityGuard::new(
+            Arc::clone(&event),
+            Arc::clone(&event.handle().in_use_by_wait_set),
+        )?;
+        self.restore_if_needed()?;
+        add_rcl_event(&mut self.rcl_wait_set, &*event)?;
+        self.events.push(exclusive_event);
+        Ok(())
+    }
+
     /// Blocks until the wait set is ready, or until the timeout has been exceeded.
     ///
     /// If the timeout is `None` then this function will block indefinitely until
@@ -310,7 +456,9 @@ impl WaitSet {
     /// that period of time has elapsed or the wait set becomes ready, which ever
     /// comes first.
     ///
-    /// This function does not change the entities registered in the wait set.
+    /// This function does not change the entities registered in the wait set, so it can be
+    /// called repeatedly. Use [`WaitSet::wait_for_ready_indices`] to avoid allocating the
+    /// returned lists.
     ///
     /// # Errors
     ///
@@ -321,7 +469,21 @@ impl WaitSet {
     /// This list is not comprehensive, since further errors may occur in the `rmw` or `rcl` layers.
     ///
     /// [1]: std::time::Duration::ZERO
-    pub fn wait(mut self, timeout: Option<Duration>) -> Result<ReadyEntities, RclrsError> {
+    pub fn wait(&mut self, timeout: Option<Duration>) -> Result<ReadyEntities, RclrsError> {
+        self.wait_for_ready_indices(timeout)?;
+        let mut ready_entities = ReadyEntities::default();
+        self.fill_ready_entities(&mut ready_entities);
+        Ok(ready_entities)
+    }
+
+    /// Like [`WaitSet::wait`], but returns the indices of the ready entities.
+    ///
+    /// The returned indices are stored in the wait set, and their buffers are reused by the next
+    /// wait. They can be obtained again with [`WaitSet::ready_indices`].
+    pub fn wait_for_ready_indices(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<&ReadyIndices, RclrsError> {
         let timeout_ns = match timeout.map(|d| d.as_nanos()) {
             None => -1,
             Some(ns) if ns <= i64::MAX as u128 => ns as i64,
@@ -332,6 +494,9 @@ impl WaitSet {
                 })
             }
         };
+        self.ready_indices.clear();
+        self.restore_if_needed()?;
+        self.needs_restore = true;
         // SAFETY: The comments in rcl mention "This function cannot operate on the same wait set
         // in multiple threads, and the wait sets may not share content."
         // We cannot currently guarantee that the wait sets may not share content, but it is
@@ -347,59 +512,318 @@ impl WaitSet {
                 _ => return Err(error),
             },
         }
-        let mut ready_entities = ReadyEntities {
-            subscriptions: Vec::new(),
-            clients: Vec::new(),
-            guard_conditions: Vec::new(),
-            services: Vec::new(),
-        };
+        // SAFETY: The entries are arrays of pointers with at least as many elements as there are
+        // entities of each kind, and this dereferencing is equivalent to
+        // https://github.com/ros2/rcl/blob/35a31b00a12f259d492bf53c0701003bd7f1745c/rcl/include/rcl/wait.h#L419
+        unsafe {
+            collect_ready(
+                self.rcl_wait_set.subscriptions,
+                self.subscriptions.len(),
+                &mut self.ready_indices.subscriptions,
+            );
+            collect_ready(
+                self.rcl_wait_set.clients,
+                self.clients.len(),
+                &mut self.ready_indices.clients,
+            );
+            collect_ready(
+                self.rcl_wait_set.guard_conditions,
+                self.guard_conditions.len(),
+                &mut self.ready_indices.guard_conditions,
+            );
+            collect_ready(
+                self.rcl_wait_set.services,
+                self.services.len(),
+                &mut self.ready_indices.services,
+            );
+            collect_ready(
+                self.rcl_wait_set.timers,
+                self.timers.len(),
+                &mut self.ready_indices.timers,
+            );
+            collect_ready(
+                self.rcl_wait_set.events,
+                self.events.len(),
+                &mut self.ready_indices.events,
+            );
+        }
+        // Messages delivered intra-process only trigger a guard condition of the subscription's
+        // node, so the subscription itself isn't marked as ready by rcl
+        let ready_subscriptions = &mut self.ready_indices.subscriptions;
         for (i, subscription) in self.subscriptions.iter().enumerate() {
-            // SAFETY: The `subscriptions` entry is an array of pointers, and this dereferencing is
-            // equivalent to
-            // https://github.com/ros2/rcl/blob/35a31b00a12f259d492bf53c0701003bd7f1745c/rcl/include/rcl/wait.h#L419
-            let wait_set_entry = unsafe { *self.rcl_wait_set.subscriptions.add(i) };
-            if !wait_set_entry.is_null() {
-                ready_entities
-                    .subscriptions
-                    .push(Arc::clone(&subscription.waitable));
+            if subscription.waitable.has_intra_process_messages()
+                && !ready_subscriptions.contains(&i)
+            {
+                ready_subscriptions.push(i);
             }
         }
+        ready_subscriptions.sort_unstable();
+        Ok(&self.ready_indices)
+    }
 
-        for (i, client) in self.clients.iter().enumerate() {
-            // SAFETY: The `clients` entry is an array of pointers, and this dereferencing is
-            // equivalent to
-            // https://github.com/ros2/rcl/blob/35a31b00a12f259d492bf53c0701003bd7f1745c/rcl/include/rcl/wait.h#L419
-            let wait_set_entry = unsafe { *self.rcl_wait_set.clients.add(i) };
-            if !wait_set_entry.is_null() {
-                ready_entities.clients.push(Arc::clone(&client.waitable));
-            }
-        }
+    /// Returns the indices of the entities that were ready after the last wait.
+    pub fn ready_indices(&self) -> &ReadyIndices {
+        &self.ready_indices
+    }
 
-        for (i, guard_condition) in self.guard_conditions.iter().enumerate() {
-            // SAFETY: The `clients` entry is an array of pointers, and this dereferencing is
-            // equivalent to
-            // https://github.com/ros2/rcl/blob/35a31b00a12f259d492bf53c0701003bd7f1745c/rcl/include/rcl/wait.h#L419
-            let wait_set_entry = unsafe { *self.rcl_wait_set.guard_conditions.add(i) };
-            if !wait_set_entry.is_null() {
-                ready_entities
-                    .guard_conditions
-                    .push(Arc::clone(&guard_condition.waitable));
-            }
+    /// Replaces the contents of the given lists with the entities that were ready after the last
+    /// wait.
+    ///
+    /// Passing the same lists repeatedly avoids allocating them for every wait.
+    pub fn fill_ready_entities(&self, ready_entities: &mut ReadyEntities) {
+        fill_ready(
+            &self.subscriptions,
+            &self.ready_indices.subscriptions,
+            &mut ready_entities.subscriptions,
+        );
+        fill_ready(
+            &self.clients,
+            &self.ready_indices.clients,
+            &mut ready_entities.clients,
+        );
+        fill_ready(
+            &self.guard_conditions,
+            &self.ready_indices.guard_conditions,
+            &mut ready_entities.guard_conditions,
+        );
+        fill_ready(
+            &self.services,
+            &self.ready_indices.services,
+            &mut ready_entities.services,
+        );
+        fill_ready(
+            &self.timers,
+            &self.ready_indices.timers,
+            &mut ready_entities.timers,
+        );
+        fill_ready(
+            &self.events,
+            &self.ready_indices.events,
+            &mut ready_entities.events,
+        );
+    }
+
+    /// Returns the subscription with the given index, in the order in which subscriptions were
+    /// added.
+    pub fn subscription(&self, index: usize) -> Option<&Arc<dyn SubscriptionBase>> {
+        self.subscriptions.get(index).map(|s| &s.waitable)
+    }
+
+    /// Returns the client with the given index, in the order in which clients were added.
+    pub fn client(&self, index: usize) -> Option<&Arc<dyn ClientBase>> {
+        self.clients.get(index).map(|c| &c.waitable)
+    }
+
+    /// Returns the guard condition with the given index, in the order in which guard conditions
+    /// were added.
+    pub fn guard_condition(&self, index: usize) -> Option<&Arc<GuardCondition>> {
+        self.guard_conditions.get(index).map(|gc| &gc.waitable)
+    }
+
+    /// Returns the service with the given index, in the order in which services were added.
+    pub fn service(&self, index: usize) -> Option<&Arc<dyn ServiceBase>> {
+        self.services.get(index).map(|s| &s.waitable)
+    }
+
+    /// Returns the timer with the given index, in the order in which timers were added.
+    pub fn timer(&self, index: usize) -> Option<&Arc<dyn TimerBase>> {
+        self.timers.get(index).map(|t| &t.waitable)
+    }
+
+    /// Returns the event with the given index, in the order in which events were added.
+    pub fn event(&self, index: usize) -> Option<&Arc<dyn EventBase>> {
+        self.events.get(index).map(|e| &e.waitable)
+    }
+
+    /// Returns the clients in the wait set.
+    pub(crate) fn clients(&self) -> impl Iterator<Item = &Arc<dyn ClientBase>> {
+        self.clients.iter().map(|c| &c.waitable)
+    }
+
+    /// Returns whether the wait set belongs to the given context.
+    pub(crate) fn has_context(&self, rcl_context_mtx: &Arc<Mutex<rcl_context_t>>) -> bool {
+        Arc::ptr_eq(&self.rcl_context_mtx, rcl_context_mtx)
+    }
+
+    fn clear_rcl_wait_set(&mut self) {
+        // This cannot fail – the rcl_wait_set_clear function only checks that the input handle is
+        // valid, which it always is in our case. Hence, only debug_assert instead of returning
+        // Result.
+        // SAFETY: No preconditions for this function (besides passing in a valid wait set).
+        let ret = unsafe { rcl_wait_set_clear(&mut self.rcl_wait_set) };
+        debug_assert_eq!(ret, 0);
+    }
+
+    // Adds the entities to the rcl_wait_set again after rcl_wait() has set the entries of those
+    // that were not ready to null. This keeps their indices and does not allocate.
+    fn restore_if_needed(&mut self) -> Result<(), RclrsError> {
+        if !self.needs_restore {
+            return Ok(());
+        }
+        self.clear_rcl_wait_set();
+        for subscription in &self.subscriptions {
+            add_rcl_subscription(&mut self.rcl_wait_set, &*subscription.waitable)?;
+        }
+        for guard_condition in &self.guard_conditions {
+            add_rcl_guard_condition(&mut self.rcl_wait_set, &guard_condition.waitable)?;
+        }
+        for timer in &self.timers {
+            add_rcl_timer(&mut self.rcl_wait_set, &*timer.waitable)?;
         }
+        for client in &self.clients {
+            add_rcl_client(&mut self.rcl_wait_set, &*client.waitable)?;
+        }
+        for service in &self.services {
+            add_rcl_service(&mut self.rcl_wait_set, &*service.waitable)?;
+        }
+        for event in &self.events {
+            add_rcl_event(&mut self.rcl_wait_set, &*event.waitable)?;
+        }
+        self.needs_restore = false;
+        Ok(())
+    }
+}
 
-        for (i, service) in self.services.iter().enumerate() {
-            // SAFETY: The `services` entry is an array of pointers, and this dereferencing is
-            // equivalent to
-            // https://github.com/ros2/rcl/blob/35a31b00a12f259d492bf53c0701003bd7f1745c/rcl/include/rcl/wait.h#L419
-            let wait_set_entry = unsafe { *self.rcl_wait_set.services.add(i) };
-            if !wait_set_entry.is_null() {
-                ready_entities.services.push(Arc::clone(&service.waitable));
-            }
+impl ReadyEntities {
+    /// Removes all entities from the lists, keeping their capacity.
+    pub fn clear(&mut self) {
+        self.subscriptions.clear();
+        self.clients.clear();
+        self.guard_conditions.clear();
+        self.services.clear();
+        self.timers.clear();
+        self.events.clear();
+    }
+}
+
+impl ReadyIndices {
+    fn clear(&mut self) {
+        self.subscriptions.clear();
+        self.clients.clear();
+        self.guard_conditions.clear();
+        self.services.clear();
+        self.timers.clear();
+        self.events.clear();
+    }
+}
+
+fn count_live<T: ?Sized>(entities: &[Weak<T>]) -> usize {
+    entities
+        .iter()
+        .filter(|entity| entity.strong_count() > 0)
+        .count()
+}
+
+// SAFETY: The caller must ensure that `entries` points to at least `len` elements.
+unsafe fn collect_ready<T>(entries: *mut *const T, len: usize, ready_indices: &mut Vec<usize>) {
+    for i in 0..len {
+        if !(*entries.add(i)).is_null() {
+            ready_indices.push(i);
         }
-        Ok(ready_entities)
     }
 }
 
+fn fill_ready<T: Clone>(
+    entities: &[ExclusivityGuard<T>],
+    ready_indices: &[usize],
+    ready_entities: &mut Vec<T>,
+) {
+    ready_entities.clear();
+    ready_entities.extend(ready_indices.iter().map(|&i| entities[i].waitable.clone()));
+}
+
+fn add_rcl_subscription(
+    rcl_wait_set: &mut rcl_wait_set_t,
+    subscription: &dyn SubscriptionBase,
+) -> Result<(), RclrsError> {
+    unsafe {
+        // SAFETY: I'm not sure if it's required, but the subscription pointer will remain valid
+        // for as long as the wait set exists, because it's stored in self.subscriptions.
+        // Passing in a null pointer for the third argument is explicitly allowed.
+        rcl_wait_set_add_subscription(
+            rcl_wait_set,
+            &*subscription.handle().lock(),
+            std::ptr::null_mut(),
+        )
+    }
+    .ok()
+}
+
+fn add_rcl_guard_condition(
+    rcl_wait_set: &mut rcl_wait_set_t,
+    guard_condition: &GuardCondition,
+) -> Result<(), RclrsError> {
+    unsafe {
+        // SAFETY: Safe if the wait set and guard condition are initialized
+        rcl_wait_set_add_guard_condition(
+            rcl_wait_set,
+            &*guard_condition.rcl_guard_condition.lock().unwrap(),
+            std::ptr::null_mut(),
+        )
+    }
+    .ok()
+}
+
+fn add_rcl_timer(
+    rcl_wait_set: &mut rcl_wait_set_t,
+    timer: &dyn TimerBase,
+) -> Result<(), RclrsError> {
+    unsafe {
+        // SAFETY: The timer pointer will remain valid for as long as the wait set exists,
+        // because it's stored in self.timers.
+        // Passing in a null pointer for the third argument is explicitly allowed.
+        rcl_wait_set_add_timer(rcl_wait_set, &*timer.handle().lock(), std::ptr::null_mut())
+    }
+    .ok()
+}
+
+fn add_rcl_client(
+    rcl_wait_set: &mut rcl_wait_set_t,
+    client: &dyn ClientBase,
+) -> Result<(), RclrsError> {
+    unsafe {
+        // SAFETY: I'm not sure if it's required, but the client pointer will remain valid
+        // for as long as the wait set exists, because it's stored in self.clients.
+        // Passing in a null pointer for the third argument is explicitly allowed.
+        rcl_wait_set_add_client(
+            rcl_wait_set,
+            &*client.handle().lock() as *const _,
+            core::ptr::null_mut(),
+        )
+    }
+    .ok()
+}
+
+fn add_rcl_service(
+    rcl_wait_set: &mut rcl_wait_set_t,
+    service: &dyn ServiceBase,
+) -> Result<(), RclrsError> {
+    unsafe {
+        // SAFETY: I'm not sure if it's required, but the service pointer will remain valid
+        // for as long as the wait set exists, because it's stored in self.services.
+        // Passing in a null pointer for the third argument is explicitly allowed.
+        rcl_wait_set_add_service(
+            rcl_wait_set,
+            &*service.handle().lock() as *const _,
+            core::ptr::null_mut(),
+        )
+    }
+    .ok()
+}
+
+fn add_rcl_event(
+    rcl_wait_set: &mut rcl_wait_set_t,
+    event: &dyn EventBase,
+) -> Result<(), RclrsError> {
+    unsafe {
+        // SAFETY: The event pointer will remain valid for as long as the wait set exists,
+        // because it's stored in self.events.
+        // Passing in a null pointer for the third argument is explicitly allowed.
+        rcl_wait_set_add_event(rcl_wait_set, &*event.handle().lock(), std::ptr::null_mut())
+    }
+    .ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -428,4 +852,45 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn wait_set_can_be_waited_on_repeatedly() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+
+        let guard_condition = Arc::new(GuardCondition::new(&context));
+
+        let mut wait_set = WaitSet::new(0, 1, 0, 0, 0, 0, &context)?;
+        wait_set.add_guard_condition(Arc::clone(&guard_condition))?;
+        for _ in 0..3 {
+            guard_condition.trigger()?;
+            let ready_indices = wait_set.wait_for_ready_indices(Some(Duration::from_millis(10)))?;
+            assert_eq!(ready_indices.guard_conditions, [0]);
+        }
+        assert!(Arc::ptr_eq(
+            wait_set.guard_condition(0).unwrap(),
+            &guard_condition
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn timer_in_wait_set_readies() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let node = Node::new(&context, "timer_in_wait_set_readies")?;
+
+        let calls = Arc::new(Mutex::new(0));
+        let calls_for_closure = Arc::clone(&calls);
+        let _timer = node.create_timer(Duration::from_millis(1), move || {
+            *calls_for_closure.lock().unwrap() += 1;
+        })?;
+
+        let mut wait_set = WaitSet::new_for_node(&node)?;
+        let ready_indices = wait_set.wait_for_ready_indices(Some(Duration::from_secs(1)))?;
+        assert_eq!(ready_indices.timers, [0]);
+        wait_set.timer(0).unwrap().execute()?;
+        assert_eq!(*calls.lock().unwrap(), 1);
+
+        Ok(())
+    }
 }
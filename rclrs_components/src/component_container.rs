@@ -0,0 +1,18 @@
+use std::env;
+
+use anyhow::{Error, Result};
+use rclrs::{ComponentOptions, ComponentRegistry};
+use rclrs_components::ComponentManager;
+
+fn main() -> Result<(), Error> {
+    let context = rclrs::Context::new(env::args())?;
+
+    let node = rclrs::create_node(&context, "ComponentManager")?;
+
+    // Like in rclcpp_components, the arguments of the container are not forwarded to the
+    // components unless requested with the 'forward_global_arguments' extra argument
+    let options = ComponentOptions::new(&context).use_global_arguments(false);
+    let _manager = ComponentManager::new(&node, options, ComponentRegistry::new())?;
+
+    rclrs::spin(node).map_err(|err| err.into())
+}
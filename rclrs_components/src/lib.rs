@@ -0,0 +1,506 @@
+//! A component container for `rclrs` nodes.
+//!
+//! The [`ComponentManager`] serves the `composition_interfaces` services that are used by
+//! `ros2 component load`, `ros2 component unload` and `ros2 component list`. Components are
+//! looked up in a static [`ComponentRegistry`] first, and otherwise loaded from the shared library
+//! of the requested package, which must define its components with
+//! [`rclrs::register_components!`].
+//!
+//! Each loaded component is spun by its own executor in its own thread.
+#![warn(missing_docs)]
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use composition_interfaces::srv::{
+    ListNodes, ListNodes_Request, ListNodes_Response, LoadNode, LoadNode_Request,
+    LoadNode_Response, UnloadNode, UnloadNode_Request, UnloadNode_Response,
+};
+use rcl_interfaces::msg::{Parameter, ParameterType, ParameterValue};
+use rclrs::{
+    rmw_request_id_t, Component, ComponentOptions, ComponentRegistry, Node, RclReturnCode,
+    RclrsError, Service, SingleThreadedExecutor, REGISTER_COMPONENTS_SYMBOL,
+};
+
+// How often the thread of a component checks whether it should stop
+const SPIN_PERIOD: Duration = Duration::from_millis(100);
+
+/// Loads and unloads components on request.
+///
+/// The services are created under the `~/_container` namespace of the node, like in the
+/// `rclcpp_components` container, so that they are found by the `ros2 component` commands.
+pub struct ComponentManager {
+    node: Arc<Node>,
+    _load_node_service: Arc<Service<LoadNode>>,
+    _unload_node_service: Arc<Service<UnloadNode>>,
+    _list_nodes_service: Arc<Service<ListNodes>>,
+}
+
+/// An error that occurred while loading a component.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The package of the component was not found in any sourced prefix.
+    PackageNotFound {
+        /// The name of the package.
+        package: String,
+    },
+    /// The shared library of the package could not be loaded.
+    LibraryLoadingError {
+        /// The path of the library.
+        path: PathBuf,
+        /// The error from loading the library or its registration function.
+        error: libloading::Error,
+    },
+    /// Neither the static registry nor the package's library contain the component.
+    ComponentNotFound {
+        /// The name of the package.
+        package: String,
+        /// The class name of the component.
+        class_name: String,
+    },
+    /// The request to load the component is invalid.
+    InvalidRequest {
+        /// Why the request is invalid.
+        reason: String,
+    },
+    /// Creating the component failed.
+    CreationError(RclrsError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PackageNotFound { package } => {
+                write!(f, "Package '{}' was not found in any prefix", package)
+            }
+            Self::LibraryLoadingError { path, error } => {
+                write!(
+                    f,
+                    "Loading the library '{}' failed: {}",
+                    path.display(),
+                    error
+                )
+            }
+            Self::ComponentNotFound {
+                package,
+                class_name,
+            } => write!(
+                f,
+                "Component '{}' was not found in package '{}'",
+                class_name, package
+            ),
+            Self::InvalidRequest { reason } => write!(f, "{}", reason),
+            Self::CreationError(error) => write!(f, "Creating the component failed: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::LibraryLoadingError { error, .. } => Some(error),
+            Self::CreationError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+struct ManagerState {
+    options: ComponentOptions,
+    registry: ComponentRegistry,
+    next_unique_id: u64,
+    // Declared before the libraries so that components are dropped before their code is unloaded
+    components: BTreeMap<u64, RunningComponent>,
+    libraries: Vec<LoadedLibrary>,
+}
+
+struct LoadedLibrary {
+    package: String,
+    registry: ComponentRegistry,
+    _library: libloading::Library,
+}
+
+struct RunningComponent {
+    full_node_name: String,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ComponentManager {
+    /// Creates the services of the container on the given node.
+    ///
+    /// Components are created with the given options, which are extended by the arguments in
+    /// each request. Components in the registry are preferred over components with the same
+    /// class name in shared libraries.
+    pub fn new(
+        node: &Arc<Node>,
+        options: ComponentOptions,
+        registry: ComponentRegistry,
+    ) -> Result<Self, RclrsError> {
+        let state = Arc::new(Mutex::new(ManagerState {
+            options,
+            registry,
+            next_unique_id: 1,
+            components: BTreeMap::new(),
+            libraries: Vec::new(),
+        }));
+
+        let load_state = Arc::clone(&state);
+        let _load_node_service = node.create_service::<LoadNode, _>(
+            "~/_container/load_node",
+            move |_: &rmw_request_id_t, request: LoadNode_Request| {
+                let mut state = load_state.lock().unwrap();
+                match state.load(request) {
+                    Ok((unique_id, full_node_name)) => LoadNode_Response {
+                        success: true,
+                        error_message: String::new(),
+                        full_node_name,
+                        unique_id,
+                    },
+                    Err(error) => LoadNode_Response {
+                        success: false,
+                        error_message: error.to_string(),
+                        ..Default::default()
+                    },
+                }
+            },
+        )?;
+
+        let unload_state = Arc::clone(&state);
+        let _unload_node_service = node.create_service::<UnloadNode, _>(
+            "~/_container/unload_node",
+            move |_: &rmw_request_id_t, request: UnloadNode_Request| {
+                let removed = unload_state
+                    .lock()
+                    .unwrap()
+                    .components
+                    .remove(&request.unique_id);
+                match removed {
+                    // The component is stopped and dropped when it goes out of scope
+                    Some(_) => UnloadNode_Response {
+                        success: true,
+                        error_message: String::new(),
+                    },
+                    None => UnloadNode_Response {
+                        success: false,
+                        error_message: format!(
+                            "No node found with unique_id: {}",
+                            request.unique_id
+                        ),
+                    },
+                }
+            },
+        )?;
+
+        let list_state = Arc::clone(&state);
+        let _list_nodes_service = node.create_service::<ListNodes, _>(
+            "~/_container/list_nodes",
+            move |_: &rmw_request_id_t, _: ListNodes_Request| {
+                let mut state = list_state.lock().unwrap();
+                // Components that stopped spinning because of an error are not running anymore
+                state
+                    .components
+                    .retain(|_, component| !component.has_stopped());
+                ListNodes_Response {
+                    full_node_names: state
+                        .components
+                        .values()
+                        .map(|component| component.full_node_name.clone())
+                        .collect(),
+                    unique_ids: state.components.keys().copied().collect(),
+                }
+            },
+        )?;
+
+        Ok(Self {
+            node: Arc::clone(node),
+            _load_node_service,
+            _unload_node_service,
+            _list_nodes_service,
+        })
+    }
+
+    /// Returns the node of the container.
+    pub fn node(&self) -> &Arc<Node> {
+        &self.node
+    }
+}
+
+impl ManagerState {
+    fn load(&mut self, request: LoadNode_Request) -> Result<(u64, String), LoadError> {
+        let options = self.component_options(&request)?;
+        let factory = match self.registry.get(&request.plugin_name).copied() {
+            Some(factory) => factory,
+            None => *self
+                .library_registry(&request.package_name)?
+                .get(&request.plugin_name)
+                .ok_or_else(|| LoadError::ComponentNotFound {
+                    package: request.package_name.clone(),
+                    class_name: request.plugin_name.clone(),
+                })?,
+        };
+        let component = factory.create(options).map_err(LoadError::CreationError)?;
+        let full_node_name = component.node().fully_qualified_name();
+        let unique_id = self.next_unique_id;
+        self.next_unique_id += 1;
+        self.components.insert(
+            unique_id,
+            RunningComponent::spawn(component, &full_node_name),
+        );
+        Ok((unique_id, full_node_name))
+    }
+
+    // Translates the request into command line arguments for the component's node
+    fn component_options(&self, request: &LoadNode_Request) -> Result<ComponentOptions, LoadError> {
+        let mut arguments = vec![String::from("--ros-args")];
+        if !request.node_name.is_empty() {
+            arguments.push(String::from("-r"));
+            arguments.push(format!("__node:={}", request.node_name));
+        }
+        if !request.node_namespace.is_empty() {
+            arguments.push(String::from("-r"));
+            arguments.push(format!("__ns:={}", request.node_namespace));
+        }
+        for rule in &request.remap_rules {
+            arguments.push(String::from("-r"));
+            arguments.push(rule.clone());
+        }
+        for parameter in &request.parameters {
+            arguments.push(String::from("-p"));
+            arguments.push(format!(
+                "{}:={}",
+                parameter.name,
+                parameter_value_from_msg(&parameter.name, &parameter.value)?.to_yaml()
+            ));
+        }
+
+        let mut options = self.options.clone().arguments(arguments);
+        for extra_argument in &request.extra_arguments {
+            match extra_argument.name.as_str() {
+                "use_intra_process_comms" => {
+                    options = options.use_intra_process_comms(bool_argument(extra_argument)?);
+                }
+                "forward_global_arguments" => {
+                    options = options.use_global_arguments(bool_argument(extra_argument)?);
+                }
+                // Unknown extra arguments are ignored, as in rclcpp_components
+                _ => {}
+            }
+        }
+        Ok(options)
+    }
+
+    // Returns the registry of the package's library, loading the library if necessary
+    fn library_registry(&mut self, package: &str) -> Result<&ComponentRegistry, LoadError> {
+        let index = match self
+            .libraries
+            .iter()
+            .position(|library| library.package == package)
+        {
+            Some(index) => index,
+            None => {
+                self.libraries.push(LoadedLibrary::load(package)?);
+                self.libraries.len() - 1
+            }
+        };
+        Ok(&self.libraries[index].registry)
+    }
+}
+
+impl LoadedLibrary {
+    fn load(package: &str) -> Result<Self, LoadError> {
+        let package_not_found = || LoadError::PackageNotFound {
+            package: package.to_owned(),
+        };
+        // Creating this is pretty cheap, it just parses an env var
+        let ament = ament_rs::Ament::new().map_err(|_| package_not_found())?;
+        let prefix = PathBuf::from(ament.find_package(package).ok_or_else(package_not_found)?);
+        let resource = prefix
+            .join("share/ament_index/resource_index/rclrs_components")
+            .join(package);
+        let relative_path = std::fs::read_to_string(resource)
+            .ok()
+            .and_then(|content| content.lines().next().map(|line| line.trim().to_owned()))
+            .filter(|line| !line.is_empty());
+        let path = match relative_path {
+            Some(relative_path) => prefix.join(relative_path),
+            None => default_library_path(prefix, package),
+        };
+        let library_error = |error| LoadError::LibraryLoadingError {
+            path: path.clone(),
+            error,
+        };
+
+        // SAFETY: Loading a library may execute its initialization routines. Libraries with
+        // components are expected to be built with rclrs, and to not cause problems there.
+        let library = unsafe { libloading::Library::new(&path) }.map_err(library_error)?;
+        let mut registry = ComponentRegistry::new();
+        {
+            // SAFETY: The function is defined by rclrs::register_components!, which ensures that
+            // it has this signature.
+            let register_components = unsafe {
+                library.get::<fn(&mut ComponentRegistry)>(REGISTER_COMPONENTS_SYMBOL.as_bytes())
+            }
+            .map_err(library_error)?;
+            register_components(&mut registry);
+        }
+        Ok(Self {
+            package: package.to_owned(),
+            registry,
+            _library: library,
+        })
+    }
+}
+
+impl RunningComponent {
+    fn spawn(component: Box<dyn Component>, full_node_name: &str) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_in_thread = Arc::clone(&stop);
+        let thread = std::thread::spawn(move || {
+            let executor = SingleThreadedExecutor::new();
+            if let Err(error) = executor.add_node(component.node()) {
+                eprintln!("Failed to add component to executor: {}", error);
+                return;
+            }
+            while !stop_in_thread.load(Ordering::Relaxed) {
+                match executor.spin_once(Some(SPIN_PERIOD)) {
+                    Ok(())
+                    | Err(RclrsError::RclError {
+                        code: RclReturnCode::Timeout,
+                        ..
+                    }) => {}
+                    Err(error) => {
+                        eprintln!("Failed to spin component: {}", error);
+                        break;
+                    }
+                }
+            }
+        });
+        Self {
+            full_node_name: full_node_name.to_owned(),
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    // The thread only stops by itself when spinning the component failed
+    fn has_stopped(&self) -> bool {
+        self.thread
+            .as_ref()
+            .map_or(true, |thread| thread.is_finished())
+    }
+}
+
+impl Drop for RunningComponent {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            // A panic in a component has already been printed, so the result can be ignored
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn default_library_path(prefix: PathBuf, package: &str) -> PathBuf {
+    prefix.join("bin").join(format!("{}.dll", package))
+}
+
+#[cfg(target_os = "macos")]
+fn default_library_path(prefix: PathBuf, package: &str) -> PathBuf {
+    prefix.join("lib").join(format!("lib{}.dylib", package))
+}
+
+#[cfg(all(not(target_os = "windows"), not(target_os = "macos")))]
+fn default_library_path(prefix: PathBuf, package: &str) -> PathBuf {
+    prefix.join("lib").join(format!("lib{}.so", package))
+}
+
+fn bool_argument(argument: &Parameter) -> Result<bool, LoadError> {
+    if argument.value.type_ == ParameterType::PARAMETER_BOOL {
+        Ok(argument.value.bool_value)
+    } else {
+        Err(LoadError::InvalidRequest {
+            reason: format!(
+                "Extra component argument '{}' must be a boolean",
+                argument.name
+            ),
+        })
+    }
+}
+
+// Converts a parameter value from the request into the value type of rclrs, which can format
+// it as YAML for the command line. The message types of rclrs are not public, so
+// rclrs::ParameterValue::try_from() can't be used with this message.
+fn parameter_value_from_msg(
+    name: &str,
+    value: &ParameterValue,
+) -> Result<rclrs::ParameterValue, LoadError> {
+    let value = match value.type_ {
+        ParameterType::PARAMETER_BOOL => rclrs::ParameterValue::Bool(value.bool_value),
+        ParameterType::PARAMETER_INTEGER => rclrs::ParameterValue::Integer(value.integer_value),
+        ParameterType::PARAMETER_DOUBLE => rclrs::ParameterValue::Double(value.double_value),
+        ParameterType::PARAMETER_STRING => {
+            rclrs::ParameterValue::String(value.string_value.clone())
+        }
+        ParameterType::PARAMETER_BOOL_ARRAY => {
+            rclrs::ParameterValue::BoolArray(value.bool_array_value.clone())
+        }
+        ParameterType::PARAMETER_INTEGER_ARRAY => {
+            rclrs::ParameterValue::IntegerArray(value.integer_array_value.clone())
+        }
+        ParameterType::PARAMETER_DOUBLE_ARRAY => {
+            rclrs::ParameterValue::DoubleArray(value.double_array_value.clone())
+        }
+        ParameterType::PARAMETER_STRING_ARRAY => {
+            rclrs::ParameterValue::StringArray(value.string_array_value.clone())
+        }
+        // Byte arrays can't be distinguished from integer arrays in YAML
+        _ => {
+            return Err(LoadError::InvalidRequest {
+                reason: format!("Parameter '{}' has an unsupported type", name),
+            })
+        }
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parameter_value_from_msg() {
+        let value = |type_| ParameterValue {
+            type_,
+            ..Default::default()
+        };
+        let mut double = value(ParameterType::PARAMETER_DOUBLE);
+        double.double_value = 1.0;
+        assert_eq!(
+            parameter_value_from_msg("p", &double).unwrap(),
+            rclrs::ParameterValue::Double(1.0)
+        );
+        let mut string = value(ParameterType::PARAMETER_STRING);
+        string.string_value = String::from("it's 42");
+        assert_eq!(
+            parameter_value_from_msg("p", &string).unwrap().to_yaml(),
+            r#""it's 42""#
+        );
+        let mut integers = value(ParameterType::PARAMETER_INTEGER_ARRAY);
+        integers.integer_array_value = vec![1, -2];
+        assert_eq!(
+            parameter_value_from_msg("p", &integers).unwrap(),
+            rclrs::ParameterValue::IntegerArray(vec![1, -2])
+        );
+        assert!(
+            parameter_value_from_msg("p", &value(ParameterType::PARAMETER_BYTE_ARRAY)).is_err()
+        );
+        assert!(parameter_value_from_msg("p", &value(ParameterType::PARAMETER_NOT_SET)).is_err());
+    }
+}
@@ -0,0 +1,224 @@
+//! Derive macros for `rclrs`.
+//!
+//! These macros are re-exported by `rclrs`, and should be used from there.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Fields, LitStr, Meta};
+
+/// Implements `rclrs::Parameters` for a struct with named fields.
+///
+/// See the documentation of the `Parameters` trait in `rclrs` for the available attributes.
+#[proc_macro_derive(Parameters, attributes(parameter))]
+pub fn derive_parameters(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive_parameters_impl(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+// The attributes of one field
+struct ParameterField {
+    field: syn::Ident,
+    ty: syn::Type,
+    name: String,
+    default: Option<Expr>,
+    description: String,
+    read_only: bool,
+    range: Option<(Expr, Expr, Option<Expr>)>,
+}
+
+fn derive_parameters_impl(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "Parameters can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "Parameters can only be derived for structs",
+            ))
+        }
+    };
+    let parameters = fields
+        .iter()
+        .map(parse_field)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let defaults = parameters.iter().map(|p| {
+        let field = &p.field;
+        match &p.default {
+            Some(default) => quote! { #field: ::std::convert::Into::into(#default) },
+            None => quote! { #field: ::std::default::Default::default() },
+        }
+    });
+    let descriptors = parameters.iter().map(|p| {
+        let ParameterField {
+            ty,
+            name,
+            description,
+            read_only,
+            ..
+        } = p;
+        let range = match &p.range {
+            Some((from, to, step)) => {
+                let step = match step {
+                    Some(step) => quote! { #step },
+                    None => quote! { ::std::default::Default::default() },
+                };
+                quote! {
+                    ::std::option::Option::Some(
+                        <#ty as ::rclrs::NumericParameter>::range(#from, #to, #step)
+                    )
+                }
+            }
+            None => quote! { ::std::option::Option::None },
+        };
+        quote! {
+            ::rclrs::ParameterDescriptor {
+                name: ::std::string::String::from(#name),
+                kind: <#ty as ::rclrs::ParameterVariant>::KIND,
+                description: ::std::string::String::from(#description),
+                read_only: #read_only,
+                range: #range,
+            }
+        }
+    });
+    let values = parameters.iter().map(|p| {
+        let ParameterField { field, name, .. } = p;
+        quote! {
+            (
+                ::std::string::String::from(#name),
+                ::rclrs::ParameterVariant::to_parameter_value(&self.#field),
+            )
+        }
+    });
+    let setters = parameters.iter().map(|p| {
+        let ParameterField {
+            field, ty, name, ..
+        } = p;
+        quote! {
+            #name => {
+                self.#field = <#ty as ::rclrs::ParameterVariant>::from_parameter_value(value)
+                    .ok_or_else(|| ::std::string::String::from("Wrong parameter type"))?;
+            }
+        }
+    });
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::rclrs::Parameters for #ident #ty_generics #where_clause {
+            fn with_defaults() -> Self {
+                Self {
+                    #(#defaults,)*
+                }
+            }
+
+            fn descriptors() -> ::std::vec::Vec<::rclrs::ParameterDescriptor> {
+                ::std::vec![#(#descriptors,)*]
+            }
+
+            fn values(&self) -> ::std::vec::Vec<(::std::string::String, ::rclrs::ParameterValue)> {
+                ::std::vec![#(#values,)*]
+            }
+
+            fn set_value(
+                &mut self,
+                name: &str,
+                value: ::rclrs::ParameterValue,
+            ) -> ::std::result::Result<(), ::std::string::String> {
+                match name {
+                    #(#setters)*
+                    _ => {
+                        return ::std::result::Result::Err(::std::string::String::from(
+                            "The parameter is not declared",
+                        ))
+                    }
+                }
+                ::std::result::Result::Ok(())
+            }
+        }
+    })
+}
+
+fn parse_field(field: &syn::Field) -> syn::Result<ParameterField> {
+    // Only named fields are passed to this function
+    let ident = field.ident.clone().unwrap();
+    let mut parameter = ParameterField {
+        field: ident.clone(),
+        ty: field.ty.clone(),
+        name: ident.to_string(),
+        default: None,
+        description: doc_comment(&field.attrs),
+        read_only: false,
+        range: None,
+    };
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("parameter"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("default") {
+                parameter.default = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("description") {
+                parameter.description = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("name") {
+                parameter.name = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("read_only") {
+                parameter.read_only = true;
+            } else if meta.path.is_ident("range") {
+                let (mut from, mut to, mut step) = (None, None, None);
+                meta.parse_nested_meta(|meta| {
+                    let bound = if meta.path.is_ident("from") {
+                        &mut from
+                    } else if meta.path.is_ident("to") {
+                        &mut to
+                    } else if meta.path.is_ident("step") {
+                        &mut step
+                    } else {
+                        return Err(meta.error("Expected `from`, `to` or `step`"));
+                    };
+                    *bound = Some(meta.value()?.parse::<Expr>()?);
+                    Ok(())
+                })?;
+                match (from, to) {
+                    (Some(from), Some(to)) => parameter.range = Some((from, to, step)),
+                    _ => return Err(meta.error("A range needs both `from` and `to`")),
+                }
+            } else {
+                return Err(
+                    meta.error("Expected `default`, `description`, `name`, `read_only` or `range`")
+                );
+            }
+            Ok(())
+        })?;
+    }
+    Ok(parameter)
+}
+
+// Joins the lines of the doc comments, which are `#[doc = "..."]` attributes
+fn doc_comment(attrs: &[syn::Attribute]) -> String {
+    let lines: Vec<_> = attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+                Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(doc),
+                    ..
+                }) => Some(doc.value().trim().to_owned()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    lines.join("\n").trim().to_owned()
+}
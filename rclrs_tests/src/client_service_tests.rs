@@ -1,8 +1,60 @@
-use rclrs::{Client, Service};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rclrs::{
+    Client, ClientError, Context, Node, NodeBuilder, RclReturnCode, RclrsError, Service,
+    ServiceResponder, SingleThreadedExecutor, QOS_PROFILE_DEFAULT, QOS_PROFILE_SERVICES_DEFAULT,
+};
+use rosidl_shared::cdr::{from_bytes, to_vec, CdrDeserialize, CdrSerialize};
+use rosidl_shared::{MiddlewareMessage, MiddlewareService};
+use test_msgs::srv;
 
 fn assert_send<T: Send>() {}
 fn assert_sync<T: Sync>() {}
 
+// Spins the node until the condition holds, for at most one second
+fn spin_until(node: &Arc<Node>, mut condition: impl FnMut() -> bool) -> Result<(), RclrsError> {
+    let executor = SingleThreadedExecutor::new();
+    executor.add_node(node)?;
+    for _ in 0..100 {
+        if condition() {
+            return Ok(());
+        }
+        match executor.spin_once(Some(Duration::from_millis(10))) {
+            Ok(())
+            | Err(RclrsError::RclError {
+                code: RclReturnCode::Timeout,
+                ..
+            }) => {}
+            Err(error) => return Err(error),
+        }
+    }
+    assert!(condition(), "The condition didn't hold after spinning");
+    Ok(())
+}
+
+// Sends a request to a handler as CDR bytes, like a transport that is not the ROS middleware
+fn call_over_bytes<S>(
+    request: &S::Request,
+    handler: impl Fn(S::Request) -> S::Response,
+) -> (String, S::Response)
+where
+    S: MiddlewareService,
+    S::Request: CdrSerialize + CdrDeserialize,
+    S::Response: CdrSerialize + CdrDeserialize,
+{
+    let request_bytes = to_vec(request).unwrap();
+    let response_bytes = to_vec(&handler(from_bytes(&request_bytes).unwrap())).unwrap();
+    let type_name = format!(
+        "{} ({} -> {})",
+        S::TYPE_NAME,
+        <S::Request as MiddlewareMessage>::TYPE_NAME,
+        <S::Response as MiddlewareMessage>::TYPE_NAME
+    );
+    (type_name, from_bytes(&response_bytes).unwrap())
+}
+
 #[test]
 fn client_is_send_and_sync() {
     assert_send::<Client<test_msgs::srv::Arrays>>();
@@ -14,3 +66,275 @@ fn service_is_send_and_sync() {
     assert_send::<Service<test_msgs::srv::Arrays>>();
     assert_sync::<Service<test_msgs::srv::Arrays>>();
 }
+
+#[test]
+fn service_responder_is_send() {
+    assert_send::<ServiceResponder<test_msgs::srv::Arrays>>();
+}
+
+#[test]
+fn clients_and_services_created_with_qos_are_discoverable() -> Result<(), RclrsError> {
+    let context = Context::new([])?;
+    let node = NodeBuilder::new(&context, "qos_test_node")
+        .namespace("/test_clients_and_services_created_with_qos")
+        .build()?;
+
+    let _service = node.create_service_with_qos::<srv::Empty, _>(
+        "qos_test_service",
+        QOS_PROFILE_DEFAULT,
+        |_, _| srv::Empty_Response::default(),
+    )?;
+    let _client = node
+        .create_client_with_qos::<srv::Empty>("qos_test_service", QOS_PROFILE_SERVICES_DEFAULT)?;
+
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    let service_name = "/test_clients_and_services_created_with_qos/qos_test_service";
+    let service_names_and_types = node.get_service_names_and_types_by_node(
+        "qos_test_node",
+        "/test_clients_and_services_created_with_qos",
+    )?;
+    assert!(service_names_and_types.contains_key(service_name));
+    let client_names_and_types = node.get_client_names_and_types_by_node(
+        "qos_test_node",
+        "/test_clients_and_services_created_with_qos",
+    )?;
+    assert!(client_names_and_types.contains_key(service_name));
+    Ok(())
+}
+
+#[test]
+fn pending_requests_can_be_pruned() -> Result<(), RclrsError> {
+    let context = Context::new([])?;
+    let node = NodeBuilder::new(&context, "prune_test_node")
+        .namespace("/test_pending_requests_can_be_pruned")
+        .build()?;
+    let client = node.create_client::<srv::Empty>("unavailable_service")?;
+
+    client.async_send_request_with_callback(srv::Empty_Request::default(), |_| {})?;
+    client.async_send_request_with_callback(srv::Empty_Request::default(), |_| {})?;
+    assert_eq!(client.num_pending_requests(), 2);
+    assert_eq!(client.prune_pending_requests(), 2);
+    assert_eq!(client.num_pending_requests(), 0);
+    Ok(())
+}
+
+#[test]
+fn blocking_call_times_out() -> Result<(), RclrsError> {
+    let context = Context::new([])?;
+    let node = NodeBuilder::new(&context, "blocking_call_test_node")
+        .namespace("/test_blocking_call_times_out")
+        .build()?;
+    let client = node.create_client::<srv::Empty>("unavailable_service")?;
+
+    let result = client.call(
+        srv::Empty_Request::default(),
+        Some(std::time::Duration::from_millis(50)),
+    );
+    assert!(matches!(
+        result,
+        Err(RclrsError::RequestFailed {
+            err: ClientError::Timeout
+        })
+    ));
+    assert_eq!(client.num_pending_requests(), 0);
+    Ok(())
+}
+
+#[test]
+fn blocking_call_returns_the_response() -> Result<(), RclrsError> {
+    let context = Context::new([])?;
+    let service_node = Arc::new(
+        NodeBuilder::new(&context, "blocking_call_service_node")
+            .namespace("/test_blocking_call_returns_the_response")
+            .build()?,
+    );
+    let _service = service_node.create_service::<srv::BasicTypes, _>(
+        "doubling_service",
+        |_, request: srv::BasicTypes_Request| srv::BasicTypes_Response {
+            int32_value: request.int32_value * 2,
+            ..Default::default()
+        },
+    )?;
+    let client_node = NodeBuilder::new(&context, "blocking_call_client_node")
+        .namespace("/test_blocking_call_returns_the_response")
+        .build()?;
+    let client = client_node.create_client::<srv::BasicTypes>("doubling_service")?;
+
+    // The service node is spun in another thread, since call() blocks
+    let stop = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        let server = scope.spawn(|| -> Result<(), RclrsError> {
+            let executor = SingleThreadedExecutor::new();
+            executor.add_node(&service_node)?;
+            // Stops eventually even if the test fails
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while !stop.load(Ordering::Relaxed) && Instant::now() < deadline {
+                match executor.spin_once(Some(Duration::from_millis(10))) {
+                    Ok(())
+                    | Err(RclrsError::RclError {
+                        code: RclReturnCode::Timeout,
+                        ..
+                    }) => {}
+                    Err(error) => return Err(error),
+                }
+            }
+            Ok(())
+        });
+        let result = (|| -> Result<srv::BasicTypes_Response, RclrsError> {
+            let deadline = Instant::now() + Duration::from_secs(2);
+            while !client.service_is_ready()? && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            client.call(
+                srv::BasicTypes_Request {
+                    int32_value: 7,
+                    ..Default::default()
+                },
+                Some(Duration::from_secs(2)),
+            )
+        })();
+        stop.store(true, Ordering::Relaxed);
+        server.join().unwrap()?;
+        assert_eq!(result?.int32_value, 14);
+        Ok::<(), RclrsError>(())
+    })?;
+    assert_eq!(client.num_pending_requests(), 0);
+    Ok(())
+}
+
+#[test]
+fn async_call_times_out() -> Result<(), RclrsError> {
+    use futures::FutureExt;
+
+    let context = Context::new([])?;
+    let node = Arc::new(
+        NodeBuilder::new(&context, "async_call_test_node")
+            .namespace("/test_async_call_times_out")
+            .build()?,
+    );
+    let client = node.create_client::<srv::Empty>("unavailable_service")?;
+
+    let future =
+        client.call_async_with_timeout(srv::Empty_Request::default(), Duration::from_millis(50));
+    futures::pin_mut!(future);
+    // The request is sent when the future is first polled
+    assert!(future.as_mut().now_or_never().is_none());
+    assert_eq!(client.num_pending_requests(), 1);
+    let mut result = None;
+    spin_until(&node, || {
+        result = future.as_mut().now_or_never();
+        result.is_some()
+    })?;
+    assert!(matches!(
+        result,
+        Some(Err(RclrsError::RequestFailed {
+            err: ClientError::Timeout
+        }))
+    ));
+    assert_eq!(client.num_pending_requests(), 0);
+    Ok(())
+}
+
+#[test]
+fn callback_request_times_out() -> Result<(), RclrsError> {
+    let context = Context::new([])?;
+    let node = Arc::new(
+        NodeBuilder::new(&context, "callback_request_test_node")
+            .namespace("/test_callback_request_times_out")
+            .build()?,
+    );
+    let client = node.create_client::<srv::Empty>("unavailable_service")?;
+
+    let result = Arc::new(Mutex::new(None));
+    let result_in_callback = Arc::clone(&result);
+    client.async_send_request_with_callback_and_timeout(
+        srv::Empty_Request::default(),
+        Duration::from_millis(50),
+        move |response| {
+            *result_in_callback.lock().unwrap() = Some(response);
+        },
+    )?;
+    spin_until(&node, || result.lock().unwrap().is_some())?;
+    assert!(matches!(
+        *result.lock().unwrap(),
+        Some(Err(ClientError::Timeout))
+    ));
+    assert_eq!(client.num_pending_requests(), 0);
+    Ok(())
+}
+
+#[test]
+fn deferred_service_responds_later() -> Result<(), RclrsError> {
+    let context = Context::new([])?;
+    let node = Arc::new(
+        NodeBuilder::new(&context, "deferred_service_test_node")
+            .namespace("/test_deferred_service_responds_later")
+            .build()?,
+    );
+    let responders = Arc::new(Mutex::new(Vec::new()));
+    let responders_in_callback = Arc::clone(&responders);
+    let _service = node.create_deferred_service::<srv::BasicTypes, _>(
+        "deferred_service",
+        move |request, responder| {
+            responders_in_callback
+                .lock()
+                .unwrap()
+                .push((request, responder));
+        },
+    )?;
+    let client = node.create_client::<srv::BasicTypes>("deferred_service")?;
+    spin_until(&node, || client.service_is_ready().unwrap())?;
+
+    let response = Arc::new(Mutex::new(None));
+    let response_in_callback = Arc::clone(&response);
+    client.async_send_request_with_callback(
+        srv::BasicTypes_Request {
+            int32_value: 7,
+            ..Default::default()
+        },
+        move |response: srv::BasicTypes_Response| {
+            *response_in_callback.lock().unwrap() = Some(response);
+        },
+    )?;
+    spin_until(&node, || !responders.lock().unwrap().is_empty())?;
+    // The service callback has returned without responding
+    assert!(response.lock().unwrap().is_none());
+    assert_eq!(client.num_pending_requests(), 1);
+
+    let (request, responder) = responders.lock().unwrap().pop().unwrap();
+    std::thread::spawn(move || {
+        responder.send(srv::BasicTypes_Response {
+            int32_value: request.int32_value * 2,
+            ..Default::default()
+        })
+    })
+    .join()
+    .unwrap()?;
+    spin_until(&node, || response.lock().unwrap().is_some())?;
+    assert_eq!(response.lock().unwrap().as_ref().unwrap().int32_value, 14);
+    assert_eq!(client.num_pending_requests(), 0);
+    Ok(())
+}
+
+#[test]
+fn middleware_service_pairs_request_and_response() {
+    let (type_name, response) = call_over_bytes::<srv::BasicTypes>(
+        &srv::BasicTypes_Request {
+            int32_value: 7,
+            string_value: String::from("seven"),
+            ..Default::default()
+        },
+        |request| srv::BasicTypes_Response {
+            int32_value: request.int32_value * 2,
+            string_value: request.string_value + " twice",
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        type_name,
+        "test_msgs/srv/BasicTypes (test_msgs/srv/BasicTypes_Request -> \
+         test_msgs/srv/BasicTypes_Response)"
+    );
+    assert_eq!(response.int32_value, 14);
+    assert_eq!(response.string_value, "seven twice");
+}
@@ -0,0 +1,132 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use composition_interfaces::srv::{
+    ListNodes, ListNodes_Request, ListNodes_Response, LoadNode, LoadNode_Request, UnloadNode,
+    UnloadNode_Request,
+};
+use rclrs::{
+    Client, Component, ComponentOptions, ComponentRegistry, Context, Node, NodeBuilder,
+    RclReturnCode, RclrsError, SingleThreadedExecutor,
+};
+use rclrs_components::ComponentManager;
+
+struct TestComponent {
+    node: Arc<Node>,
+}
+
+impl Component for TestComponent {
+    fn new(options: ComponentOptions) -> Result<Self, RclrsError> {
+        let node = Arc::new(options.node_builder("test_component").build()?);
+        Ok(Self { node })
+    }
+
+    fn node(&self) -> &Arc<Node> {
+        &self.node
+    }
+}
+
+// Waits until the service of the client is available, for at most two seconds
+fn wait_for_service<T: rosidl_runtime_rs::Service>(client: &Client<T>) -> Result<(), RclrsError> {
+    for _ in 0..100 {
+        if client.service_is_ready()? {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    panic!("The service is not available");
+}
+
+#[test]
+fn component_manager_loads_lists_and_unloads_nodes() -> Result<(), RclrsError> {
+    let context = Context::new([])?;
+    let mut registry = ComponentRegistry::new();
+    registry.register::<TestComponent>("rclrs_tests::TestComponent");
+    let container_node = Arc::new(
+        NodeBuilder::new(&context, "container")
+            .namespace("/test_component_manager")
+            .build()?,
+    );
+    let manager =
+        ComponentManager::new(&container_node, ComponentOptions::new(&context), registry)?;
+    let client_node = NodeBuilder::new(&context, "client")
+        .namespace("/test_component_manager")
+        .build()?;
+    let load_node = client_node.create_client::<LoadNode>("container/_container/load_node")?;
+    let unload_node =
+        client_node.create_client::<UnloadNode>("container/_container/unload_node")?;
+    let list_nodes = client_node.create_client::<ListNodes>("container/_container/list_nodes")?;
+
+    // The container is spun in another thread, since the calls below block
+    let stop = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        let container = scope.spawn(|| -> Result<(), RclrsError> {
+            let executor = SingleThreadedExecutor::new();
+            executor.add_node(manager.node())?;
+            // Stops eventually even if the test fails
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while !stop.load(Ordering::Relaxed) && Instant::now() < deadline {
+                match executor.spin_once(Some(Duration::from_millis(10))) {
+                    Ok(())
+                    | Err(RclrsError::RclError {
+                        code: RclReturnCode::Timeout,
+                        ..
+                    }) => {}
+                    Err(error) => return Err(error),
+                }
+            }
+            Ok(())
+        });
+        let result = (|| -> Result<(), RclrsError> {
+            wait_for_service(&load_node)?;
+            wait_for_service(&unload_node)?;
+            wait_for_service(&list_nodes)?;
+            let timeout = Some(Duration::from_secs(2));
+
+            let response = load_node.call(
+                LoadNode_Request {
+                    package_name: String::from("rclrs_tests"),
+                    plugin_name: String::from("rclrs_tests::TestComponent"),
+                    node_name: String::from("loaded"),
+                    ..Default::default()
+                },
+                timeout,
+            )?;
+            assert!(response.success, "{}", response.error_message);
+            assert_eq!(response.full_node_name, "/test_component_manager/loaded");
+            let unique_id = response.unique_id;
+
+            let response = load_node.call(
+                LoadNode_Request {
+                    package_name: String::from("rclrs_tests"),
+                    plugin_name: String::from("rclrs_tests::MissingComponent"),
+                    ..Default::default()
+                },
+                timeout,
+            )?;
+            assert!(!response.success);
+            assert!(!response.error_message.is_empty());
+
+            let response = list_nodes.call(ListNodes_Request::default(), timeout)?;
+            assert_eq!(
+                response,
+                ListNodes_Response {
+                    full_node_names: vec![String::from("/test_component_manager/loaded")],
+                    unique_ids: vec![unique_id],
+                }
+            );
+
+            let request = UnloadNode_Request { unique_id };
+            assert!(unload_node.call(request.clone(), timeout)?.success);
+            let response = list_nodes.call(ListNodes_Request::default(), timeout)?;
+            assert!(response.unique_ids.is_empty());
+            // The node can only be unloaded once
+            assert!(!unload_node.call(request, timeout)?.success);
+            Ok(())
+        })();
+        stop.store(true, Ordering::Relaxed);
+        container.join().unwrap()?;
+        result
+    })
+}
@@ -1,5 +1,8 @@
 #![cfg(test)]
 
 mod client_service_tests;
+mod component_tests;
 mod graph_tests;
+mod parameter_tests;
 mod pub_sub_tests;
+mod serialization_tests;
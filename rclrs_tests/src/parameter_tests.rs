@@ -0,0 +1,112 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rclrs::{
+    Context, NodeBuilder, ParameterDescriptor, ParameterKind, ParameterRange, ParameterValue,
+    Parameters, RclReturnCode, RclrsError, SingleThreadedExecutor, SyncParametersClient,
+};
+
+#[derive(Clone, Parameters)]
+struct TestParameters {
+    /// The rate in Hz.
+    #[parameter(default = 10.0, range(from = 1.0, to = 100.0))]
+    rate: f64,
+    #[parameter(default = "base_link", read_only)]
+    frame_id: String,
+}
+
+#[test]
+fn parameter_client_uses_the_services_of_declared_parameters() -> Result<(), RclrsError> {
+    let context = Context::new([])?;
+    let parameter_node = Arc::new(
+        NodeBuilder::new(&context, "parameters")
+            .namespace("/test_parameter_client")
+            .build()?,
+    );
+    let parameters = parameter_node.declare_parameters::<TestParameters>()?;
+    let client_node = NodeBuilder::new(&context, "client")
+        .namespace("/test_parameter_client")
+        .build()?;
+    let client = SyncParametersClient::new(&client_node, "parameters")?;
+
+    // The parameter node is spun in another thread, since the calls below block
+    let stop = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        let server = scope.spawn(|| -> Result<(), RclrsError> {
+            let executor = SingleThreadedExecutor::new();
+            executor.add_node(&parameter_node)?;
+            // Stops eventually even if the test fails
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while !stop.load(Ordering::Relaxed) && Instant::now() < deadline {
+                match executor.spin_once(Some(Duration::from_millis(10))) {
+                    Ok(())
+                    | Err(RclrsError::RclError {
+                        code: RclReturnCode::Timeout,
+                        ..
+                    }) => {}
+                    Err(error) => return Err(error),
+                }
+            }
+            Ok(())
+        });
+        let result = (|| -> Result<(), RclrsError> {
+            assert!(client.wait_for_service(Some(Duration::from_secs(2)))?);
+            let timeout = Some(Duration::from_secs(2));
+
+            // There is one entry per name, also for names that are not declared
+            assert_eq!(
+                client.get_parameters(&["rate", "missing", "frame_id"], timeout)?,
+                vec![
+                    Some(ParameterValue::Double(10.0)),
+                    None,
+                    Some(ParameterValue::from("base_link")),
+                ]
+            );
+            assert_eq!(
+                client.get_parameter_types(&["missing", "frame_id"], timeout)?,
+                vec![None, Some(ParameterKind::String)]
+            );
+            assert_eq!(
+                client.describe_parameters(&["rate", "missing"], timeout)?,
+                vec![
+                    Some(ParameterDescriptor {
+                        name: String::from("rate"),
+                        kind: ParameterKind::Double,
+                        description: String::from("The rate in Hz."),
+                        read_only: false,
+                        range: Some(ParameterRange::Double {
+                            from: 1.0,
+                            to: 100.0,
+                            step: 0.0,
+                        }),
+                    }),
+                    None,
+                ]
+            );
+
+            let results = client.set_parameters(
+                &[
+                    ("rate", ParameterValue::Double(20.0)),
+                    ("frame_id", ParameterValue::from("map")),
+                    ("rate", ParameterValue::Double(0.0)),
+                    ("missing", ParameterValue::Bool(true)),
+                ],
+                timeout,
+            )?;
+            assert_eq!(results.len(), 4);
+            assert!(results[0].is_ok());
+            assert!(results[1..].iter().all(Result::is_err));
+            assert_eq!(parameters.get().rate, 20.0);
+            assert_eq!(parameters.get().frame_id, "base_link");
+            assert_eq!(
+                client.get_parameters(&["rate"], timeout)?,
+                vec![Some(ParameterValue::Double(20.0))]
+            );
+            Ok(())
+        })();
+        stop.store(true, Ordering::Relaxed);
+        server.join().unwrap()?;
+        result
+    })
+}
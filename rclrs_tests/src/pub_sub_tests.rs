@@ -1,6 +1,11 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
 use rclrs::{
-    AnySubscriptionCallback, LoanedMessage, MessageInfo, Publisher, ReadOnlyLoanedMessage,
-    Subscription, SubscriptionCallback,
+    AnySubscriptionCallback, Context, LoanedMessage, MessageInfo, Node, Publisher,
+    QoSDurabilityPolicy, QoSHistoryPolicy, QoSProfile, RclReturnCode, RclrsError,
+    ReadOnlyLoanedMessage, SequenceStatistics, SequenceStatus, SingleThreadedExecutor,
+    Subscription, SubscriptionCallback, QOS_PROFILE_DEFAULT,
 };
 
 fn assert_send<T: Send>() {}
@@ -43,6 +48,11 @@ fn callback_conversion() {
         cb.into_callback(),
         AnySubscriptionCallback::<Message>::RegularWithMessageInfo(_)
     ));
+    let cb = |_msg: Message, _info: MessageInfo, _status: SequenceStatus| {};
+    assert!(matches!(
+        cb.into_callback(),
+        AnySubscriptionCallback::<Message>::RegularWithSequenceStatus(_)
+    ));
     let cb = |_msg: Box<Message>| {};
     assert!(matches!(
         cb.into_callback(),
@@ -53,6 +63,16 @@ fn callback_conversion() {
         cb.into_callback(),
         AnySubscriptionCallback::<Message>::BoxedWithMessageInfo(_)
     ));
+    let cb = |_msg: Arc<Message>| {};
+    assert!(matches!(
+        cb.into_callback(),
+        AnySubscriptionCallback::<Message>::Shared(_)
+    ));
+    let cb = |_msg: Arc<Message>, _info: MessageInfo| {};
+    assert!(matches!(
+        cb.into_callback(),
+        AnySubscriptionCallback::<Message>::SharedWithMessageInfo(_)
+    ));
     let cb = |_msg: ReadOnlyLoanedMessage<'_, Message>| {};
     assert!(matches!(
         cb.into_callback(),
@@ -64,3 +84,269 @@ fn callback_conversion() {
         AnySubscriptionCallback::<Message>::LoanedWithMessageInfo(_)
     ));
 }
+
+#[test]
+fn idiomatic_and_rmw_conversions_agree() {
+    use rosidl_runtime_rs::Message;
+
+    let mut message = test_msgs::msg::UnboundedSequences::default();
+    message.string_values = vec![String::from("a"), String::from("bc")];
+    message.basic_types_values = vec![test_msgs::msg::BasicTypes {
+        int32_value: 3,
+        ..Default::default()
+    }];
+    let rmw_message = test_msgs::msg::rmw::UnboundedSequences::from(&message);
+    assert_eq!(
+        test_msgs::msg::UnboundedSequences::from_rmw_message_ref(&rmw_message),
+        message
+    );
+    assert_eq!(
+        test_msgs::msg::UnboundedSequences::from(&rmw_message),
+        message
+    );
+    assert_eq!(
+        test_msgs::msg::UnboundedSequences::from(rmw_message),
+        message
+    );
+
+    let message = test_msgs::msg::Arrays::default();
+    let rmw_message: test_msgs::msg::rmw::Arrays = message.clone().into();
+    assert_eq!(
+        test_msgs::msg::Arrays::from_rmw_message_ref(&rmw_message),
+        message
+    );
+}
+
+#[test]
+fn intra_process_messages_are_delivered_once() -> Result<(), RclrsError> {
+    type Message = test_msgs::msg::BasicTypes;
+    let context = Context::new([])?;
+    let publisher_node = Arc::new(
+        Node::builder(&context, "intra_process_publisher")
+            .use_intra_process_comms(true)
+            .build()?,
+    );
+    let subscription_node = Arc::new(
+        Node::builder(&context, "intra_process_subscription")
+            .use_intra_process_comms(true)
+            .build()?,
+    );
+    let topic = "intra_process_messages_are_delivered_once";
+    let publisher = publisher_node.create_publisher::<Message>(topic, QOS_PROFILE_DEFAULT)?;
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let received_in_callback = Arc::clone(&received);
+    let _subscription = subscription_node.create_subscription(
+        topic,
+        QOS_PROFILE_DEFAULT,
+        move |msg: Arc<Message>, info: MessageInfo| {
+            received_in_callback.lock().unwrap().push((msg, info));
+        },
+    )?;
+
+    let executor = SingleThreadedExecutor::new();
+    executor.add_node(&subscription_node)?;
+    for value in 1..=3 {
+        publisher.publish(Message {
+            int32_value: value,
+            ..Default::default()
+        })?;
+    }
+    // Wait for a potential duplicate from the middleware as well
+    spin_for(&executor, Duration::from_millis(200))?;
+
+    let received = received.lock().unwrap();
+    let values: Vec<_> = received.iter().map(|(msg, _)| msg.int32_value).collect();
+    assert_eq!(values, [1, 2, 3]);
+    let sequence_numbers: Vec<_> = received
+        .iter()
+        .map(|(_, info)| info.publication_sequence_number)
+        .collect();
+    assert_eq!(sequence_numbers, [1, 2, 3]);
+    Ok(())
+}
+
+#[test]
+fn intra_process_publishers_also_publish_to_other_subscriptions() -> Result<(), RclrsError> {
+    type Message = test_msgs::msg::BasicTypes;
+    let context = Context::new([])?;
+    let intra_process_node = Arc::new(
+        Node::builder(&context, "intra_process_node")
+            .use_intra_process_comms(true)
+            .build()?,
+    );
+    let external_node = Arc::new(Node::builder(&context, "external_node").build()?);
+    let topic = "intra_process_publishers_also_publish_to_other_subscriptions";
+    let publisher = intra_process_node.create_publisher::<Message>(topic, QOS_PROFILE_DEFAULT)?;
+    let intra_process_values = Arc::new(Mutex::new(Vec::new()));
+    let intra_process_values_in_callback = Arc::clone(&intra_process_values);
+    let _intra_process_subscription = intra_process_node.create_subscription(
+        topic,
+        QOS_PROFILE_DEFAULT,
+        move |msg: Message| {
+            intra_process_values_in_callback
+                .lock()
+                .unwrap()
+                .push(msg.int32_value);
+        },
+    )?;
+    let external_values = Arc::new(Mutex::new(Vec::new()));
+    let external_values_in_callback = Arc::clone(&external_values);
+    let _external_subscription =
+        external_node.create_subscription(topic, QOS_PROFILE_DEFAULT, move |msg: Message| {
+            external_values_in_callback
+                .lock()
+                .unwrap()
+                .push(msg.int32_value);
+        })?;
+    wait_for_subscriptions(&publisher, 2)?;
+
+    let executor = SingleThreadedExecutor::new();
+    executor.add_node(&intra_process_node)?;
+    executor.add_node(&external_node)?;
+    for value in 1..=3 {
+        publisher.publish(Message {
+            int32_value: value,
+            ..Default::default()
+        })?;
+    }
+    spin_for(&executor, Duration::from_millis(500))?;
+
+    assert_eq!(*intra_process_values.lock().unwrap(), [1, 2, 3]);
+    assert_eq!(*external_values.lock().unwrap(), [1, 2, 3]);
+    Ok(())
+}
+
+#[test]
+fn transient_local_messages_are_published_to_the_middleware() -> Result<(), RclrsError> {
+    type Message = test_msgs::msg::BasicTypes;
+    let context = Context::new([])?;
+    let node = Arc::new(
+        Node::builder(&context, "transient_local_node")
+            .use_intra_process_comms(true)
+            .build()?,
+    );
+    let topic = "transient_local_messages_are_published_to_the_middleware";
+    let qos = QoSProfile {
+        durability: QoSDurabilityPolicy::TransientLocal,
+        ..QOS_PROFILE_DEFAULT
+    };
+    let publisher = node.create_publisher::<Message>(topic, qos)?;
+    // Published before the subscription exists, so only the middleware can deliver it
+    publisher.publish(Message {
+        int32_value: 1,
+        ..Default::default()
+    })?;
+    let values = Arc::new(Mutex::new(Vec::new()));
+    let values_in_callback = Arc::clone(&values);
+    let _subscription = node.create_subscription(topic, qos, move |msg: Message| {
+        values_in_callback.lock().unwrap().push(msg.int32_value);
+    })?;
+    wait_for_subscriptions(&publisher, 1)?;
+
+    let executor = SingleThreadedExecutor::new();
+    executor.add_node(&node)?;
+    for value in 2..=3 {
+        publisher.publish(Message {
+            int32_value: value,
+            ..Default::default()
+        })?;
+    }
+    spin_for(&executor, Duration::from_millis(500))?;
+
+    assert_eq!(*values.lock().unwrap(), [1, 2, 3]);
+    Ok(())
+}
+
+#[test]
+fn sequence_tracking_reports_lost_messages() -> Result<(), RclrsError> {
+    type Message = test_msgs::msg::BasicTypes;
+    let context = Context::new([])?;
+    let node = Arc::new(
+        Node::builder(&context, "sequence_tracking_node")
+            .use_intra_process_comms(true)
+            .build()?,
+    );
+    let topic = "sequence_tracking_reports_lost_messages";
+    // Only the newest message is kept, so messages that are not taken in time are lost
+    let qos = QoSProfile {
+        history: QoSHistoryPolicy::KeepLast { depth: 1 },
+        ..QOS_PROFILE_DEFAULT
+    };
+    let publisher = node.create_publisher::<Message>(topic, qos)?;
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let received_in_callback = Arc::clone(&received);
+    let subscription = node.create_subscription(
+        topic,
+        qos,
+        move |msg: Message, _info: MessageInfo, status: SequenceStatus| {
+            received_in_callback
+                .lock()
+                .unwrap()
+                .push((msg.int32_value, status));
+        },
+    )?;
+    wait_for_subscriptions(&publisher, 1)?;
+
+    let executor = SingleThreadedExecutor::new();
+    executor.add_node(&node)?;
+    publisher.publish(Message {
+        int32_value: 1,
+        ..Default::default()
+    })?;
+    spin_for(&executor, Duration::from_millis(200))?;
+    for value in 2..=4 {
+        publisher.publish(Message {
+            int32_value: value,
+            ..Default::default()
+        })?;
+    }
+    spin_for(&executor, Duration::from_millis(200))?;
+
+    assert_eq!(
+        *received.lock().unwrap(),
+        [
+            (1, SequenceStatus::First),
+            (4, SequenceStatus::Gap { lost: 2 })
+        ]
+    );
+    assert_eq!(
+        subscription.sequence_statistics(),
+        Some(SequenceStatistics {
+            received: 2,
+            lost: 2,
+            out_of_order: 0,
+            duplicates: 0,
+        })
+    );
+    Ok(())
+}
+
+// Spins the executor until the duration has passed, to receive all messages that are on the way
+fn spin_for(executor: &SingleThreadedExecutor, duration: Duration) -> Result<(), RclrsError> {
+    let deadline = Instant::now() + duration;
+    while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+        match executor.spin_once(Some(timeout.min(Duration::from_millis(20)))) {
+            Ok(())
+            | Err(RclrsError::RclError {
+                code: RclReturnCode::Timeout,
+                ..
+            }) => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(())
+}
+
+// Waits until the middleware has matched the publisher with the given number of subscriptions
+fn wait_for_subscriptions<T: rosidl_runtime_rs::Message>(
+    publisher: &Publisher<T>,
+    count: usize,
+) -> Result<(), RclrsError> {
+    for _ in 0..100 {
+        if publisher.get_subscription_count()? >= count {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    panic!("the publisher was not matched with {} subscriptions", count);
+}
@@ -0,0 +1,40 @@
+use rclrs::{deserialize_message, serialize_message};
+use rosidl_runtime_rs::Message;
+use rosidl_shared::cdr::{from_bytes, to_vec, CdrDeserialize, CdrSerialize};
+
+fn assert_cdr_matches_rmw<T>(message: T)
+where
+    T: Message + CdrSerialize + CdrDeserialize + PartialEq,
+{
+    let rmw_bytes = serialize_message(&message).unwrap();
+    assert_eq!(to_vec(&message).unwrap(), rmw_bytes);
+    assert_eq!(from_bytes::<T>(&rmw_bytes).unwrap(), message);
+    assert_eq!(deserialize_message::<T>(&rmw_bytes).unwrap(), message);
+}
+
+#[test]
+fn cdr_matches_rmw_serialization() {
+    assert_cdr_matches_rmw(test_msgs::msg::BasicTypes::default());
+    assert_cdr_matches_rmw(test_msgs::msg::Defaults::default());
+    assert_cdr_matches_rmw(test_msgs::msg::Strings::default());
+    assert_cdr_matches_rmw(test_msgs::msg::Arrays::default());
+    assert_cdr_matches_rmw(test_msgs::msg::BoundedSequences::default());
+    assert_cdr_matches_rmw(test_msgs::msg::UnboundedSequences::default());
+    assert_cdr_matches_rmw(test_msgs::msg::MultiNested::default());
+    assert_cdr_matches_rmw(test_msgs::msg::Empty::default());
+}
+
+#[test]
+fn cdr_matches_rmw_serialization_of_non_default_values() {
+    let mut message = test_msgs::msg::UnboundedSequences::default();
+    message.bool_values = vec![true, false, true];
+    message.int16_values = vec![-1, 2];
+    message.float64_values = vec![1.5];
+    message.string_values = vec![String::from("a"), String::new(), String::from("bcd")];
+    message.basic_types_values = vec![test_msgs::msg::BasicTypes {
+        char_value: b'x',
+        int64_value: -7,
+        ..Default::default()
+    }];
+    assert_cdr_matches_rmw(message);
+}
@@ -120,6 +120,18 @@ foreach(dep ${target_dependencies})
   endif()
 endforeach()
 
+# Type description hashes are only available since Iron, where rosidl_generate_interfaces()
+# defines the description tuples
+set(_type_description_arguments "")
+if(DEFINED ${rosidl_generate_interfaces_TARGET}__DESCRIPTION_TUPLES)
+  set(_type_description_arguments
+    TYPE_DESCRIPTION_TUPLES "${${rosidl_generate_interfaces_TARGET}__DESCRIPTION_TUPLES}")
+  foreach(_type_description_tuple ${${rosidl_generate_interfaces_TARGET}__DESCRIPTION_TUPLES})
+    string(REGEX REPLACE "^[^:]*:" "" _type_description_file "${_type_description_tuple}")
+    list(APPEND target_dependencies "${_type_description_file}")
+  endforeach()
+endif()
+
 set(generator_arguments_file "${CMAKE_CURRENT_BINARY_DIR}/rosidl_generator_rs__arguments.json")
 rosidl_write_generator_arguments(
   "${generator_arguments_file}"
@@ -130,11 +142,19 @@ rosidl_write_generator_arguments(
   OUTPUT_DIR "${_output_path}"
   TEMPLATE_DIR "${rosidl_generator_rs_TEMPLATE_DIR}"
   TARGET_DEPENDENCIES ${target_dependencies}
+  ${_type_description_arguments}
 )
 
+# Set ROSIDL_GENERATOR_RS_HEAPLESS to generate crates that don't need an allocator
+if(ROSIDL_GENERATOR_RS_HEAPLESS)
+  set(_heapless "true")
+else()
+  set(_heapless "false")
+endif()
+
 file(READ ${generator_arguments_file} contents)
 string(REPLACE "\n}"
-  ",\n  \"package_version\": \"${${PROJECT_NAME}_VERSION}\"\n}" contents ${contents})
+  ",\n  \"package_version\": \"${${PROJECT_NAME}_VERSION}\",\n  \"heapless\": ${_heapless}\n}" contents ${contents})
 file(WRITE ${generator_arguments_file} ${contents})
 
 file(MAKE_DIRECTORY "${_output_path}")
@@ -6,24 +6,36 @@ edition = "2021"
 [dependencies]
 heapless = "0.7.16"
 rosidl_runtime_rs = { version = "0.3", optional = true }
-rosidl_shared = { version = "*" }
-serde = { version = "1", optional = true, features = ["derive"] }
+@[if heapless]@
+rosidl_shared = { version = "*", default-features = false }
+serde = { version = "1", optional = true, default-features = false, features = ["derive"] }
+@[else]@
+rosidl_shared = { version = "*", default-features = false, features = ["alloc"] }
+serde = { version = "1", optional = true, default-features = false, features = ["derive", "alloc"] }
+@[end if]@
 serde-big-array = { version = "0.5.1", optional = true }
 @[for dep in dependency_packages]@
-@(dep) = "*"
+@(dep) = { version = "*", default-features = false }
 @[end for]@
 
 [features]
-default = []
+default = ["std"]
+# Disable to build the crate as no_std
 @{
-serde_features = ["dep:serde", "dep:serde-big-array", "rosidl_runtime_rs?/serde", "heapless/serde"]
+std_features = ["rosidl_shared/std", "serde?/std"]
+for dep in dependency_packages:
+	std_features.append("{}/std".format(dep))
+}@
+std = @(std_features)
+@{
+serde_features = ["dep:serde", "dep:serde-big-array", "rosidl_runtime_rs?/serde", "rosidl_shared/serde", "heapless/serde"]
 for dep in dependency_packages:
 	serde_features.append("{}/serde".format(dep))
 }@
 serde = @(serde_features)
 # Enable to prevent linking against the ROS middleware
 @{
-with_middleware_features = ["dep:rosidl_runtime_rs"]
+with_middleware_features = ["std", "dep:rosidl_runtime_rs"]
 for dep in dependency_packages:
 	with_middleware_features.append("{}/with_middleware".format(dep))
 }@
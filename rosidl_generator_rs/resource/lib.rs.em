@@ -1,4 +1,9 @@
 #![allow(non_camel_case_types)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+@[if not heapless]@
+extern crate alloc;
+@[end if]@
 
 @[if len(msg_specs) > 0]@
 pub mod msg;
@@ -9,7 +9,12 @@ TEMPLATE(
     annotated_comments=annotated_comments,
     pre_field_serde=pre_field_serde,
     get_idiomatic_rs_type=get_idiomatic_rs_type,
-    constant_value_to_rs=constant_value_to_rs)
+    constant_value_to_rs=constant_value_to_rs,
+    default_value_to_rs=default_value_to_rs,
+    field_type_to_rs=field_type_to_rs,
+    value_type_to_rs=value_type_to_rs,
+    metadata_value_to_rs=metadata_value_to_rs,
+    type_hashes=type_hashes)
 }@
 }  // mod rmw
 
@@ -22,5 +27,10 @@ TEMPLATE(
     annotated_comments=annotated_comments,
     pre_field_serde=pre_field_serde,
     get_idiomatic_rs_type=get_idiomatic_rs_type,
-    constant_value_to_rs=constant_value_to_rs)
+    constant_value_to_rs=constant_value_to_rs,
+    default_value_to_rs=default_value_to_rs,
+    field_type_to_rs=field_type_to_rs,
+    value_type_to_rs=value_type_to_rs,
+    metadata_value_to_rs=metadata_value_to_rs,
+    type_hashes=type_hashes)
 }@
@@ -1,5 +1,7 @@
 @{
 from rosidl_parser.definition import AbstractGenericString
+from rosidl_parser.definition import AbstractSequence
+from rosidl_parser.definition import AbstractWString
 from rosidl_parser.definition import Array
 from rosidl_parser.definition import BasicType
 from rosidl_parser.definition import BoundedSequence
@@ -27,6 +29,7 @@ type_name = msg_spec.structure.namespaced_type.name
 #[doc = "@(line)"]
 @[end for]@
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub struct @(type_name) {
 @[for member in msg_spec.structure.members]@
@@ -54,11 +57,204 @@ impl @(type_name) {
 }
 @[end if]
 
-#[cfg(feature = "with_middleware")]
 impl Default for @(type_name) {
   fn default() -> Self {
-@#  This has the benefit of automatically setting the right default values
-    <Self as rosidl_runtime_rs::Message>::from_rmw_message(crate::@(subfolder)::rmw::@(type_name)::default())
+@#  These are the same values as set by the C init function
+    Self {
+@[for member in msg_spec.structure.members]@
+      @(get_rs_name(member.name)): @(default_value_to_rs(member)),
+@[end for]@
+    }
+  }
+}
+
+@# Most bounds are enforced by the field types, so the builder converts its inputs with checked
+@# conversions and reports every violation when building instead of panicking. The bounds of wide
+@# strings are only checked by validate(), which also covers messages that were not built.
+#[doc = "Builder for [`@(type_name)`] that checks the bounds of the fields."]
+#[doc = ""]
+#[doc = "Fields that are not set keep their default values."]
+#[derive(Clone, Debug, Default)]
+pub struct @(type_name)Builder {
+  msg: @(type_name),
+  violations: rosidl_shared::validation::Violations,
+}
+
+impl @(type_name) {
+  /// Creates a builder for this message.
+  pub fn builder() -> @(type_name)Builder {
+    @(type_name)Builder::default()
+  }
+
+  /// Returns an error listing every field that violates its bound, with the field path.
+  ///
+  /// This is needed for messages that were not created with [`Self::builder()`], or whose
+  /// fields were changed afterwards.
+  pub fn validate(&self) -> Result<(), rosidl_shared::validation::ValidationError> {
+    let mut violations = rosidl_shared::validation::Violations::default();
+    self.collect_violations(&mut violations);
+    rosidl_shared::validation::ValidationError::check(&violations)
+  }
+
+@{
+def has_checked_bound(type_):
+    if isinstance(type_, (Array, AbstractSequence)):
+        type_ = type_.value_type
+    return isinstance(type_, (BoundedString, BoundedWString, NamedType, NamespacedType))
+
+any_checked_bound = any(has_checked_bound(member.type) for member in msg_spec.structure.members)
+}@
+  /// Records every field that violates its bound, including the fields of nested messages.
+  ///
+  /// The violations of nested messages have the field names of the nested message.
+@[if any_checked_bound]@
+  pub fn collect_violations(&self, violations: &mut rosidl_shared::validation::Violations) {
+@[else]@
+  pub fn collect_violations(&self, _violations: &mut rosidl_shared::validation::Violations) {
+@[end if]@
+@[for member in msg_spec.structure.members]@
+@{
+field_name = get_rs_name(member.name)
+}@
+@[    if isinstance(member.type, BoundedString)]@
+    rosidl_shared::validation::check_bounded_string("@(member.name)", None, &self.@(field_name), @(member.type.maximum_size), violations);
+@[    elif isinstance(member.type, BoundedWString)]@
+    rosidl_shared::validation::check_bounded_wstring("@(member.name)", None, &self.@(field_name), @(member.type.maximum_size), violations);
+@[    elif isinstance(member.type, (NamedType, NamespacedType))]@
+    self.@(field_name).collect_violations(violations);
+@[    elif isinstance(member.type, (Array, AbstractSequence)) and isinstance(member.type.value_type, BoundedString)]@
+    for (index, elem) in self.@(field_name).iter().enumerate() {
+      rosidl_shared::validation::check_bounded_string("@(member.name)", Some(index), elem, @(member.type.value_type.maximum_size), violations);
+    }
+@[    elif isinstance(member.type, (Array, AbstractSequence)) and isinstance(member.type.value_type, BoundedWString)]@
+    for (index, elem) in self.@(field_name).iter().enumerate() {
+      rosidl_shared::validation::check_bounded_wstring("@(member.name)", Some(index), elem, @(member.type.value_type.maximum_size), violations);
+    }
+@[    elif isinstance(member.type, (Array, AbstractSequence)) and isinstance(member.type.value_type, (NamedType, NamespacedType))]@
+    for elem in self.@(field_name).iter() {
+      elem.collect_violations(violations);
+    }
+@[    end if]@
+@[end for]@
+  }
+}
+
+impl @(type_name)Builder {
+@[for member in msg_spec.structure.members]@
+@{
+field_name = get_rs_name(member.name)
+}@
+  /// Sets the `@(member.name)` field.
+@[    if isinstance(member.type, BoundedString)]@
+  pub fn @(field_name)(mut self, value: &str) -> Self {
+    self.violations.clear_field("@(member.name)");
+    self.msg.@(field_name) = rosidl_shared::validation::to_bounded_string("@(member.name)", None, value, @(member.type.maximum_size), &mut self.violations);
+    self
+  }
+@[    elif isinstance(member.type, BoundedWString)]@
+  pub fn @(field_name)(mut self, value: &str) -> Self {
+    self.violations.clear_field("@(member.name)");
+    self.msg.@(field_name) = rosidl_shared::validation::to_bounded_wstring("@(member.name)", None, value, @(member.type.maximum_size), &mut self.violations);
+    self
+  }
+@[    elif isinstance(member.type, (UnboundedString, UnboundedWString))]@
+  pub fn @(field_name)(mut self, value: impl Into<alloc::string::String>) -> Self {
+    self.msg.@(field_name) = value.into();
+    self
+  }
+@[    elif isinstance(member.type, (Array, AbstractSequence))]@
+@{
+if isinstance(member.type, Array):
+    convert_fn = 'to_array'
+elif isinstance(member.type, BoundedSequence):
+    convert_fn = 'to_bounded_sequence'
+else:
+    convert_fn = 'to_sequence'
+}@
+@[        if isinstance(member.type.value_type, BoundedString)]@
+  pub fn @(field_name)(mut self, value: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+    self.violations.clear_field("@(member.name)");
+    let converted = rosidl_shared::validation::@(convert_fn)("@(member.name)", value, |index, elem, violations| rosidl_shared::validation::to_bounded_string("@(member.name)", Some(index), elem.as_ref(), @(member.type.value_type.maximum_size), violations), &mut self.violations);
+@[        elif isinstance(member.type.value_type, BoundedWString)]@
+  pub fn @(field_name)(mut self, value: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+    self.violations.clear_field("@(member.name)");
+    let converted = rosidl_shared::validation::@(convert_fn)("@(member.name)", value, |index, elem, violations| rosidl_shared::validation::to_bounded_wstring("@(member.name)", Some(index), elem.as_ref(), @(member.type.value_type.maximum_size), violations), &mut self.violations);
+@[        elif isinstance(member.type.value_type, (UnboundedString, UnboundedWString))]@
+  pub fn @(field_name)(mut self, value: impl IntoIterator<Item = impl Into<alloc::string::String>>) -> Self {
+    self.violations.clear_field("@(member.name)");
+    let converted = rosidl_shared::validation::@(convert_fn)("@(member.name)", value, |_, elem, _| elem.into(), &mut self.violations);
+@[        else]@
+  pub fn @(field_name)(mut self, value: impl IntoIterator<Item = @(get_idiomatic_rs_type(member.type.value_type))>) -> Self {
+    self.violations.clear_field("@(member.name)");
+    let converted = rosidl_shared::validation::@(convert_fn)("@(member.name)", value, |_, elem, _| elem, &mut self.violations);
+@[        end if]@
+@[        if isinstance(member.type, Array)]@
+    if let Some(array) = converted {
+      self.msg.@(field_name) = array;
+    }
+@[        else]@
+    self.msg.@(field_name) = converted;
+@[        end if]@
+    self
+  }
+@[    else]@
+  pub fn @(field_name)(mut self, value: @(get_idiomatic_rs_type(member.type))) -> Self {
+    self.msg.@(field_name) = value;
+    self
+  }
+@[    end if]@
+
+@[end for]@
+  /// Returns an error listing every field that violates its bound, with the field path.
+  ///
+  /// Besides the values that didn't fit when they were set, this reports the violations that
+  /// [`@(type_name)::validate()`] finds, e.g. in nested messages that were set as a whole.
+  pub fn validate(&self) -> Result<(), rosidl_shared::validation::ValidationError> {
+    let mut violations = self.violations.clone();
+    self.msg.collect_violations(&mut violations);
+    rosidl_shared::validation::ValidationError::check(&violations)
+  }
+
+  /// Builds the message, or returns an error listing every field that violates its bound.
+  pub fn build(self) -> Result<@(type_name), rosidl_shared::validation::ValidationError> {
+    self.validate()?;
+    Ok(self.msg)
+  }
+}
+
+@# Wide strings have the same Rust type as strings, so they need dedicated functions
+impl rosidl_shared::cdr::CdrSerialize for @(type_name) {
+  fn serialize<W: rosidl_shared::cdr::CdrWrite>(&self, serializer: &mut rosidl_shared::cdr::CdrSerializer<W>) -> Result<(), rosidl_shared::cdr::CdrError> {
+@[for member in msg_spec.structure.members]@
+@[    if isinstance(member.type, AbstractWString)]@
+    serializer.serialize_wstring(&self.@(get_rs_name(member.name)))?;
+@[    elif isinstance(member.type, Array) and isinstance(member.type.value_type, AbstractWString)]@
+    serializer.serialize_array_with(&self.@(get_rs_name(member.name)), |serializer, elem| serializer.serialize_wstring(elem))?;
+@[    elif isinstance(member.type, AbstractSequence) and isinstance(member.type.value_type, AbstractWString)]@
+    serializer.serialize_sequence_with(&self.@(get_rs_name(member.name)), |serializer, elem| serializer.serialize_wstring(elem))?;
+@[    else]@
+    serializer.serialize(&self.@(get_rs_name(member.name)))?;
+@[    end if]@
+@[end for]@
+    Ok(())
+  }
+}
+
+impl rosidl_shared::cdr::CdrDeserialize for @(type_name) {
+  fn deserialize(deserializer: &mut rosidl_shared::cdr::CdrDeserializer<'_>) -> Result<Self, rosidl_shared::cdr::CdrError> {
+    Ok(Self {
+@[for member in msg_spec.structure.members]@
+@[    if isinstance(member.type, AbstractWString)]@
+      @(get_rs_name(member.name)): deserializer.deserialize_wstring()?,
+@[    elif isinstance(member.type, Array) and isinstance(member.type.value_type, AbstractWString)]@
+      @(get_rs_name(member.name)): deserializer.deserialize_array_with(|deserializer| deserializer.deserialize_wstring())?,
+@[    elif isinstance(member.type, AbstractSequence) and isinstance(member.type.value_type, AbstractWString)]@
+      @(get_rs_name(member.name)): deserializer.deserialize_sequence_with(|deserializer| deserializer.deserialize_wstring())?,
+@[    else]@
+      @(get_rs_name(member.name)): deserializer.deserialize()?,
+@[    end if]@
+@[end for]@
+    })
   }
 }
 
@@ -67,7 +263,13 @@ impl rosidl_runtime_rs::Message for @(type_name) {
   type RmwMsg = crate::@(subfolder)::rmw::@(type_name);
 
   fn into_rmw_message(msg_cow: std::borrow::Cow<'_, Self>) -> std::borrow::Cow<'_, Self::RmwMsg> {
-    match msg_cow {
+    Self::try_into_rmw_message(msg_cow).unwrap_or_else(|error| panic!("{}", error))
+  }
+
+@# The bounds of strings are checked by validate(), so their conversions below can't fail.
+  fn try_into_rmw_message(msg_cow: std::borrow::Cow<'_, Self>) -> Result<std::borrow::Cow<'_, Self::RmwMsg>, rosidl_runtime_rs::ValidationError> {
+    msg_cow.validate()?;
+    Ok(match msg_cow {
       std::borrow::Cow::Owned(msg) => std::borrow::Cow::Owned(Self::RmwMsg {
 @[for member in msg_spec.structure.members]@
 @#
@@ -78,7 +280,8 @@ impl rosidl_runtime_rs::Message for @(type_name) {
         @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
           .map(|elem| elem.as_str().into()),
 @[        elif isinstance(member.type.value_type, BoundedString) or isinstance(member.type.value_type, BoundedWString)]@
-        @(get_rs_name(member.name)): unimplemented!(),
+        @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
+          .map(|elem| (&elem).try_into().unwrap()),
 @[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
         @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
           .map(|elem| @(get_idiomatic_rs_type(member.type.value_type))::into_rmw_message(std::borrow::Cow::Owned(elem)).into_owned()),
@@ -102,7 +305,10 @@ impl rosidl_runtime_rs::Message for @(type_name) {
           .map(|elem| elem.as_str().into())
           .collect(),
 @[        elif isinstance(member.type.value_type, BoundedString) or isinstance(member.type.value_type, BoundedWString)]@
-        @(get_rs_name(member.name)): unimplemented!(),
+        @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
+          .into_iter()
+          .map(|elem| (&elem).try_into().unwrap())
+          .collect(),
 @[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
         @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
           .into_iter()
@@ -125,7 +331,9 @@ impl rosidl_runtime_rs::Message for @(type_name) {
           .into_iter()
           .map(|elem| elem.as_str().into())),
 @[        elif isinstance(member.type.value_type, BoundedString) or isinstance(member.type.value_type, BoundedWString)]@
-        @(get_rs_name(member.name)): unimplemented!(),
+        @(get_rs_name(member.name)): rosidl_runtime_rs::BoundedSequence::from_iter(msg.@(get_rs_name(member.name))
+          .iter()
+          .map(|elem| elem.try_into().unwrap())),
 @[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
         @(get_rs_name(member.name)): rosidl_runtime_rs::BoundedSequence::from_iter(
             msg.@(get_rs_name(member.name))
@@ -138,7 +346,7 @@ impl rosidl_runtime_rs::Message for @(type_name) {
 @#
 @#    == BoundedString + BoundedWString ==
 @[    elif isinstance(member.type, BoundedString) or isinstance(member.type, BoundedWString)]@
-        @(get_rs_name(member.name)): unimplemented!(),
+        @(get_rs_name(member.name)): (&msg.@(get_rs_name(member.name))).try_into().unwrap(),
 @#
 @#
 @#    == Basic types ==
@@ -161,7 +369,12 @@ impl rosidl_runtime_rs::Message for @(type_name) {
           .try_into()
           .unwrap(),
 @[        elif isinstance(member.type.value_type, BoundedString) or isinstance(member.type.value_type, BoundedWString)]@
-        @(get_rs_name(member.name)): unimplemented!(),
+        @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
+          .iter()
+          .map(|elem| elem.try_into().unwrap())
+          .collect::<Vec<_>>()
+          .try_into()
+          .unwrap(),
 @[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
         @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
           .iter()
@@ -189,7 +402,10 @@ impl rosidl_runtime_rs::Message for @(type_name) {
           .map(|elem| elem.as_str().into())
           .collect(),
 @[        elif isinstance(member.type.value_type, BoundedString) or isinstance(member.type.value_type, BoundedWString)]@
-        @(get_rs_name(member.name)): unimplemented!(),
+        @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
+          .iter()
+          .map(|elem| elem.try_into().unwrap())
+          .collect(),
 @[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
         @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
           .iter()
@@ -212,7 +428,9 @@ impl rosidl_runtime_rs::Message for @(type_name) {
           .iter()
           .map(|elem| elem.as_str().into())),
 @[        elif isinstance(member.type.value_type, BoundedString) or isinstance(member.type.value_type, BoundedWString)]@
-        @(get_rs_name(member.name)): unimplemented!(),
+        @(get_rs_name(member.name)): rosidl_runtime_rs::BoundedSequence::from_iter(msg.@(get_rs_name(member.name))
+          .iter()
+          .map(|elem| elem.try_into().unwrap())),
 @[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
         @(get_rs_name(member.name)): rosidl_runtime_rs::BoundedSequence::from_iter(msg.@(get_rs_name(member.name))
           .iter()
@@ -224,7 +442,7 @@ impl rosidl_runtime_rs::Message for @(type_name) {
 @#
 @#    == BoundedString + BoundedWString ==
 @[    elif isinstance(member.type, BoundedString) or isinstance(member.type, BoundedWString)]@
-        @(get_rs_name(member.name)): unimplemented!(),
+        @(get_rs_name(member.name)): (&msg.@(get_rs_name(member.name))).try_into().unwrap(),
 @#
 @#
 @#    == BasicType ==
@@ -238,12 +456,31 @@ impl rosidl_runtime_rs::Message for @(type_name) {
 @[    end if]@
 @[end for]@
       })
-    }
+    })
   }
 
   fn from_rmw_message(msg: Self::RmwMsg) -> Self {
-    Self {
+    Self::try_from_rmw_message(msg).unwrap_or_else(|error| panic!("{}", error))
+  }
+
+  fn from_rmw_message_ref(msg: &Self::RmwMsg) -> Self {
+    Self::try_from_rmw_message_ref(msg).unwrap_or_else(|error| panic!("{}", error))
+  }
+
+@# Not every middleware checks the bounds of received strings, so the conversions of bounded
+@# strings and nested messages record every field that doesn't fit, instead of truncating it.
+  fn try_from_rmw_message(msg: Self::RmwMsg) -> Result<Self, rosidl_runtime_rs::ValidationError> {
+@[if any_checked_bound]@
+    let mut violations = rosidl_runtime_rs::validation::Violations::default();
+@[else]@
+    let violations = rosidl_runtime_rs::validation::Violations::default();
+@[end if]@
+    let converted = Self {
 @[for member in msg_spec.structure.members]@
+@{
+string_type = member.type.value_type if isinstance(member.type, (Array, AbstractSequence)) else member.type
+from_rmw_fn = 'wstring_from_rmw' if isinstance(string_type, BoundedWString) else 'string_from_rmw'
+}@
 @#
 @#
 @#    == Array ==
@@ -252,10 +489,10 @@ impl rosidl_runtime_rs::Message for @(type_name) {
       @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
         .map(|elem| elem.to_string()),
 @[        elif isinstance(member.type.value_type, BoundedString) or isinstance(member.type.value_type, BoundedWString)]@
-      @(get_rs_name(member.name)): unimplemented!(),
+      @(get_rs_name(member.name)): core::array::from_fn(|i| rosidl_runtime_rs::validation::@(from_rmw_fn)("@(member.name)", Some(i), &msg.@(get_rs_name(member.name))[i], &mut violations)),
 @[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
       @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
-        .map(@(get_idiomatic_rs_type(member.type.value_type))::from_rmw_message),
+        .map(|elem| rosidl_runtime_rs::validation::message_from_rmw::<@(get_idiomatic_rs_type(member.type.value_type))>(elem, &mut violations)),
 @[        else]@
       @(get_rs_name(member.name)): msg.@(get_rs_name(member.name)),
 @[        end if]@
@@ -268,9 +505,10 @@ impl rosidl_runtime_rs::Message for @(type_name) {
 @[        if isinstance(member.type.value_type, UnboundedString) or isinstance(member.type.value_type, UnboundedWString)]@
           .map(|elem| elem.to_string())
 @[        elif isinstance(member.type.value_type, BoundedString) or isinstance(member.type.value_type, BoundedWString)]@
-          .map(|elem| unimplemented!())
+          .enumerate()
+          .map(|(index, elem)| rosidl_runtime_rs::validation::@(from_rmw_fn)("@(member.name)", Some(index), &elem, &mut violations))
 @[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
-          .map(@(get_idiomatic_rs_type(member.type.value_type))::from_rmw_message)
+          .map(|elem| rosidl_runtime_rs::validation::message_from_rmw::<@(get_idiomatic_rs_type(member.type.value_type))>(elem, &mut violations))
 @[        end if]@
           .collect(),
 @#
@@ -282,12 +520,12 @@ impl rosidl_runtime_rs::Message for @(type_name) {
 @#
 @#    == BoundedString + BoundedWString ==
 @[    elif isinstance(member.type, BoundedString) or isinstance(member.type, BoundedWString)]@
-      @(get_rs_name(member.name)): unimplemented!(),
+      @(get_rs_name(member.name)): rosidl_runtime_rs::validation::@(from_rmw_fn)("@(member.name)", None, &msg.@(get_rs_name(member.name)), &mut violations),
 @#
 @#
 @#    == NamedType + NamespacedType ==
 @[    elif isinstance(member.type, NamedType) or isinstance(member.type, NamespacedType)]@
-      @(get_rs_name(member.name)): @(get_idiomatic_rs_type(member.type))::from_rmw_message(msg.@(get_rs_name(member.name))),
+      @(get_rs_name(member.name)): rosidl_runtime_rs::validation::message_from_rmw::<@(get_idiomatic_rs_type(member.type))>(msg.@(get_rs_name(member.name)), &mut violations),
 @#
 @#
 @#    == BoundedSequence ==
@@ -297,9 +535,10 @@ impl rosidl_runtime_rs::Message for @(type_name) {
 @[        if isinstance(member.type.value_type, UnboundedString) or isinstance(member.type.value_type, UnboundedWString)]@
           .map(|elem| elem.to_string())
 @[        elif isinstance(member.type.value_type, BoundedString) or isinstance(member.type.value_type, BoundedWString)]@
-          .map(|elem| unimplemented!())
+          .enumerate()
+          .map(|(index, elem)| rosidl_runtime_rs::validation::@(from_rmw_fn)("@(member.name)", Some(index), &elem, &mut violations))
 @[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
-          .map(@(get_idiomatic_rs_type(member.type.value_type))::from_rmw_message)
+          .map(|elem| rosidl_runtime_rs::validation::message_from_rmw::<@(get_idiomatic_rs_type(member.type.value_type))>(elem, &mut violations))
 @[        end if]@
           ),
 @#
@@ -309,11 +548,160 @@ impl rosidl_runtime_rs::Message for @(type_name) {
       @(get_rs_name(member.name)): msg.@(get_rs_name(member.name)),
 @[    end if]@
 @[end for]@
-    }
+    };
+    rosidl_runtime_rs::ValidationError::check(&violations)?;
+    Ok(converted)
+  }
+
+  fn try_from_rmw_message_ref(msg: &Self::RmwMsg) -> Result<Self, rosidl_runtime_rs::ValidationError> {
+@[if any_checked_bound]@
+    let mut violations = rosidl_runtime_rs::validation::Violations::default();
+@[else]@
+    let violations = rosidl_runtime_rs::validation::Violations::default();
+@[end if]@
+    let converted = Self {
+@[for member in msg_spec.structure.members]@
+@{
+string_type = member.type.value_type if isinstance(member.type, (Array, AbstractSequence)) else member.type
+from_rmw_fn = 'wstring_from_rmw' if isinstance(string_type, BoundedWString) else 'string_from_rmw'
+}@
+@#
+@#
+@#    == Array ==
+@[    if isinstance(member.type, Array)]@
+@[        if isinstance(member.type.value_type, UnboundedString) or isinstance(member.type.value_type, UnboundedWString)]@
+      @(get_rs_name(member.name)): core::array::from_fn(|i| msg.@(get_rs_name(member.name))[i].to_string()),
+@[        elif isinstance(member.type.value_type, BoundedString) or isinstance(member.type.value_type, BoundedWString)]@
+      @(get_rs_name(member.name)): core::array::from_fn(|i| rosidl_runtime_rs::validation::@(from_rmw_fn)("@(member.name)", Some(i), &msg.@(get_rs_name(member.name))[i], &mut violations)),
+@[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
+      @(get_rs_name(member.name)): core::array::from_fn(|i| rosidl_runtime_rs::validation::message_from_rmw_ref::<@(get_idiomatic_rs_type(member.type.value_type))>(&msg.@(get_rs_name(member.name))[i], &mut violations)),
+@[        else]@
+      @(get_rs_name(member.name)): msg.@(get_rs_name(member.name)),
+@[        end if]@
+@#
+@#
+@#    == UnboundedSequence ==
+@[    elif isinstance(member.type, UnboundedSequence)]@
+      @(get_rs_name(member.name)): msg.@(get_rs_name(member.name))
+          .iter()
+@[        if isinstance(member.type.value_type, UnboundedString) or isinstance(member.type.value_type, UnboundedWString)]@
+          .map(|elem| elem.to_string())
+@[        elif isinstance(member.type.value_type, BoundedString) or isinstance(member.type.value_type, BoundedWString)]@
+          .enumerate()
+          .map(|(index, elem)| rosidl_runtime_rs::validation::@(from_rmw_fn)("@(member.name)", Some(index), elem, &mut violations))
+@[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
+          .map(|elem| rosidl_runtime_rs::validation::message_from_rmw_ref::<@(get_idiomatic_rs_type(member.type.value_type))>(elem, &mut violations))
+@[        else]@
+          .cloned()
+@[        end if]@
+          .collect(),
+@#
+@#
+@#    == UnboundedString + UnboundedWString ==
+@[    elif isinstance(member.type, UnboundedString) or isinstance(member.type, UnboundedWString)]@
+      @(get_rs_name(member.name)): msg.@(get_rs_name(member.name)).to_string(),
+@#
+@#
+@#    == BoundedString + BoundedWString ==
+@[    elif isinstance(member.type, BoundedString) or isinstance(member.type, BoundedWString)]@
+      @(get_rs_name(member.name)): rosidl_runtime_rs::validation::@(from_rmw_fn)("@(member.name)", None, &msg.@(get_rs_name(member.name)), &mut violations),
+@#
+@#
+@#    == NamedType + NamespacedType ==
+@[    elif isinstance(member.type, NamedType) or isinstance(member.type, NamespacedType)]@
+      @(get_rs_name(member.name)): rosidl_runtime_rs::validation::message_from_rmw_ref::<@(get_idiomatic_rs_type(member.type))>(&msg.@(get_rs_name(member.name)), &mut violations),
+@#
+@#
+@#    == BoundedSequence ==
+@[    elif isinstance(member.type, BoundedSequence)]@
+      @(get_rs_name(member.name)): heapless::Vec::from_iter(msg.@(get_rs_name(member.name))
+          .iter()
+@[        if isinstance(member.type.value_type, UnboundedString) or isinstance(member.type.value_type, UnboundedWString)]@
+          .map(|elem| elem.to_string())
+@[        elif isinstance(member.type.value_type, BoundedString) or isinstance(member.type.value_type, BoundedWString)]@
+          .enumerate()
+          .map(|(index, elem)| rosidl_runtime_rs::validation::@(from_rmw_fn)("@(member.name)", Some(index), elem, &mut violations))
+@[        elif isinstance(member.type.value_type, NamedType) or isinstance(member.type.value_type, NamespacedType)]@
+          .map(|elem| rosidl_runtime_rs::validation::message_from_rmw_ref::<@(get_idiomatic_rs_type(member.type.value_type))>(elem, &mut violations))
+@[        else]@
+          .cloned()
+@[        end if]@
+          ),
+@#
+@#
+@#    == Basic types ==
+@[    else]@
+      @(get_rs_name(member.name)): msg.@(get_rs_name(member.name)),
+@[    end if]@
+@[end for]@
+    };
+    rosidl_runtime_rs::ValidationError::check(&violations)?;
+    Ok(converted)
+  }
+}
+
+#[cfg(feature = "with_middleware")]
+impl From<crate::@(subfolder)::rmw::@(type_name)> for @(type_name) {
+  fn from(msg: crate::@(subfolder)::rmw::@(type_name)) -> Self {
+    <Self as rosidl_runtime_rs::Message>::from_rmw_message(msg)
+  }
+}
+
+#[cfg(feature = "with_middleware")]
+impl From<&crate::@(subfolder)::rmw::@(type_name)> for @(type_name) {
+  fn from(msg: &crate::@(subfolder)::rmw::@(type_name)) -> Self {
+    <Self as rosidl_runtime_rs::Message>::from_rmw_message_ref(msg)
+  }
+}
+
+#[cfg(feature = "with_middleware")]
+impl From<@(type_name)> for crate::@(subfolder)::rmw::@(type_name) {
+  fn from(msg: @(type_name)) -> Self {
+    <@(type_name) as rosidl_runtime_rs::Message>::into_rmw_message(std::borrow::Cow::Owned(msg)).into_owned()
+  }
+}
+
+#[cfg(feature = "with_middleware")]
+impl From<&@(type_name)> for crate::@(subfolder)::rmw::@(type_name) {
+  fn from(msg: &@(type_name)) -> Self {
+    <@(type_name) as rosidl_runtime_rs::Message>::into_rmw_message(std::borrow::Cow::Borrowed(msg)).into_owned()
   }
 }
 
 impl rosidl_shared::MiddlewareMessage for @(type_name) where Self: Sized {
   const TYPE_NAME: &'static str = "@(package_name)/@(subfolder)/@(type_name)";
+
+  const FIELDS: &'static [rosidl_shared::metadata::FieldMetadata] = &[
+@[for member in msg_spec.structure.members]@
+    rosidl_shared::metadata::FieldMetadata {
+      name: "@(member.name)",
+      field_type: @(field_type_to_rs(member.type)),
+@[    if member.has_annotation('default')]@
+      default_value: Some(@(metadata_value_to_rs(member.get_annotation_value('default')['value']))),
+@[    else]@
+      default_value: None,
+@[    end if]@
+    },
+@[end for]@
+  ];
+
+  const CONSTANTS: &'static [rosidl_shared::metadata::ConstantMetadata] = &[
+@[for constant in msg_spec.constants]@
+    rosidl_shared::metadata::ConstantMetadata {
+      name: "@(constant.name)",
+      value_type: @(value_type_to_rs(constant.type)),
+      value: @(metadata_value_to_rs(constant.value)),
+    },
+@[end for]@
+  ];
+
+@{
+type_hash = type_hashes.get('%s/%s/%s' % (package_name, subfolder, type_name))
+}@
+@[if type_hash]@
+  const TYPE_HASH: Option<&'static str> = Some("@(type_hash)");
+@[else]@
+  const TYPE_HASH: Option<&'static str> = None;
+@[end if]@
 }
 @[end for]
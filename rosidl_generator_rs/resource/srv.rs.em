@@ -15,7 +15,12 @@ TEMPLATE(
     annotated_comments=annotated_comments,
     pre_field_serde=pre_field_serde,
     get_idiomatic_rs_type=get_idiomatic_rs_type,
-    constant_value_to_rs=constant_value_to_rs)
+    constant_value_to_rs=constant_value_to_rs,
+    default_value_to_rs=default_value_to_rs,
+    field_type_to_rs=field_type_to_rs,
+    value_type_to_rs=value_type_to_rs,
+    metadata_value_to_rs=metadata_value_to_rs,
+    type_hashes=type_hashes)
 }@
 
 @[for subfolder, srv_spec in srv_specs]
@@ -33,6 +38,22 @@ extern "C" {
 // Corresponds to @(package_name)__@(subfolder)__@(type_name)
 pub struct @(type_name);
 
+impl rosidl_shared::MiddlewareService for @(type_name) {
+  const TYPE_NAME: &'static str = "@(package_name)/@(subfolder)/@(type_name)";
+
+  type Request = crate::@(subfolder)::@(type_name)_Request;
+  type Response = crate::@(subfolder)::@(type_name)_Response;
+
+@{
+type_hash = type_hashes.get('%s/%s/%s' % (package_name, subfolder, type_name))
+}@
+@[if type_hash]@
+  const TYPE_HASH: Option<&'static str> = Some("@(type_hash)");
+@[else]@
+  const TYPE_HASH: Option<&'static str> = None;
+@[end if]@
+}
+
 #[cfg(feature = "with_middleware")]
 impl rosidl_runtime_rs::Service for @(type_name) {
   type Request = crate::@(subfolder)::@(type_name)_Request;
@@ -57,7 +78,12 @@ TEMPLATE(
     annotated_comments=annotated_comments,
     pre_field_serde=pre_field_serde,
     get_idiomatic_rs_type=get_idiomatic_rs_type,
-    constant_value_to_rs=constant_value_to_rs)
+    constant_value_to_rs=constant_value_to_rs,
+    default_value_to_rs=default_value_to_rs,
+    field_type_to_rs=field_type_to_rs,
+    value_type_to_rs=value_type_to_rs,
+    metadata_value_to_rs=metadata_value_to_rs,
+    type_hashes=type_hashes)
 }@
 
 @[for subfolder, srv_spec in srv_specs]
@@ -12,6 +12,7 @@
 # See the License for the specific language governing permissions and
 # limitations under the License.
 
+import json
 import os
 import pathlib
 import subprocess
@@ -85,6 +86,19 @@ def generate_rs(generator_arguments_file, typesupport_impls):
 
     template_dir = args['template_dir']
 
+    # In heapless mode, the generated crate must not need an allocator
+    heapless = args.get('heapless', False)
+
+    # Type description hashes are only available since Iron
+    type_hashes = {}
+    for description_tuple in args.get('type_description_tuples', []):
+        description_parts = description_tuple.split(':', 1)
+        assert len(description_parts) == 2
+        with open(description_parts[1], 'r') as f:
+            type_description_info = json.load(f)
+        for type_hash in type_description_info['type_hashes']:
+            type_hashes[type_hash['type_name']] = type_hash['hash_string']
+
     mapping_msgs = {
         os.path.join(template_dir, 'msg.rs.em'): ['rust/src/%s.rs'],
     }
@@ -108,6 +122,11 @@ def generate_rs(generator_arguments_file, typesupport_impls):
         'get_rs_name': get_rs_name,
         'get_idiomatic_rs_type': make_get_idiomatic_rs_type(args['package_name']),
         'constant_value_to_rs': constant_value_to_rs,
+        'default_value_to_rs': default_value_to_rs,
+        'field_type_to_rs': field_type_to_rs,
+        'value_type_to_rs': value_type_to_rs,
+        'metadata_value_to_rs': metadata_value_to_rs,
+        'type_hashes': type_hashes,
         'value_to_rs': value_to_rs,
         'convert_camel_case_to_lower_case_underscore':
         rosidl_pycommon.convert_camel_case_to_lower_case_underscore,
@@ -118,6 +137,7 @@ def generate_rs(generator_arguments_file, typesupport_impls):
         'package_name': args['package_name'],
         'typesupport_impls': typesupport_impls,
         'interface_path': idl_rel_path,
+        'heapless': heapless,
     }
 
     latest_target_timestamp = rosidl_pycommon.get_newest_modification_time(
@@ -129,6 +149,13 @@ def generate_rs(generator_arguments_file, typesupport_impls):
     for service in idl_content.get_elements_of_type(Service):
         data['srv_specs'].append(('srv', service))
 
+    if heapless:
+        for _, message in data['msg_specs']:
+            check_heapless(message)
+        for _, service in data['srv_specs']:
+            check_heapless(service.request_message)
+            check_heapless(service.response_message)
+
     if data['msg_specs']:
         for template_file, generated_filenames in mapping_msgs.items():
             for generated_filename in generated_filenames:
@@ -161,6 +188,7 @@ def generate_rs(generator_arguments_file, typesupport_impls):
         'dependency_packages': dependency_packages,
         'package_name': args['package_name'],
         'package_version': args['package_version'],
+        'heapless': heapless,
     }
     rosidl_pycommon.expand_template(
         os.path.join(template_dir, 'Cargo.toml.em'),
@@ -191,6 +219,22 @@ def get_rs_name(name):
     # If the field name is a reserved keyword in Rust append an underscore
     return name if not name in keywords else name + '_'
 
+def check_heapless(message):
+    """Raise an error if the message has a field that needs an allocator."""
+    for member in message.structure.members:
+        type_ = member.type
+        if isinstance(type_, UnboundedSequence):
+            unbounded = True
+        else:
+            if isinstance(type_, AbstractNestedType):
+                type_ = type_.value_type
+            unbounded = isinstance(type_, UnboundedString) or isinstance(type_, UnboundedWString)
+        if unbounded:
+            raise ValueError(
+                "Field '%s' of message '%s' is unbounded, which is not supported in heapless mode" %
+                (member.name, '/'.join(message.structure.namespaced_type.namespaced_name())))
+
+
 def escape_string(s):
     s = s.replace('\\', '\\\\')
     s = s.replace("'", "\\'")
@@ -257,6 +301,202 @@ def constant_value_to_rs(type_, value):
 
     assert False, "unknown constant type '%s'" % type_
 
+def default_value_to_rs(member):
+    """Return a Rust expression for the default value of a field of an idiomatic message.
+
+    The values are the same as the ones set by the C init function: the value of the
+    @default annotation if there is one, and zero, false or empty otherwise.
+    """
+    type_ = member.type
+    if not member.has_annotation('default'):
+        if isinstance(type_, Array):
+            # Default is only implemented for arrays of up to 32 elements
+            return 'core::array::from_fn(|_| Default::default())'
+        return 'Default::default()'
+
+    value = member.get_annotation_value('default')['value']
+    if isinstance(type_, AbstractNestedType):
+        elements = ', '.join(
+            nestable_value_to_rs(type_.value_type, element)
+            for element in parse_sequence_value(value))
+        if isinstance(type_, Array):
+            return '[%s]' % elements
+        elif isinstance(type_, UnboundedSequence):
+            return 'alloc::vec![%s]' % elements
+        return 'heapless::Vec::from_iter([%s])' % elements
+    return nestable_value_to_rs(type_, value)
+
+
+def nestable_value_to_rs(type_, value):
+    if isinstance(type_, BasicType):
+        if type_.typename == 'boolean':
+            if isinstance(value, str):
+                value = value.strip().lower() in ['true', '1']
+            return 'true' if value else 'false'
+        elif type_.typename in ['float', 'double']:
+            return float_value_to_rs('f32' if type_.typename == 'float' else 'f64', value)
+        return str(value)
+
+    if isinstance(type_, UnboundedString) or isinstance(type_, UnboundedWString):
+        return 'alloc::string::String::from(%s)' % string_literal_to_rs(value)
+
+    if isinstance(type_, BoundedString) or isinstance(type_, BoundedWString):
+        check_string_bound(type_, value)
+        # Parsing can't fail after the check above, but heapless::String::from() would panic if
+        # the capacity was exceeded
+        return '%s.parse().unwrap_or_default()' % string_literal_to_rs(value)
+
+    assert False, "unsupported type for default value '%s'" % type_
+
+
+def check_string_bound(type_, value):
+    """Raise an error if the default value of a bounded string exceeds its bound.
+
+    The bound of a string is in characters, and the bound of a wide string is in UTF-16 code
+    units, like in rosidl_runtime_rs.
+    """
+    if isinstance(type_, BoundedWString):
+        length = len(value.encode('utf-16-le')) // 2
+        unit = 'UTF-16 code units'
+    else:
+        length = len(value)
+        unit = 'characters'
+    if length > type_.maximum_size:
+        raise ValueError(
+            "Default value '%s' has %d %s, more than the bound of %d" %
+            (value, length, unit, type_.maximum_size))
+
+
+def float_value_to_rs(rs_type, value):
+    literal = str(value).strip()
+    if literal.lower() in ['nan', '+nan', '-nan']:
+        return '%s::NAN' % rs_type
+    if literal.lower() in ['inf', '+inf', 'infinity', '+infinity']:
+        return '%s::INFINITY' % rs_type
+    if literal.lower() in ['-inf', '-infinity']:
+        return '%s::NEG_INFINITY' % rs_type
+    sign = ''
+    if literal[0] in '+-':
+        sign, literal = literal[0].replace('+', ''), literal[1:]
+    # Rust float literals must start with a digit and contain a '.' or an exponent
+    if literal.startswith('.'):
+        literal = '0' + literal
+    if not any(c in literal for c in '.eE'):
+        literal += '.0'
+    return sign + literal
+
+
+def string_literal_to_rs(value):
+    return '"%s"' % ''.join(
+        c if c.isprintable() and c not in '\\"' else '\\u{%x}' % ord(c)
+        for c in value)
+
+
+def parse_sequence_value(value):
+    """Return the elements of the default value of an array or sequence.
+
+    Depending on the origin of the interface, the value is either already a list, or a string
+    such as "(1, 2, 3)" or "['a', 'b']".
+    """
+    if not isinstance(value, str):
+        return list(value)
+
+    value = value.strip()
+    if value[:1] in ['(', '['] and value[-1:] in [')', ']']:
+        value = value[1:-1]
+    elements = []
+    # The characters of the current element, and whether each of them was quoted
+    current = []
+    has_quotes = False
+    quote = None
+    escaped = False
+    for c in value:
+        if escaped:
+            current.append((c, True))
+            escaped = False
+        elif quote is not None and c == '\\':
+            escaped = True
+        elif quote is not None and c == quote:
+            quote = None
+        elif quote is None and c in ['"', "'"]:
+            quote = c
+            has_quotes = True
+        elif quote is None and c == ',':
+            elements.append(strip_unquoted(current))
+            current = []
+            has_quotes = False
+        else:
+            current.append((c, quote is not None))
+    if has_quotes or strip_unquoted(current) or elements:
+        elements.append(strip_unquoted(current))
+    return elements
+
+
+def strip_unquoted(chars):
+    """Join the characters of an element, without the whitespace around it outside of quotes."""
+    start, end = 0, len(chars)
+    while start < end and not chars[start][1] and chars[start][0].isspace():
+        start += 1
+    while end > start and not chars[end - 1][1] and chars[end - 1][0].isspace():
+        end -= 1
+    return ''.join(c for c, _ in chars[start:end])
+
+def field_type_to_rs(type_):
+    """Return a Rust expression for the rosidl_shared::metadata::FieldType of a field."""
+    if isinstance(type_, Array):
+        return 'rosidl_shared::metadata::FieldType::Array(%s, %d)' % (
+            value_type_to_rs(type_.value_type), type_.size)
+    elif isinstance(type_, BoundedSequence):
+        return 'rosidl_shared::metadata::FieldType::BoundedSequence(%s, %d)' % (
+            value_type_to_rs(type_.value_type), type_.maximum_size)
+    elif isinstance(type_, UnboundedSequence):
+        return 'rosidl_shared::metadata::FieldType::UnboundedSequence(%s)' % (
+            value_type_to_rs(type_.value_type))
+    return 'rosidl_shared::metadata::FieldType::Single(%s)' % value_type_to_rs(type_)
+
+
+def value_type_to_rs(type_):
+    """Return a Rust expression for the rosidl_shared::metadata::ValueType of a nestable type."""
+    if isinstance(type_, BasicType):
+        variants = {
+            'boolean': 'Boolean',
+            'byte': 'Byte',
+            'octet': 'Byte',
+            'char': 'Char',
+            'float': 'Float32',
+            'double': 'Float64',
+            'int8': 'Int8',
+            'uint8': 'Uint8',
+            'int16': 'Int16',
+            'uint16': 'Uint16',
+            'int32': 'Int32',
+            'uint32': 'Uint32',
+            'int64': 'Int64',
+            'uint64': 'Uint64',
+        }
+        assert type_.typename in variants, "unknown type '%s'" % type_.typename
+        return 'rosidl_shared::metadata::ValueType::%s' % variants[type_.typename]
+    elif isinstance(type_, BoundedString):
+        return 'rosidl_shared::metadata::ValueType::String(Some(%d))' % type_.maximum_size
+    elif isinstance(type_, UnboundedString):
+        return 'rosidl_shared::metadata::ValueType::String(None)'
+    elif isinstance(type_, BoundedWString):
+        return 'rosidl_shared::metadata::ValueType::WString(Some(%d))' % type_.maximum_size
+    elif isinstance(type_, UnboundedWString):
+        return 'rosidl_shared::metadata::ValueType::WString(None)'
+    elif isinstance(type_, NamespacedType):
+        return 'rosidl_shared::metadata::ValueType::Message("%s")' % '/'.join(
+            type_.namespaced_name())
+    assert False, "unknown type '%s'" % type_
+
+
+def metadata_value_to_rs(value):
+    """Return a Rust string literal for a constant or default value in the type metadata."""
+    if isinstance(value, bool):
+        value = 'true' if value else 'false'
+    return string_literal_to_rs(str(value))
+
+
 # Type hierarchy:
 # 
 # AbstractType
@@ -279,11 +519,21 @@ def constant_value_to_rs(type_, value):
 
 
 def pre_field_serde(type_):
-    if isinstance(type_, Array) and type_.size > 32:
+    # Like the ros2 CLI, show byte and char values as strings instead of integers
+    if is_byte_or_char(type_):
+        return '#[cfg_attr(feature = "serde", serde(with = "rosidl_shared::byte_serde::byte"))]\n    '
+    elif isinstance(type_, Array) and is_byte_or_char(type_.value_type):
+        return '#[cfg_attr(feature = "serde", serde(with = "rosidl_shared::byte_serde::byte_array"))]\n    '
+    elif isinstance(type_, AbstractSequence) and is_byte_or_char(type_.value_type):
+        return '#[cfg_attr(feature = "serde", serde(with = "rosidl_shared::byte_serde::byte_sequence"))]\n    '
+    elif isinstance(type_, Array) and type_.size > 32:
         return '#[cfg_attr(feature = "serde", serde(with = "serde_big_array::BigArray"))]\n    '
     else:
         return ''
 
+def is_byte_or_char(type_):
+    return isinstance(type_, BasicType) and type_.typename in ['byte', 'octet', 'char']
+
 def annotated_comments(field: Annotatable):
     comments = field.get_comment_lines()
     if comments is None:
@@ -296,11 +546,15 @@ def make_get_idiomatic_rs_type(package_name):
     get_rmw_rs_type = make_get_rmw_rs_type(package_name)
     def get_idiomatic_rs_type(type_):
         if isinstance(type_, UnboundedString) or isinstance(type_, UnboundedWString):
-            return 'std::string::String'
-        elif isinstance(type_, BoundedString) or isinstance(type_, BoundedWString):
-            return 'heapless::String<{}>'.format(type_.maximum_size)
+            return 'alloc::string::String'
+        elif isinstance(type_, BoundedString):
+            # The bound is in characters, each of which needs up to four bytes in UTF-8
+            return 'heapless::String<{}>'.format(4 * type_.maximum_size)
+        elif isinstance(type_, BoundedWString):
+            # The bound is in UTF-16 code units, each of which needs up to three bytes in UTF-8
+            return 'heapless::String<{}>'.format(3 * type_.maximum_size)
         elif isinstance(type_, UnboundedSequence):
-            return 'Vec<{}>'.format(get_idiomatic_rs_type(type_.value_type))
+            return 'alloc::vec::Vec<{}>'.format(get_idiomatic_rs_type(type_.value_type))
         elif isinstance(type_, BoundedSequence):
             return 'heapless::Vec<{}, {}>'.format(get_idiomatic_rs_type(type_.value_type), type_.maximum_size)
         elif isinstance(type_, NamespacedType):
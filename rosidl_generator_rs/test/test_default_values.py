@@ -0,0 +1,54 @@
+# Copyright 2016-2017 Esteve Fernandez <esteve@apache.org>
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+import pytest
+
+from rosidl_generator_rs import nestable_value_to_rs
+from rosidl_generator_rs import parse_sequence_value
+from rosidl_parser.definition import BoundedString
+from rosidl_parser.definition import BoundedWString
+
+
+def test_parse_sequence_value_keeps_whitespace_inside_quotes():
+    assert parse_sequence_value('["a b", " c"]') == ['a b', ' c']
+    assert parse_sequence_value("[ ' leading', 'trailing ' ]") == [' leading', 'trailing ']
+    assert parse_sequence_value('["", "x"]') == ['', 'x']
+    assert parse_sequence_value('[""]') == ['']
+    assert parse_sequence_value('["a, b", c]') == ['a, b', 'c']
+
+
+def test_parse_sequence_value_strips_whitespace_outside_quotes():
+    assert parse_sequence_value('[1, 2 ,3]') == ['1', '2', '3']
+    assert parse_sequence_value('( 1.5 , -2 )') == ['1.5', '-2']
+    assert parse_sequence_value('[]') == []
+
+
+def test_bounded_string_default_is_fallible():
+    value = nestable_value_to_rs(BoundedString(maximum_size=5), 'hello')
+    assert value == '"hello".parse().unwrap_or_default()'
+    assert 'heapless::String::from' not in value
+
+
+def test_bounded_string_default_bound_is_in_characters():
+    # 'ü' is two bytes in UTF-8, but one character
+    nestable_value_to_rs(BoundedString(maximum_size=3), 'üüü')
+    with pytest.raises(ValueError):
+        nestable_value_to_rs(BoundedString(maximum_size=2), 'üüü')
+
+
+def test_bounded_wstring_default_bound_is_in_utf16_code_units():
+    # '€' is one UTF-16 code unit, '𝄞' is two
+    nestable_value_to_rs(BoundedWString(maximum_size=3), '€€€')
+    with pytest.raises(ValueError):
+        nestable_value_to_rs(BoundedWString(maximum_size=1), '𝄞')
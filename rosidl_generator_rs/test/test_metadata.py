@@ -0,0 +1,58 @@
+# Copyright 2016-2017 Esteve Fernandez <esteve@apache.org>
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+from rosidl_generator_rs import field_type_to_rs
+from rosidl_generator_rs import metadata_value_to_rs
+from rosidl_generator_rs import value_type_to_rs
+from rosidl_parser.definition import Array
+from rosidl_parser.definition import BasicType
+from rosidl_parser.definition import BoundedSequence
+from rosidl_parser.definition import BoundedString
+from rosidl_parser.definition import BoundedWString
+from rosidl_parser.definition import NamespacedType
+from rosidl_parser.definition import UnboundedSequence
+from rosidl_parser.definition import UnboundedString
+from rosidl_parser.definition import UnboundedWString
+
+VALUE_TYPE = 'rosidl_shared::metadata::ValueType::'
+FIELD_TYPE = 'rosidl_shared::metadata::FieldType::'
+
+
+def test_value_types():
+    assert value_type_to_rs(BasicType('uint8')) == VALUE_TYPE + 'Uint8'
+    assert value_type_to_rs(BasicType('octet')) == VALUE_TYPE + 'Byte'
+    assert value_type_to_rs(BasicType('double')) == VALUE_TYPE + 'Float64'
+    assert value_type_to_rs(UnboundedString()) == VALUE_TYPE + 'String(None)'
+    assert value_type_to_rs(BoundedString(5)) == VALUE_TYPE + 'String(Some(5))'
+    assert value_type_to_rs(UnboundedWString()) == VALUE_TYPE + 'WString(None)'
+    assert value_type_to_rs(BoundedWString(3)) == VALUE_TYPE + 'WString(Some(3))'
+    assert value_type_to_rs(NamespacedType(['std_msgs', 'msg'], 'Header')) == \
+        VALUE_TYPE + 'Message("std_msgs/msg/Header")'
+
+
+def test_field_types():
+    uint8 = BasicType('uint8')
+    assert field_type_to_rs(uint8) == FIELD_TYPE + 'Single(%sUint8)' % VALUE_TYPE
+    assert field_type_to_rs(Array(uint8, 4)) == FIELD_TYPE + 'Array(%sUint8, 4)' % VALUE_TYPE
+    assert field_type_to_rs(BoundedSequence(uint8, 2)) == \
+        FIELD_TYPE + 'BoundedSequence(%sUint8, 2)' % VALUE_TYPE
+    assert field_type_to_rs(UnboundedSequence(BoundedString(8))) == \
+        FIELD_TYPE + 'UnboundedSequence(%sString(Some(8)))' % VALUE_TYPE
+
+
+def test_metadata_values():
+    assert metadata_value_to_rs(True) == '"true"'
+    assert metadata_value_to_rs(-1) == '"-1"'
+    assert metadata_value_to_rs(1.5) == '"1.5"'
+    assert metadata_value_to_rs('say "hi"\n') == '"say \\u{22}hi\\u{22}\\u{a}"'
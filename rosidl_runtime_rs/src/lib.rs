@@ -10,3 +10,11 @@ pub use string::{BoundedString, BoundedWString, String, StringExceedsBoundsError
 
 mod traits;
 pub use traits::{Message, RmwMessage, SequenceAlloc, Service};
+
+pub mod validation;
+pub use validation::ValidationError;
+
+#[cfg(feature = "yaml")]
+mod yaml;
+#[cfg(feature = "yaml")]
+pub use yaml::{from_yaml, to_yaml, YamlError};
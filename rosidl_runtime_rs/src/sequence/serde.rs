@@ -1,8 +1,50 @@
-use serde::{de::Error, ser::SerializeSeq, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::marker::PhantomData;
 
-use super::{BoundedSequence, Sequence};
+use serde::{
+    de::{Error, IgnoredAny, SeqAccess, Visitor},
+    ser::SerializeSeq,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use super::{BoundedSequence, Sequence, SequenceExceedsBoundsError};
 use crate::traits::SequenceAlloc;
 
+// Stops collecting elements once the bound is exceeded, so that oversized input isn't buffered.
+struct BoundedSequenceVisitor<T, const N: usize>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de> + SequenceAlloc, const N: usize> Visitor<'de>
+    for BoundedSequenceVisitor<T, N>
+{
+    type Value = BoundedSequence<T, N>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a sequence with at most {} elements", N)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut elements = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(N));
+        while let Some(element) = seq.next_element()? {
+            if elements.len() == N {
+                // Count the remaining elements for the error message
+                let mut len = N + 1;
+                while seq.next_element::<IgnoredAny>()?.is_some() {
+                    len += 1;
+                }
+                return Err(A::Error::custom(SequenceExceedsBoundsError {
+                    len,
+                    upper_bound: N,
+                }));
+            }
+            elements.push(element);
+        }
+        Ok(BoundedSequence::from_iter(elements))
+    }
+}
+
 impl<'de, T: Deserialize<'de> + SequenceAlloc> Deserialize<'de> for Sequence<T> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -33,8 +75,7 @@ impl<'de, T: Deserialize<'de> + SequenceAlloc, const N: usize> Deserialize<'de>
     where
         D: Deserializer<'de>,
     {
-        let v: Vec<_> = Deserialize::deserialize(deserializer)?;
-        Self::try_from(v).map_err(D::Error::custom)
+        deserializer.deserialize_seq(BoundedSequenceVisitor(PhantomData))
     }
 }
 
@@ -51,7 +92,7 @@ impl<T: Serialize + SequenceAlloc, const N: usize> Serialize for BoundedSequence
 mod tests {
     use quickcheck::quickcheck;
 
-    use crate::{BoundedSequence, Sequence};
+    use crate::{BoundedSequence, BoundedString, BoundedWString, Sequence, String, WString};
 
     quickcheck! {
         fn test_json_roundtrip_sequence(xs: Sequence<i32>) -> bool {
@@ -68,4 +109,40 @@ mod tests {
             xs == recovered
         }
     }
+
+    quickcheck! {
+        fn test_json_roundtrip_string_sequences(
+            strings: Sequence<String>,
+            wstrings: Sequence<WString>,
+            bounded_strings: BoundedSequence<BoundedString<256>, 256>,
+            bounded_wstrings: BoundedSequence<BoundedWString<256>, 256>
+        ) -> bool {
+            let value = serde_json::to_value(strings.clone()).unwrap();
+            let recovered_strings: Sequence<String> = serde_json::from_value(value).unwrap();
+            let value = serde_json::to_value(wstrings.clone()).unwrap();
+            let recovered_wstrings: Sequence<WString> = serde_json::from_value(value).unwrap();
+            let value = serde_json::to_value(bounded_strings.clone()).unwrap();
+            let recovered_bounded_strings: BoundedSequence<BoundedString<256>, 256> =
+                serde_json::from_value(value).unwrap();
+            let value = serde_json::to_value(bounded_wstrings.clone()).unwrap();
+            let recovered_bounded_wstrings: BoundedSequence<BoundedWString<256>, 256> =
+                serde_json::from_value(value).unwrap();
+            strings == recovered_strings
+                && wstrings == recovered_wstrings
+                && bounded_strings == recovered_bounded_strings
+                && bounded_wstrings == recovered_bounded_wstrings
+        }
+    }
+
+    quickcheck! {
+        fn test_bounded_sequence_bound_is_enforced(xs: Vec<i32>) -> bool {
+            let value = serde_json::to_value(&xs).unwrap();
+            match serde_json::from_value::<BoundedSequence<i32, 8>>(value) {
+                Ok(seq) => xs.len() <= 8 && seq.as_slice() == xs.as_slice(),
+                Err(err) => {
+                    xs.len() > 8 && err.to_string().contains(&format!("len {}", xs.len()))
+                }
+            }
+        }
+    }
 }
@@ -80,7 +80,7 @@ pub struct BoundedString<const N: usize> {
 /// A zero-terminated string of 16-bit characters with a length limit.
 ///
 /// The same as [`WString`], but it cannot be constructed from a string that is too large.
-/// The length is measured as the number of Unicode scalar values, not bytes.
+/// The length is measured as the number of UTF-16 code units, like in `rosidl_runtime_c`.
 ///
 /// # Example
 ///
@@ -99,7 +99,8 @@ pub struct BoundedWString<const N: usize> {
     inner: WString,
 }
 
-/// Error type for [`BoundedString::try_from()`] and [`BoundedWString::try_from()`].
+/// Error type for [`BoundedString::try_from()`] and [`BoundedWString::try_from()`], and for
+/// converting them into a [`heapless::String`] that is too small.
 #[derive(Debug)]
 pub struct StringExceedsBoundsError {
     /// The actual length the string would have after the operation.
@@ -420,6 +421,35 @@ impl<const N: usize> TryFrom<&str> for BoundedString<N> {
     }
 }
 
+/// Converts a string with a capacity of `M` bytes into a [`BoundedString`].
+///
+/// Idiomatic messages use a capacity of `4 * N` bytes for `string<=N` fields, so that every
+/// `BoundedString<N>` fits. Such a string can however have more than `N` characters, and then an
+/// error with the number of characters is returned.
+impl<const N: usize, const M: usize> TryFrom<&heapless::String<M>> for BoundedString<N> {
+    type Error = StringExceedsBoundsError;
+    fn try_from(s: &heapless::String<M>) -> Result<Self, Self::Error> {
+        Self::try_from(s.as_str())
+    }
+}
+
+/// Converts a [`BoundedString`] into a string with a capacity of `M` bytes.
+///
+/// A `BoundedString<N>` needs up to `4 * N` bytes in UTF-8. Invalid UTF-8 is replaced with
+/// [`char::REPLACEMENT_CHARACTER`]. An error with the number of characters is returned if the
+/// string has more than `N` characters, which the middleware may not have checked, and an error
+/// with the length in bytes is returned if the string doesn't fit.
+impl<const N: usize, const M: usize> TryFrom<&BoundedString<N>> for heapless::String<M> {
+    type Error = StringExceedsBoundsError;
+    fn try_from(s: &BoundedString<N>) -> Result<Self, Self::Error> {
+        // SAFETY: Casting the signed char type to the unsigned one of the same size is fine.
+        let u8_slice = unsafe { std::slice::from_raw_parts(s.as_ptr() as *const u8, s.len()) };
+        let decoded = std::string::String::from_utf8_lossy(u8_slice);
+        check_bound(decoded.chars().count(), N)?;
+        to_heapless(&decoded)
+    }
+}
+
 // ========================= impl for BoundedWString =========================
 
 impl<const N: usize> Debug for BoundedWString<N> {
@@ -472,20 +502,60 @@ impl<const N: usize> SequenceAlloc for BoundedWString<N> {
 impl<const N: usize> TryFrom<&str> for BoundedWString<N> {
     type Error = StringExceedsBoundsError;
     fn try_from(s: &str) -> Result<Self, Self::Error> {
-        let length = s.chars().count();
-        if length <= N {
-            Ok(Self {
-                inner: WString::from(s),
-            })
-        } else {
-            Err(StringExceedsBoundsError {
-                len: length,
-                upper_bound: N,
-            })
-        }
+        check_bound(s.encode_utf16().count(), N)?;
+        Ok(Self {
+            inner: WString::from(s),
+        })
+    }
+}
+
+/// Converts a string with a capacity of `M` bytes into a [`BoundedWString`].
+///
+/// Idiomatic messages use a capacity of `3 * N` bytes for `wstring<=N` fields, so that every
+/// `BoundedWString<N>` fits. Such a string can however have more than `N` UTF-16 code units, and
+/// then an error with the number of code units is returned.
+impl<const N: usize, const M: usize> TryFrom<&heapless::String<M>> for BoundedWString<N> {
+    type Error = StringExceedsBoundsError;
+    fn try_from(s: &heapless::String<M>) -> Result<Self, Self::Error> {
+        Self::try_from(s.as_str())
+    }
+}
+
+/// Converts a [`BoundedWString`] into a string with a capacity of `M` bytes.
+///
+/// A `BoundedWString<N>` can need up to `3 * N` bytes in UTF-8, since a UTF-16 code unit is
+/// encoded with up to three bytes. Invalid UTF-16 is replaced with
+/// [`char::REPLACEMENT_CHARACTER`], which has the same maximum length. An error with the number
+/// of code units is returned if the string has more than `N` code units, which the middleware may
+/// not have checked, and an error with the length in bytes is returned if the string doesn't fit.
+impl<const N: usize, const M: usize> TryFrom<&BoundedWString<N>> for heapless::String<M> {
+    type Error = StringExceedsBoundsError;
+    fn try_from(s: &BoundedWString<N>) -> Result<Self, Self::Error> {
+        check_bound(s.len(), N)?;
+        let decoded: std::string::String = char::decode_utf16(s.iter().copied())
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect();
+        to_heapless(&decoded)
     }
 }
 
+fn check_bound(len: usize, upper_bound: usize) -> Result<(), StringExceedsBoundsError> {
+    if len <= upper_bound {
+        Ok(())
+    } else {
+        Err(StringExceedsBoundsError { len, upper_bound })
+    }
+}
+
+fn to_heapless<const N: usize>(s: &str) -> Result<heapless::String<N>, StringExceedsBoundsError> {
+    let mut string = heapless::String::new();
+    string.push_str(s).map_err(|_| StringExceedsBoundsError {
+        len: s.len(),
+        upper_bound: N,
+    })?;
+    Ok(string)
+}
+
 // ========================= impl for StringExceedsBoundsError =========================
 
 impl Display for StringExceedsBoundsError {
@@ -502,7 +572,7 @@ impl std::error::Error for StringExceedsBoundsError {}
 
 #[cfg(test)]
 mod tests {
-    use quickcheck::{Arbitrary, Gen};
+    use quickcheck::{quickcheck, Arbitrary, Gen};
 
     use super::*;
 
@@ -529,11 +599,124 @@ mod tests {
     impl Arbitrary for BoundedWString<256> {
         fn arbitrary(g: &mut Gen) -> Self {
             let len = u8::arbitrary(g);
-            let s: std::string::String = (0..len).map(|_| char::arbitrary(g)).collect();
+            let mut s = std::string::String::new();
+            // Characters outside the basic multilingual plane need two UTF-16 code units
+            for c in (0..len).map(|_| char::arbitrary(g)) {
+                if s.encode_utf16().count() + c.len_utf16() > 256 {
+                    break;
+                }
+                s.push(c);
+            }
             s.as_str().try_into().unwrap()
         }
     }
 
+    // Truncates the string to at most N bytes, at a char boundary.
+    fn truncate<const N: usize>(s: &str) -> heapless::String<N> {
+        let mut end = s.len().min(N);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        heapless::String::from(&s[..end])
+    }
+
+    quickcheck! {
+        fn heapless_string_roundtrip(s: std::string::String) -> bool {
+            let heapless_string = truncate::<256>(&s);
+            let bounded = BoundedString::<256>::try_from(&heapless_string).unwrap();
+            bounded.to_string() == heapless_string.as_str()
+                && heapless::String::<256>::try_from(&bounded).unwrap() == heapless_string
+        }
+    }
+
+    quickcheck! {
+        fn heapless_wstring_roundtrip(s: std::string::String) -> bool {
+            let heapless_string = truncate::<256>(&s);
+            let bounded = BoundedWString::<256>::try_from(&heapless_string).unwrap();
+            bounded.to_string() == heapless_string.as_str()
+                && heapless::String::<256>::try_from(&bounded).unwrap() == heapless_string
+        }
+    }
+
+    quickcheck! {
+        fn bounded_wstring_always_fits_three_times_the_bound(s: BoundedWString<256>) -> bool {
+            heapless::String::<768>::try_from(&s).unwrap().as_str() == s.to_string()
+        }
+    }
+
+    quickcheck! {
+        fn bounded_string_always_fits_four_times_the_bound(s: BoundedString<256>) -> bool {
+            heapless::String::<1024>::try_from(&s).unwrap().as_str() == s.to_string()
+        }
+    }
+
+    quickcheck! {
+        fn bounded_string_to_heapless_is_lossless_or_fails(s: BoundedString<256>) -> bool {
+            let full = s.to_string();
+            match heapless::String::<256>::try_from(&s) {
+                Ok(heapless_string) => full == heapless_string.as_str(),
+                Err(err) => err.len == full.len() && full.len() > 256,
+            }
+        }
+    }
+
+    #[test]
+    fn bounded_wstring_from_heapless_uses_utf16() {
+        let heapless_string = heapless::String::<8>::from("a𝕊ü");
+        let bounded = BoundedWString::<8>::try_from(&heapless_string).unwrap();
+        // 𝕊 is outside the basic multilingual plane and encoded as a surrogate pair
+        assert_eq!(&*bounded, &[0x61, 0xD835, 0xDD4A, 0xFC]);
+        assert_eq!(
+            heapless::String::<8>::try_from(&bounded).unwrap(),
+            heapless_string
+        );
+    }
+
+    #[test]
+    fn multibyte_wstring_at_bound_to_heapless() {
+        // Three code units that need three bytes each in UTF-8
+        let bounded = BoundedWString::<3>::try_from("€€€").unwrap();
+        assert_eq!(bounded.len(), 3);
+        let err = heapless::String::<3>::try_from(&bounded).unwrap_err();
+        assert_eq!((err.len, err.upper_bound), (9, 3));
+        assert_eq!(
+            heapless::String::<9>::try_from(&bounded).unwrap().as_str(),
+            "€€€"
+        );
+        let bounded = BoundedString::<3>::try_from("üüü").unwrap();
+        let err = heapless::String::<3>::try_from(&bounded).unwrap_err();
+        assert_eq!((err.len, err.upper_bound), (6, 3));
+    }
+
+    #[test]
+    fn heapless_to_bounded_checks_the_bound() {
+        // Three characters in six bytes
+        let heapless_string = heapless::String::<12>::from("üüü");
+        assert!(BoundedString::<3>::try_from(&heapless_string).is_ok());
+        let err = BoundedString::<2>::try_from(&heapless_string).unwrap_err();
+        assert_eq!((err.len, err.upper_bound), (3, 2));
+        // Two UTF-16 code units in four bytes
+        let heapless_string = heapless::String::<12>::from("𝕊");
+        assert!(BoundedWString::<2>::try_from(&heapless_string).is_ok());
+        let err = BoundedWString::<1>::try_from(&heapless_string).unwrap_err();
+        assert_eq!((err.len, err.upper_bound), (2, 1));
+    }
+
+    #[test]
+    fn bounded_over_bound_to_heapless_fails() {
+        // The middleware may deliver strings that exceed their bound
+        let bounded = BoundedString::<2> {
+            inner: String::from("abc"),
+        };
+        let err = heapless::String::<8>::try_from(&bounded).unwrap_err();
+        assert_eq!((err.len, err.upper_bound), (3, 2));
+        let bounded = BoundedWString::<2> {
+            inner: WString::from("abc"),
+        };
+        let err = heapless::String::<6>::try_from(&bounded).unwrap_err();
+        assert_eq!((err.len, err.upper_bound), (3, 2));
+    }
+
     #[test]
     fn string_from_char_iterator() {
         // Base char case
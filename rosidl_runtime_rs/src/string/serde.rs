@@ -1,4 +1,5 @@
 use std::fmt;
+use std::marker::PhantomData;
 
 use serde::{
     de::{Error, SeqAccess, Visitor},
@@ -7,11 +8,13 @@ use serde::{
 
 use super::{
     rosidl_runtime_c__String__assignn, rosidl_runtime_c__U16String__assignn, BoundedString,
-    BoundedWString, String, WString,
+    BoundedWString, String, StringExceedsBoundsError, WString,
 };
 
 struct StringVisitor;
 struct WStringVisitor;
+// Checks the bound of a BoundedString or BoundedWString without an intermediate String.
+struct BoundedStringVisitor<T>(PhantomData<T>);
 
 impl<'de> Visitor<'de> for StringVisitor {
     type Value = String;
@@ -87,6 +90,24 @@ impl<'de> Visitor<'de> for WStringVisitor {
     // We don't implement visit_bytes_buf, since the data in a string must always be managed by C.
 }
 
+impl<'de, T> Visitor<'de> for BoundedStringVisitor<T>
+where
+    T: for<'a> TryFrom<&'a str, Error = StringExceedsBoundsError>,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        T::try_from(v).map_err(E::custom)
+    }
+}
+
 impl<'de> Deserialize<'de> for String {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -136,8 +157,7 @@ impl<'de, const N: usize> Deserialize<'de> for BoundedString<N> {
     where
         D: Deserializer<'de>,
     {
-        std::string::String::deserialize(deserializer)
-            .and_then(|s| Self::try_from(s.as_str()).map_err(D::Error::custom))
+        deserializer.deserialize_str(BoundedStringVisitor(PhantomData))
     }
 }
 
@@ -155,8 +175,7 @@ impl<'de, const N: usize> Deserialize<'de> for BoundedWString<N> {
     where
         D: Deserializer<'de>,
     {
-        std::string::String::deserialize(deserializer)
-            .and_then(|s| Self::try_from(s.as_str()).map_err(D::Error::custom))
+        deserializer.deserialize_str(BoundedStringVisitor(PhantomData))
     }
 }
 
@@ -206,4 +225,34 @@ mod tests {
             s == recovered
         }
     }
+
+    quickcheck! {
+        fn test_wstrings_serialize_as_utf8(s: std::string::String) -> bool {
+            let expected = serde_json::Value::String(s.clone());
+            let bounded = BoundedWString::<256>::try_from(s.as_str());
+            serde_json::to_value(WString::from(s.as_str())).unwrap() == expected
+                && bounded.map_or(true, |b| serde_json::to_value(b).unwrap() == expected)
+        }
+    }
+
+    quickcheck! {
+        fn test_bounded_string_bound_is_enforced(s: std::string::String) -> bool {
+            // The bound of wide strings is in UTF-16 code units
+            let within_bound = s.chars().count() <= 8;
+            let within_wide_bound = s.encode_utf16().count() <= 8;
+            let value = serde_json::Value::String(s);
+            serde_json::from_value::<BoundedString<8>>(value.clone()).is_ok() == within_bound
+                && serde_json::from_value::<BoundedWString<8>>(value).is_ok() == within_wide_bound
+        }
+    }
+
+    #[test]
+    fn test_bounded_string_bound_error() {
+        let err = serde_json::from_str::<BoundedString<2>>("\"abc\"").unwrap_err();
+        assert!(err.to_string().contains("upper bound 2"));
+        let err = serde_json::from_str::<BoundedWString<2>>("\"abc\"").unwrap_err();
+        assert!(err.to_string().contains("upper bound 2"));
+        // Non-strings are rejected with a type error
+        assert!(serde_json::from_str::<BoundedString<2>>("12").is_err());
+    }
 }
@@ -18,6 +18,8 @@
 use std::borrow::Cow;
 use std::fmt::Debug;
 
+use crate::ValidationError;
+
 /// Internal trait that connects a particular `Sequence<T>` instance to generated C functions
 /// that allocate and deallocate memory.
 ///
@@ -141,10 +143,62 @@ pub trait Message: Clone + Debug + Default + 'static + Send + Sync {
     /// If this function receives a borrowed message that is already RMW-native, it should
     /// directly return that borrowed message.
     /// This is why the return type is also `Cow`.
+    ///
+    /// # Panics
+    /// Panics if a field violates its bound, see [`Message::try_into_rmw_message`].
     fn into_rmw_message(msg_cow: Cow<'_, Self>) -> Cow<'_, Self::RmwMsg>;
 
     /// Converts the RMW-native message into an idiomatic message.
+    ///
+    /// # Panics
+    /// Panics if a field doesn't fit into the idiomatic message, see
+    /// [`Message::try_from_rmw_message`].
     fn from_rmw_message(msg: Self::RmwMsg) -> Self;
+
+    /// Converts a borrowed RMW-native message into an idiomatic message.
+    ///
+    /// This is useful for messages that can't be moved out of, such as loaned messages.
+    /// The default implementation clones the message and calls [`Message::from_rmw_message`].
+    ///
+    /// # Panics
+    /// Panics if a field doesn't fit into the idiomatic message, see
+    /// [`Message::try_from_rmw_message_ref`].
+    fn from_rmw_message_ref(msg: &Self::RmwMsg) -> Self {
+        Self::from_rmw_message(msg.clone())
+    }
+
+    /// Converts the idiomatic message into an RMW-native message, or returns an error if a field
+    /// violates its bound.
+    ///
+    /// The fields of idiomatic messages can't always enforce their bounds, e.g. a `wstring<=N`
+    /// field can hold more than `N` UTF-16 code units.
+    /// The default implementation calls [`Message::into_rmw_message`], for messages whose
+    /// conversion can't fail.
+    fn try_into_rmw_message(
+        msg_cow: Cow<'_, Self>,
+    ) -> Result<Cow<'_, Self::RmwMsg>, ValidationError> {
+        Ok(Self::into_rmw_message(msg_cow))
+    }
+
+    /// Converts the RMW-native message into an idiomatic message, or returns an error if a field
+    /// doesn't fit into the idiomatic message.
+    ///
+    /// Not every middleware checks the bounds of received strings, so a string can exceed the
+    /// bound of its field.
+    /// The default implementation calls [`Message::from_rmw_message`], for messages whose
+    /// conversion can't fail.
+    fn try_from_rmw_message(msg: Self::RmwMsg) -> Result<Self, ValidationError> {
+        Ok(Self::from_rmw_message(msg))
+    }
+
+    /// Converts a borrowed RMW-native message into an idiomatic message, or returns an error if a
+    /// field doesn't fit into the idiomatic message.
+    ///
+    /// The default implementation clones the message and calls
+    /// [`Message::try_from_rmw_message`].
+    fn try_from_rmw_message_ref(msg: &Self::RmwMsg) -> Result<Self, ValidationError> {
+        Self::try_from_rmw_message(msg.clone())
+    }
 }
 
 /// Trait for services.
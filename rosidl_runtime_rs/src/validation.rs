@@ -0,0 +1,103 @@
+//! Checked conversions of the fields of RMW-native messages, used by the generated
+//! [`Message::try_from_rmw_message()`] implementations.
+//!
+//! Not every middleware checks the bounds of the strings it receives. Instead of failing at the
+//! first field that doesn't fit into the idiomatic message, these functions record a
+//! [`Violation`] for each such field, so that the error lists all of them.
+
+pub use rosidl_shared::validation::{ValidationError, Violation, ViolationKind, Violations};
+
+use crate::{BoundedString, BoundedWString, Message};
+
+/// Converts a bounded string into a string with a capacity of `M` bytes, or records a violation
+/// and returns an empty string.
+///
+/// The `index` is the index of the element if the string is an element of an array or sequence.
+/// The length of the violation is in characters, see [`heapless::String::try_from()`].
+pub fn string_from_rmw<const N: usize, const M: usize>(
+    field: &'static str,
+    index: Option<usize>,
+    value: &BoundedString<N>,
+    violations: &mut Violations,
+) -> heapless::String<M> {
+    heapless::String::try_from(value).unwrap_or_else(|err| {
+        violations.push(Violation {
+            field,
+            index,
+            kind: ViolationKind::BoundedString,
+            length: err.len,
+            bound: err.upper_bound,
+        });
+        heapless::String::new()
+    })
+}
+
+/// Converts a bounded wide string into a string with a capacity of `M` bytes, or records a
+/// violation and returns an empty string.
+///
+/// The `index` is the index of the element if the string is an element of an array or sequence.
+/// The length of the violation is in UTF-16 code units, see [`heapless::String::try_from()`].
+pub fn wstring_from_rmw<const N: usize, const M: usize>(
+    field: &'static str,
+    index: Option<usize>,
+    value: &BoundedWString<N>,
+    violations: &mut Violations,
+) -> heapless::String<M> {
+    heapless::String::try_from(value).unwrap_or_else(|err| {
+        violations.push(Violation {
+            field,
+            index,
+            kind: ViolationKind::BoundedWString,
+            length: err.len,
+            bound: err.upper_bound,
+        });
+        heapless::String::new()
+    })
+}
+
+/// Converts a nested message, or records its violations and returns the default message.
+pub fn message_from_rmw<T: Message>(msg: T::RmwMsg, violations: &mut Violations) -> T {
+    T::try_from_rmw_message(msg).unwrap_or_else(|err| record(err, violations))
+}
+
+/// Converts a borrowed nested message, or records its violations and returns the default message.
+pub fn message_from_rmw_ref<T: Message>(msg: &T::RmwMsg, violations: &mut Violations) -> T {
+    T::try_from_rmw_message_ref(msg).unwrap_or_else(|err| record(err, violations))
+}
+
+fn record<T: Default>(err: ValidationError, violations: &mut Violations) -> T {
+    for violation in err.violations.iter() {
+        violations.push(*violation);
+    }
+    T::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_from_rmw() {
+        let mut violations = Violations::default();
+        let value = BoundedString::<3>::try_from("üüü").unwrap();
+        let converted: heapless::String<12> =
+            string_from_rmw("name", None, &value, &mut violations);
+        assert_eq!(converted, "üüü");
+        assert!(violations.is_empty());
+
+        let value = BoundedWString::<3>::try_from("abc").unwrap();
+        let converted: heapless::String<2> =
+            wstring_from_rmw("names", Some(1), &value, &mut violations);
+        assert_eq!(converted, "");
+        assert_eq!(
+            violations[..],
+            [Violation {
+                field: "names",
+                index: Some(1),
+                kind: ViolationKind::BoundedWString,
+                length: 3,
+                bound: 2,
+            }]
+        );
+    }
+}
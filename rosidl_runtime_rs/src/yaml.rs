@@ -0,0 +1,193 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Error type for [`to_yaml()`] and [`from_yaml()`].
+pub use serde_yaml::Error as YamlError;
+
+/// Converts a message to YAML in the format printed by `ros2 topic echo`.
+///
+/// Nested messages become nested mappings, and arrays and sequences become lists. Values of
+/// `byte` and `char` fields become strings of one character, e.g. `"\x01"`, while `uint8` values
+/// stay integers. Unlike `ros2 topic echo`, the output does not end with a `---` separator.
+///
+/// This works for both idiomatic and RMW-native messages generated with the `serde` feature.
+pub fn to_yaml<T: Serialize>(msg: &T) -> Result<std::string::String, YamlError> {
+    serde_yaml::to_string(msg)
+}
+
+/// Parses a message from YAML in the format accepted by `ros2 topic pub`.
+///
+/// Both block style, as printed by `ros2 topic echo`, and flow style, such as `{data: 1}`, are
+/// accepted. A trailing `---` separator is ignored.
+///
+/// Fields that are missing are set to their default values, so an empty input results in the
+/// default message.
+pub fn from_yaml<T: DeserializeOwned>(yaml: &str) -> Result<T, YamlError> {
+    let yaml = strip_document_end(yaml);
+    if yaml.trim().is_empty() {
+        return serde_yaml::from_str("{}");
+    }
+    serde_yaml::from_str(yaml)
+}
+
+// Removes the `---` line that `ros2 topic echo` prints after each message.
+fn strip_document_end(yaml: &str) -> &str {
+    let yaml = yaml.trim_end();
+    match yaml.rsplit_once('\n') {
+        Some((body, last_line)) if last_line.trim_end() == "---" => body,
+        None if yaml == "---" => "",
+        _ => yaml,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{BoundedSequence, Sequence, String};
+
+    #[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+    #[serde(default)]
+    struct Time {
+        sec: i32,
+        nanosec: u32,
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+    #[serde(default)]
+    struct Sample {
+        stamp: Time,
+        frame_id: String,
+        // uint8[]
+        data: Sequence<u8>,
+        flags: [bool; 2],
+        scale: f64,
+        labels: BoundedSequence<String, 4>,
+    }
+
+    // The serde attributes that the generator emits for byte and char fields
+    #[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+    #[serde(default)]
+    struct Bytes {
+        #[serde(with = "rosidl_shared::byte_serde::byte")]
+        byte_value: u8,
+        #[serde(with = "rosidl_shared::byte_serde::byte")]
+        char_value: u8,
+        #[serde(with = "rosidl_shared::byte_serde::byte_array")]
+        byte_array: [u8; 2],
+        #[serde(with = "rosidl_shared::byte_serde::byte_sequence")]
+        byte_sequence: Sequence<u8>,
+        #[serde(with = "rosidl_shared::byte_serde::byte_sequence")]
+        char_sequence: BoundedSequence<u8, 3>,
+        uint8_array: [u8; 2],
+    }
+
+    fn bytes() -> Bytes {
+        Bytes {
+            byte_value: 0x01,
+            char_value: b'a',
+            byte_array: [0, 0xff],
+            byte_sequence: Sequence::from(vec![b'1', b'\t']),
+            char_sequence: BoundedSequence::from_iter([b'x', b' ']),
+            uint8_array: [1, 255],
+        }
+    }
+
+    fn sample() -> Sample {
+        Sample {
+            stamp: Time {
+                sec: 12,
+                nanosec: 34,
+            },
+            frame_id: String::from("map"),
+            data: Sequence::from(vec![1, 255]),
+            flags: [true, false],
+            scale: 1.0,
+            labels: BoundedSequence::from_iter([String::from("a"), String::from("")]),
+        }
+    }
+
+    #[test]
+    fn test_to_yaml_matches_ros2_topic_echo() {
+        let expected = "\
+stamp:
+  sec: 12
+  nanosec: 34
+frame_id: map
+data:
+- 1
+- 255
+flags:
+- true
+- false
+scale: 1.0
+labels:
+- a
+- ''
+";
+        assert_eq!(to_yaml(&sample()).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_to_yaml_shows_bytes_and_chars_as_strings() {
+        let expected = "\
+byte_value: \"\\x01\"
+char_value: a
+byte_array:
+- \"\\0\"
+- ÿ
+byte_sequence:
+- '1'
+- \"\\t\"
+char_sequence:
+- x
+- ' '
+uint8_array:
+- 1
+- 255
+";
+        assert_eq!(to_yaml(&bytes()).unwrap(), expected);
+        assert_eq!(from_yaml::<Bytes>(expected).unwrap(), bytes());
+    }
+
+    #[test]
+    fn test_from_yaml_accepts_integers_for_bytes_and_chars() {
+        let msg: Bytes = from_yaml(
+            "{byte_value: 1, char_value: 97, byte_array: [0, 255], byte_sequence: ['1', 9], \
+             char_sequence: [x, 32], uint8_array: [1, 255]}",
+        )
+        .unwrap();
+        assert_eq!(msg, bytes());
+        assert!(from_yaml::<Bytes>("char_value: ab").is_err());
+        assert!(from_yaml::<Bytes>("char_sequence: [a, b, c, d]").is_err());
+    }
+
+    #[test]
+    fn test_from_yaml_roundtrip() {
+        let yaml = to_yaml(&sample()).unwrap();
+        assert_eq!(from_yaml::<Sample>(&yaml).unwrap(), sample());
+        let echoed = format!("{}---\n", yaml);
+        assert_eq!(from_yaml::<Sample>(&echoed).unwrap(), sample());
+    }
+
+    #[test]
+    fn test_from_yaml_accepts_ros2_topic_pub_input() {
+        let msg: Sample =
+            from_yaml("{stamp: {sec: 12}, frame_id: map, data: [1, 255], scale: 2}").unwrap();
+        assert_eq!(msg.stamp.sec, 12);
+        assert_eq!(msg.stamp.nanosec, 0);
+        assert_eq!(msg.frame_id.to_string(), "map");
+        assert_eq!(msg.data.as_slice(), [1, 255]);
+        assert_eq!(msg.flags, [false, false]);
+        assert_eq!(msg.scale, 2.0);
+        assert_eq!(from_yaml::<Sample>("").unwrap(), Sample::default());
+        assert_eq!(from_yaml::<Sample>("{}").unwrap(), Sample::default());
+    }
+
+    #[test]
+    fn test_from_yaml_rejects_invalid_input() {
+        assert!(from_yaml::<Sample>("data: [256]").is_err());
+        assert!(from_yaml::<Sample>("labels: [a, b, c, d, e]").is_err());
+        assert!(from_yaml::<Sample>("stamp: 5").is_err());
+    }
+}
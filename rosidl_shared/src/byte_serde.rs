@@ -0,0 +1,286 @@
+//! Serde support for `byte` and `char` fields, which are `u8` in Rust.
+//!
+//! The ros2 CLI shows such values as strings of one character, e.g. `"\x01"` or `a`, while
+//! `uint8` values are shown as integers. Generated messages use the modules in here with
+//! `#[serde(with = "...")]` to do the same. Both strings of one character and integers are
+//! accepted when deserializing.
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+use serde::de::{self, DeserializeSeed, Deserializer, SeqAccess, Unexpected, Visitor};
+use serde::ser::{SerializeTuple, Serializer};
+use serde::{Deserialize, Serialize};
+
+/// For `byte` and `char` fields.
+pub mod byte {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(char::from(*value).encode_utf8(&mut [0; 2]))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+        u8::deserialize(ByteDeserializer(deserializer))
+    }
+}
+
+/// For arrays of `byte` and `char`, of any size.
+pub mod byte_array {
+    use super::*;
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        value: &[u8; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for byte in value {
+            tuple.serialize_element(&ByteStr(*byte))?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        deserializer.deserialize_tuple(N, ByteArrayVisitor::<N>)
+    }
+}
+
+/// For sequences of `byte` and `char`, both bounded and unbounded.
+pub mod byte_sequence {
+    use super::*;
+
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Deref<Target = [u8]>,
+    {
+        serializer.collect_seq(value.iter().map(|byte| ByteStr(*byte)))
+    }
+
+    /// Deserializes the sequence with its own implementation, e.g. to check its bound, but with
+    /// elements that may be strings.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        T::deserialize(SequenceDeserializer(deserializer))
+    }
+}
+
+// Serializes a byte as a string, since some formats quote characters differently than strings.
+struct ByteStr(u8);
+
+impl Serialize for ByteStr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        byte::serialize(&self.0, serializer)
+    }
+}
+
+struct ByteArrayVisitor<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for ByteArrayVisitor<N> {
+    type Value = [u8; N];
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "an array of {} bytes", N)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut array = [0; N];
+        for (i, byte) in array.iter_mut().enumerate() {
+            *byte = seq
+                .next_element_seed(ByteSeed(PhantomData::<u8>))?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+        if seq.next_element::<de::IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(N + 1, &self));
+        }
+        Ok(array)
+    }
+}
+
+// Passes a string of one character to the visitor of a byte as its code point.
+struct ByteDeserializer<D>(D);
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for ByteDeserializer<D> {
+    type Error = D::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.0.deserialize_any(ByteVisitor(visitor))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct ByteVisitor<V>(V);
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for ByteVisitor<V> {
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an integer or a string of one character")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        self.0.visit_u64(v)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        self.0.visit_i64(v)
+    }
+
+    fn visit_char<E: de::Error>(self, v: char) -> Result<Self::Value, E> {
+        match u8::try_from(v) {
+            Ok(byte) => self.0.visit_u8(byte),
+            Err(_) => Err(E::invalid_value(Unexpected::Char(v), &self)),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        let mut chars = v.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => self.visit_char(c),
+            _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
+        }
+    }
+}
+
+struct ByteSeed<S>(S);
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for ByteSeed<S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<S::Value, D::Error> {
+        self.0.deserialize(ByteDeserializer(deserializer))
+    }
+}
+
+// Deserializes each element of a sequence with a ByteDeserializer.
+struct SequenceDeserializer<D>(D);
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for SequenceDeserializer<D> {
+    type Error = D::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.0.deserialize_any(SequenceVisitor(visitor))
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.0.deserialize_seq(SequenceVisitor(visitor))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct SequenceVisitor<V>(V);
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for SequenceVisitor<V> {
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.0.expecting(formatter)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        self.0.visit_seq(ByteSeqAccess(seq))
+    }
+}
+
+struct ByteSeqAccess<A>(A);
+
+impl<'de, A: SeqAccess<'de>> SeqAccess<'de> for ByteSeqAccess<A> {
+    type Error = A::Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, Self::Error> {
+        self.0.next_element_seed(ByteSeed(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    #[serde(default)]
+    struct Bytes {
+        #[serde(with = "super::byte")]
+        byte: u8,
+        // Larger than the arrays that serde supports by itself
+        #[serde(with = "super::byte_array")]
+        array: [u8; 40],
+        #[serde(with = "super::byte_sequence")]
+        sequence: Vec<u8>,
+        #[serde(with = "super::byte_sequence")]
+        bounded_sequence: heapless::Vec<u8, 2>,
+    }
+
+    impl Default for Bytes {
+        fn default() -> Self {
+            Self {
+                byte: 0,
+                array: [0; 40],
+                sequence: Vec::new(),
+                bounded_sequence: heapless::Vec::new(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_bytes_are_strings() {
+        let mut msg = Bytes {
+            byte: b'a',
+            sequence: vec![0, 0xe9],
+            bounded_sequence: heapless::Vec::from_slice(&[1, 2]).unwrap(),
+            ..Default::default()
+        };
+        msg.array[0] = b'"';
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.starts_with(r#"{"byte":"a","array":["\"","\u0000","#));
+        assert!(
+            json.ends_with(r#""sequence":["\u0000","é"],"bounded_sequence":["\u0001","\u0002"]}"#)
+        );
+        assert_eq!(serde_json::from_str::<Bytes>(&json).unwrap(), msg);
+    }
+
+    #[test]
+    fn test_integers_are_accepted() {
+        let msg: Bytes =
+            serde_json::from_str(r#"{"byte": 97, "sequence": [0, "b"], "bounded_sequence": [1]}"#)
+                .unwrap();
+        assert_eq!(msg.byte, b'a');
+        assert_eq!(msg.sequence, [0, b'b']);
+        assert_eq!(msg.bounded_sequence, [1]);
+    }
+
+    #[test]
+    fn test_invalid_bytes_are_rejected() {
+        for json in [
+            r#"{"byte": 256}"#,
+            r#"{"byte": "ab"}"#,
+            r#"{"byte": "€"}"#,
+            r#"{"array": [1, 2]}"#,
+            r#"{"bounded_sequence": [1, 2, 3]}"#,
+        ] {
+            assert!(serde_json::from_str::<Bytes>(json).is_err(), "{}", json);
+        }
+    }
+}
@@ -0,0 +1,818 @@
+//! Serialization of messages in the CDR format used by the ROS middleware.
+//!
+//! The encoding is plain (XCDR1) CDR, as produced by `rmw_serialize()`: the payload is prefixed
+//! by a four byte encapsulation header, and every primitive is aligned to its size relative to the
+//! start of the payload.
+//!
+//! - Strings are encoded as a `u32` length including the null terminator, followed by the
+//!   UTF-8 bytes and the null terminator.
+//! - Wide strings are encoded as a `u32` number of UTF-16 code units, followed by the code units.
+//! - Sequences are encoded as a `u32` length followed by the elements, arrays only contain the
+//!   elements.
+//! - Messages are encoded as the concatenation of their fields.
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Display};
+
+/// Encapsulation identifier of big-endian plain CDR.
+const CDR_BE: [u8; 2] = [0x00, 0x00];
+/// Encapsulation identifier of little-endian plain CDR.
+const CDR_LE: [u8; 2] = [0x00, 0x01];
+
+/// The byte order of serialized data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    /// Most significant byte first.
+    BigEndian,
+    /// Least significant byte first.
+    LittleEndian,
+}
+
+/// An error that occurred while serializing or deserializing CDR data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CdrError {
+    /// The input ended before the value was complete.
+    UnexpectedEnd,
+    /// The output buffer has no space left.
+    BufferFull,
+    /// The encapsulation header is not one of plain CDR.
+    InvalidEncapsulation([u8; 2]),
+    /// A boolean was neither 0 nor 1.
+    InvalidBool(u8),
+    /// A string was not null-terminated.
+    MissingNullTerminator,
+    /// A string was not valid UTF-8.
+    InvalidUtf8,
+    /// A wide string was not valid UTF-16.
+    InvalidUtf16,
+    /// A bounded sequence or string is longer than its bound.
+    BoundExceeded {
+        /// The length of the sequence or string.
+        length: usize,
+        /// The bound of the sequence or string.
+        bound: usize,
+    },
+    /// A length does not fit into the `u32` length prefix.
+    LengthOverflow(usize),
+}
+
+impl Display for CdrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "Unexpected end of CDR data"),
+            Self::BufferFull => write!(f, "Buffer is full"),
+            Self::InvalidEncapsulation(id) => {
+                write!(
+                    f,
+                    "Unsupported CDR encapsulation {:02x}{:02x}",
+                    id[0], id[1]
+                )
+            }
+            Self::InvalidBool(value) => write!(f, "Invalid boolean value {}", value),
+            Self::MissingNullTerminator => write!(f, "String is not null-terminated"),
+            Self::InvalidUtf8 => write!(f, "String is not valid UTF-8"),
+            Self::InvalidUtf16 => write!(f, "Wide string is not valid UTF-16"),
+            Self::BoundExceeded { length, bound } => {
+                write!(f, "Length {} exceeds the bound of {}", length, bound)
+            }
+            Self::LengthOverflow(length) => write!(f, "Length {} does not fit into u32", length),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CdrError {}
+
+/// A buffer that serialized data can be written to.
+pub trait CdrWrite {
+    /// Appends the bytes to the buffer.
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), CdrError>;
+}
+
+#[cfg(feature = "alloc")]
+impl CdrWrite for Vec<u8> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), CdrError> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl<const N: usize> CdrWrite for heapless::Vec<u8, N> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), CdrError> {
+        self.extend_from_slice(bytes)
+            .map_err(|_| CdrError::BufferFull)
+    }
+}
+
+impl<W: CdrWrite + ?Sized> CdrWrite for &mut W {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), CdrError> {
+        (**self).write_bytes(bytes)
+    }
+}
+
+/// A type that can be serialized to CDR.
+///
+/// This is implemented by the generated idiomatic message types.
+pub trait CdrSerialize {
+    /// Serializes the value.
+    fn serialize<W: CdrWrite>(&self, serializer: &mut CdrSerializer<W>) -> Result<(), CdrError>;
+}
+
+/// A type that can be deserialized from CDR.
+///
+/// This is implemented by the generated idiomatic message types.
+pub trait CdrDeserialize: Sized {
+    /// Deserializes a value.
+    fn deserialize(deserializer: &mut CdrDeserializer<'_>) -> Result<Self, CdrError>;
+}
+
+/// A string type that strings can be deserialized into.
+pub trait CdrString: Default {
+    /// Appends the string slice, or fails if the string would exceed its bound.
+    fn push_cdr_str(&mut self, s: &str) -> Result<(), CdrError>;
+}
+
+#[cfg(feature = "alloc")]
+impl CdrString for String {
+    fn push_cdr_str(&mut self, s: &str) -> Result<(), CdrError> {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl<const N: usize> CdrString for heapless::String<N> {
+    fn push_cdr_str(&mut self, s: &str) -> Result<(), CdrError> {
+        self.push_str(s).map_err(|_| CdrError::BoundExceeded {
+            length: self.len() + s.len(),
+            bound: N,
+        })
+    }
+}
+
+/// A sequence type that sequences can be deserialized into.
+pub trait CdrSequence<T>: Sized {
+    /// Creates an empty sequence, or fails if the length exceeds the bound of the sequence.
+    fn with_cdr_len(len: usize) -> Result<Self, CdrError>;
+    /// Appends an element.
+    ///
+    /// This is only called as many times as the length passed to `with_cdr_len()`.
+    fn push_cdr_elem(&mut self, elem: T);
+}
+
+#[cfg(feature = "alloc")]
+impl<T> CdrSequence<T> for Vec<T> {
+    fn with_cdr_len(len: usize) -> Result<Self, CdrError> {
+        Ok(Vec::with_capacity(len))
+    }
+
+    fn push_cdr_elem(&mut self, elem: T) {
+        self.push(elem);
+    }
+}
+
+impl<T, const N: usize> CdrSequence<T> for heapless::Vec<T, N> {
+    fn with_cdr_len(len: usize) -> Result<Self, CdrError> {
+        if len > N {
+            return Err(CdrError::BoundExceeded {
+                length: len,
+                bound: N,
+            });
+        }
+        Ok(heapless::Vec::new())
+    }
+
+    fn push_cdr_elem(&mut self, elem: T) {
+        // The length was checked in with_cdr_len()
+        let _ = self.push(elem);
+    }
+}
+
+/// Serializes values into a buffer, without an encapsulation header.
+///
+/// Use [`to_writer()`] to serialize a complete message.
+pub struct CdrSerializer<W> {
+    writer: W,
+    endianness: Endianness,
+    // The position relative to the start of the payload, used for alignment
+    position: usize,
+}
+
+impl<W: CdrWrite> CdrSerializer<W> {
+    /// Creates a serializer that writes a payload to the buffer.
+    pub fn new(writer: W, endianness: Endianness) -> Self {
+        Self {
+            writer,
+            endianness,
+            position: 0,
+        }
+    }
+
+    /// Returns the buffer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Returns the byte order of the serializer.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// Serializes a value.
+    pub fn serialize<T: CdrSerialize + ?Sized>(&mut self, value: &T) -> Result<(), CdrError> {
+        value.serialize(self)
+    }
+
+    /// Serializes the length of a sequence or string.
+    pub fn serialize_length(&mut self, len: usize) -> Result<(), CdrError> {
+        let len = u32::try_from(len).map_err(|_| CdrError::LengthOverflow(len))?;
+        self.serialize(&len)
+    }
+
+    /// Serializes a wide string.
+    pub fn serialize_wstring(&mut self, value: &str) -> Result<(), CdrError> {
+        self.serialize_length(value.encode_utf16().count())?;
+        value
+            .encode_utf16()
+            .try_for_each(|code_unit| self.serialize(&code_unit))
+    }
+
+    /// Serializes the elements of an array with the given function.
+    pub fn serialize_array_with<T, F>(&mut self, elems: &[T], mut f: F) -> Result<(), CdrError>
+    where
+        F: FnMut(&mut Self, &T) -> Result<(), CdrError>,
+    {
+        elems.iter().try_for_each(|elem| f(self, elem))
+    }
+
+    /// Serializes the length and elements of a sequence with the given function.
+    pub fn serialize_sequence_with<T, F>(&mut self, elems: &[T], f: F) -> Result<(), CdrError>
+    where
+        F: FnMut(&mut Self, &T) -> Result<(), CdrError>,
+    {
+        self.serialize_length(elems.len())?;
+        self.serialize_array_with(elems, f)
+    }
+
+    fn write_aligned(&mut self, bytes: &[u8]) -> Result<(), CdrError> {
+        const PADDING: [u8; 8] = [0; 8];
+        let padding = (bytes.len() - self.position % bytes.len()) % bytes.len();
+        self.writer.write_bytes(&PADDING[..padding])?;
+        self.writer.write_bytes(bytes)?;
+        self.position += padding + bytes.len();
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), CdrError> {
+        self.writer.write_bytes(bytes)?;
+        self.position += bytes.len();
+        Ok(())
+    }
+}
+
+/// Deserializes values from a buffer, without an encapsulation header.
+///
+/// Use [`from_bytes()`] to deserialize a complete message.
+pub struct CdrDeserializer<'a> {
+    buffer: &'a [u8],
+    endianness: Endianness,
+    // The position relative to the start of the payload
+    position: usize,
+}
+
+impl<'a> CdrDeserializer<'a> {
+    /// Creates a deserializer that reads a payload from the buffer.
+    pub fn new(buffer: &'a [u8], endianness: Endianness) -> Self {
+        Self {
+            buffer,
+            endianness,
+            position: 0,
+        }
+    }
+
+    /// Returns the byte order of the deserializer.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// Returns the number of bytes that have not been read yet.
+    pub fn remaining(&self) -> usize {
+        self.buffer.len() - self.position
+    }
+
+    /// Deserializes a value.
+    pub fn deserialize<T: CdrDeserialize>(&mut self) -> Result<T, CdrError> {
+        T::deserialize(self)
+    }
+
+    /// Deserializes the length of a sequence or string.
+    ///
+    /// Since every element takes at least one byte, lengths that exceed the remaining data are
+    /// rejected before anything is allocated for them.
+    pub fn deserialize_length(&mut self) -> Result<usize, CdrError> {
+        let len = self.deserialize::<u32>()? as usize;
+        if len > self.remaining() {
+            return Err(CdrError::UnexpectedEnd);
+        }
+        Ok(len)
+    }
+
+    /// Deserializes a wide string.
+    pub fn deserialize_wstring<S: CdrString>(&mut self) -> Result<S, CdrError> {
+        let len = self.deserialize_length()?;
+        let mut code_units_left = len;
+        let code_units = core::iter::from_fn(|| {
+            if code_units_left == 0 {
+                return None;
+            }
+            code_units_left -= 1;
+            Some(self.deserialize::<u16>())
+        });
+        let mut string = S::default();
+        let mut error = None;
+        let chars = char::decode_utf16(
+            code_units.map_while(|code_unit| code_unit.map_err(|e| error = Some(e)).ok()),
+        );
+        for c in chars {
+            let c = c.map_err(|_| CdrError::InvalidUtf16)?;
+            string.push_cdr_str(c.encode_utf8(&mut [0; 4]))?;
+        }
+        match error {
+            Some(e) => Err(e),
+            None => Ok(string),
+        }
+    }
+
+    /// Deserializes the elements of an array with the given function.
+    pub fn deserialize_array_with<T, F, const N: usize>(
+        &mut self,
+        mut f: F,
+    ) -> Result<[T; N], CdrError>
+    where
+        F: FnMut(&mut Self) -> Result<T, CdrError>,
+    {
+        let mut error = None;
+        let elems = core::array::from_fn(|_| {
+            if error.is_some() {
+                return None;
+            }
+            f(self).map_err(|e| error = Some(e)).ok()
+        });
+        match error {
+            Some(e) => Err(e),
+            None => Ok(elems.map(Option::unwrap)),
+        }
+    }
+
+    /// Deserializes the length and elements of a sequence with the given function.
+    pub fn deserialize_sequence_with<T, S, F>(&mut self, mut f: F) -> Result<S, CdrError>
+    where
+        S: CdrSequence<T>,
+        F: FnMut(&mut Self) -> Result<T, CdrError>,
+    {
+        let len = self.deserialize_length()?;
+        let mut elems = S::with_cdr_len(len)?;
+        for _ in 0..len {
+            elems.push_cdr_elem(f(self)?);
+        }
+        Ok(elems)
+    }
+
+    fn read_aligned<const N: usize>(&mut self) -> Result<[u8; N], CdrError> {
+        let padding = (N - self.position % N) % N;
+        self.read_bytes(padding)?;
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(self.read_bytes(N)?);
+        Ok(bytes)
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], CdrError> {
+        if len > self.remaining() {
+            return Err(CdrError::UnexpectedEnd);
+        }
+        let bytes = &self.buffer[self.position..self.position + len];
+        self.position += len;
+        Ok(bytes)
+    }
+}
+
+/// Serializes a message with an encapsulation header into the buffer.
+pub fn to_writer<T, W>(value: &T, mut writer: W, endianness: Endianness) -> Result<W, CdrError>
+where
+    T: CdrSerialize + ?Sized,
+    W: CdrWrite,
+{
+    let id = match endianness {
+        Endianness::BigEndian => CDR_BE,
+        Endianness::LittleEndian => CDR_LE,
+    };
+    writer.write_bytes(&[id[0], id[1], 0x00, 0x00])?;
+    let mut serializer = CdrSerializer::new(writer, endianness);
+    serializer.serialize(value)?;
+    Ok(serializer.into_inner())
+}
+
+/// Serializes a message with an encapsulation header into a new little-endian buffer.
+///
+/// This is the same format as returned by `rmw_serialize()` on little-endian platforms.
+#[cfg(feature = "alloc")]
+pub fn to_vec<T: CdrSerialize + ?Sized>(value: &T) -> Result<Vec<u8>, CdrError> {
+    to_writer(value, Vec::new(), Endianness::LittleEndian)
+}
+
+/// Deserializes a message with an encapsulation header.
+///
+/// Trailing bytes after the message, such as padding, are ignored.
+pub fn from_bytes<T: CdrDeserialize>(bytes: &[u8]) -> Result<T, CdrError> {
+    if bytes.len() < 4 {
+        return Err(CdrError::UnexpectedEnd);
+    }
+    let endianness = match [bytes[0], bytes[1]] {
+        CDR_BE => Endianness::BigEndian,
+        CDR_LE => Endianness::LittleEndian,
+        id => return Err(CdrError::InvalidEncapsulation(id)),
+    };
+    CdrDeserializer::new(&bytes[4..], endianness).deserialize()
+}
+
+macro_rules! impl_cdr_for_primitive {
+    ($($type:ty),*) => {
+        $(
+            impl CdrSerialize for $type {
+                fn serialize<W: CdrWrite>(
+                    &self,
+                    serializer: &mut CdrSerializer<W>,
+                ) -> Result<(), CdrError> {
+                    match serializer.endianness {
+                        Endianness::BigEndian => serializer.write_aligned(&self.to_be_bytes()),
+                        Endianness::LittleEndian => serializer.write_aligned(&self.to_le_bytes()),
+                    }
+                }
+            }
+
+            impl CdrDeserialize for $type {
+                fn deserialize(deserializer: &mut CdrDeserializer<'_>) -> Result<Self, CdrError> {
+                    let bytes = deserializer.read_aligned()?;
+                    Ok(match deserializer.endianness {
+                        Endianness::BigEndian => <$type>::from_be_bytes(bytes),
+                        Endianness::LittleEndian => <$type>::from_le_bytes(bytes),
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_cdr_for_primitive!(i8, u8, i16, u16, i32, u32, i64, u64, f32, f64);
+
+impl CdrSerialize for bool {
+    fn serialize<W: CdrWrite>(&self, serializer: &mut CdrSerializer<W>) -> Result<(), CdrError> {
+        serializer.serialize(&u8::from(*self))
+    }
+}
+
+impl CdrDeserialize for bool {
+    fn deserialize(deserializer: &mut CdrDeserializer<'_>) -> Result<Self, CdrError> {
+        match deserializer.deserialize::<u8>()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(CdrError::InvalidBool(value)),
+        }
+    }
+}
+
+impl CdrSerialize for str {
+    fn serialize<W: CdrWrite>(&self, serializer: &mut CdrSerializer<W>) -> Result<(), CdrError> {
+        serializer.serialize_length(self.len() + 1)?;
+        serializer.write_bytes(self.as_bytes())?;
+        serializer.write_bytes(&[0])
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl CdrSerialize for String {
+    fn serialize<W: CdrWrite>(&self, serializer: &mut CdrSerializer<W>) -> Result<(), CdrError> {
+        serializer.serialize(self.as_str())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl CdrDeserialize for String {
+    fn deserialize(deserializer: &mut CdrDeserializer<'_>) -> Result<Self, CdrError> {
+        deserialize_string(deserializer)
+    }
+}
+
+impl<const N: usize> CdrSerialize for heapless::String<N> {
+    fn serialize<W: CdrWrite>(&self, serializer: &mut CdrSerializer<W>) -> Result<(), CdrError> {
+        serializer.serialize(self.as_str())
+    }
+}
+
+impl<const N: usize> CdrDeserialize for heapless::String<N> {
+    fn deserialize(deserializer: &mut CdrDeserializer<'_>) -> Result<Self, CdrError> {
+        deserialize_string(deserializer)
+    }
+}
+
+impl<T: CdrSerialize, const N: usize> CdrSerialize for [T; N] {
+    fn serialize<W: CdrWrite>(&self, serializer: &mut CdrSerializer<W>) -> Result<(), CdrError> {
+        serializer.serialize_array_with(self, |serializer, elem| serializer.serialize(elem))
+    }
+}
+
+impl<T: CdrDeserialize, const N: usize> CdrDeserialize for [T; N] {
+    fn deserialize(deserializer: &mut CdrDeserializer<'_>) -> Result<Self, CdrError> {
+        deserializer.deserialize_array_with(|deserializer| deserializer.deserialize())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: CdrSerialize> CdrSerialize for Vec<T> {
+    fn serialize<W: CdrWrite>(&self, serializer: &mut CdrSerializer<W>) -> Result<(), CdrError> {
+        serializer.serialize_sequence_with(self, |serializer, elem| serializer.serialize(elem))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: CdrDeserialize> CdrDeserialize for Vec<T> {
+    fn deserialize(deserializer: &mut CdrDeserializer<'_>) -> Result<Self, CdrError> {
+        deserializer.deserialize_sequence_with(|deserializer| deserializer.deserialize())
+    }
+}
+
+impl<T: CdrSerialize, const N: usize> CdrSerialize for heapless::Vec<T, N> {
+    fn serialize<W: CdrWrite>(&self, serializer: &mut CdrSerializer<W>) -> Result<(), CdrError> {
+        serializer.serialize_sequence_with(self, |serializer, elem| serializer.serialize(elem))
+    }
+}
+
+impl<T: CdrDeserialize, const N: usize> CdrDeserialize for heapless::Vec<T, N> {
+    fn deserialize(deserializer: &mut CdrDeserializer<'_>) -> Result<Self, CdrError> {
+        deserializer.deserialize_sequence_with(|deserializer| deserializer.deserialize())
+    }
+}
+
+fn deserialize_string<S: CdrString>(deserializer: &mut CdrDeserializer<'_>) -> Result<S, CdrError> {
+    let len = deserializer.deserialize_length()?;
+    // Some implementations encode empty strings without the null terminator
+    if len == 0 {
+        return Ok(S::default());
+    }
+    let bytes = deserializer.read_bytes(len)?;
+    let (nul, bytes) = bytes.split_last().ok_or(CdrError::MissingNullTerminator)?;
+    if *nul != 0 {
+        return Err(CdrError::MissingNullTerminator);
+    }
+    let s = core::str::from_utf8(bytes).map_err(|_| CdrError::InvalidUtf8)?;
+    let mut string = S::default();
+    string.push_cdr_str(s)?;
+    Ok(string)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Sample {
+        flag: bool,
+        value: f64,
+        short: i16,
+        name: String,
+        bounded_name: heapless::String<4>,
+        wide_name: String,
+        values: Vec<u32>,
+        bounded_values: heapless::Vec<u8, 2>,
+        array: [u16; 3],
+    }
+
+    impl CdrSerialize for Sample {
+        fn serialize<W: CdrWrite>(
+            &self,
+            serializer: &mut CdrSerializer<W>,
+        ) -> Result<(), CdrError> {
+            serializer.serialize(&self.flag)?;
+            serializer.serialize(&self.value)?;
+            serializer.serialize(&self.short)?;
+            serializer.serialize(&self.name)?;
+            serializer.serialize(&self.bounded_name)?;
+            serializer.serialize_wstring(&self.wide_name)?;
+            serializer.serialize(&self.values)?;
+            serializer.serialize(&self.bounded_values)?;
+            serializer.serialize(&self.array)?;
+            Ok(())
+        }
+    }
+
+    impl CdrDeserialize for Sample {
+        fn deserialize(deserializer: &mut CdrDeserializer<'_>) -> Result<Self, CdrError> {
+            Ok(Self {
+                flag: deserializer.deserialize()?,
+                value: deserializer.deserialize()?,
+                short: deserializer.deserialize()?,
+                name: deserializer.deserialize()?,
+                bounded_name: deserializer.deserialize()?,
+                wide_name: deserializer.deserialize_wstring()?,
+                values: deserializer.deserialize()?,
+                bounded_values: deserializer.deserialize()?,
+                array: deserializer.deserialize()?,
+            })
+        }
+    }
+
+    fn sample() -> Sample {
+        Sample {
+            flag: true,
+            value: 1.5,
+            short: -2,
+            name: String::from("ab"),
+            bounded_name: heapless::String::from("c"),
+            wide_name: String::from("\u{1f600}"),
+            values: vec![7],
+            bounded_values: heapless::Vec::from_slice(&[1, 2]).unwrap(),
+            array: [1, 2, 3],
+        }
+    }
+
+    #[test]
+    fn test_little_endian_layout() {
+        #[rustfmt::skip]
+        let expected = [
+            // Encapsulation header
+            0x00, 0x01, 0x00, 0x00,
+            // flag, padding to 8
+            0x01, 0, 0, 0, 0, 0, 0, 0,
+            // value
+            0, 0, 0, 0, 0, 0, 0xf8, 0x3f,
+            // short, padding to 4
+            0xfe, 0xff, 0, 0,
+            // name
+            3, 0, 0, 0, b'a', b'b', 0,
+            // padding to 4, bounded_name
+            0, 2, 0, 0, 0, b'c', 0,
+            // padding to 4, wide_name as a surrogate pair
+            0, 0, 2, 0, 0, 0, 0x3d, 0xd8, 0x00, 0xde,
+            // values
+            1, 0, 0, 0, 7, 0, 0, 0,
+            // bounded_values
+            2, 0, 0, 0, 1, 2,
+            // array
+            1, 0, 2, 0, 3, 0,
+        ];
+        assert_eq!(to_vec(&sample()).unwrap(), expected);
+        assert_eq!(from_bytes::<Sample>(&expected).unwrap(), sample());
+    }
+
+    // The layout written by rmw_cyclonedds_cpp for a message with the fields "string name" and
+    // "wstring wide_name": the length of a wide string counts UTF-16 code units, and there is no
+    // null terminator.
+    #[test]
+    fn test_wstring_layout() {
+        #[derive(Debug, PartialEq)]
+        struct WideSample {
+            name: String,
+            wide_name: String,
+        }
+
+        impl CdrSerialize for WideSample {
+            fn serialize<W: CdrWrite>(
+                &self,
+                serializer: &mut CdrSerializer<W>,
+            ) -> Result<(), CdrError> {
+                serializer.serialize(&self.name)?;
+                serializer.serialize_wstring(&self.wide_name)
+            }
+        }
+
+        impl CdrDeserialize for WideSample {
+            fn deserialize(deserializer: &mut CdrDeserializer<'_>) -> Result<Self, CdrError> {
+                Ok(Self {
+                    name: deserializer.deserialize()?,
+                    wide_name: deserializer.deserialize_wstring()?,
+                })
+            }
+        }
+
+        let sample = WideSample {
+            name: String::from("a"),
+            wide_name: String::from("h\u{e9}\u{20ac}"),
+        };
+        #[rustfmt::skip]
+        let little_endian = [
+            // Encapsulation header
+            0x00, 0x01, 0x00, 0x00,
+            // name
+            2, 0, 0, 0, b'a', 0,
+            // padding to 4, wide_name
+            0, 0, 3, 0, 0, 0, b'h', 0, 0xe9, 0x00, 0xac, 0x20,
+        ];
+        #[rustfmt::skip]
+        let big_endian = [
+            // Encapsulation header
+            0x00, 0x00, 0x00, 0x00,
+            // name
+            0, 0, 0, 2, b'a', 0,
+            // padding to 4, wide_name
+            0, 0, 0, 0, 0, 3, 0, b'h', 0x00, 0xe9, 0x20, 0xac,
+        ];
+        assert_eq!(to_vec(&sample).unwrap(), little_endian);
+        assert_eq!(from_bytes::<WideSample>(&little_endian).unwrap(), sample);
+        assert_eq!(
+            to_writer(&sample, Vec::new(), Endianness::BigEndian).unwrap(),
+            big_endian
+        );
+        assert_eq!(from_bytes::<WideSample>(&big_endian).unwrap(), sample);
+
+        // An empty wide string is only its length
+        let empty = WideSample {
+            name: String::new(),
+            wide_name: String::new(),
+        };
+        #[rustfmt::skip]
+        let expected = [0x00, 0x01, 0x00, 0x00, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(to_vec(&empty).unwrap(), expected);
+        assert_eq!(from_bytes::<WideSample>(&expected).unwrap(), empty);
+
+        // A lone surrogate is not valid UTF-16
+        #[rustfmt::skip]
+        let bytes = [0x00, 0x01, 0x00, 0x00, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0x00, 0xd8];
+        assert_eq!(
+            from_bytes::<WideSample>(&bytes),
+            Err(CdrError::InvalidUtf16)
+        );
+    }
+
+    #[test]
+    fn test_big_endian_round_trip() {
+        let bytes = to_writer(&sample(), Vec::new(), Endianness::BigEndian).unwrap();
+        assert_eq!(bytes[..4], [0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(bytes[12..20], 1.5f64.to_be_bytes());
+        assert_eq!(from_bytes::<Sample>(&bytes).unwrap(), sample());
+    }
+
+    #[test]
+    fn test_heapless_writer() {
+        let bytes = to_writer(
+            &7u32,
+            heapless::Vec::<u8, 8>::new(),
+            Endianness::LittleEndian,
+        )
+        .unwrap();
+        assert_eq!(bytes, [0x00, 0x01, 0x00, 0x00, 7, 0, 0, 0]);
+        assert_eq!(
+            to_writer(
+                &7u64,
+                heapless::Vec::<u8, 8>::new(),
+                Endianness::LittleEndian
+            ),
+            Err(CdrError::BufferFull)
+        );
+    }
+
+    #[test]
+    fn test_bounds_are_checked() {
+        let bytes = to_vec(&vec![1u32, 2, 3]).unwrap();
+        assert_eq!(
+            from_bytes::<heapless::Vec<u32, 2>>(&bytes),
+            Err(CdrError::BoundExceeded {
+                length: 3,
+                bound: 2
+            })
+        );
+        let bytes = to_vec("abcde").unwrap();
+        assert_eq!(
+            from_bytes::<heapless::String<4>>(&bytes),
+            Err(CdrError::BoundExceeded {
+                length: 5,
+                bound: 4
+            })
+        );
+        assert_eq!(from_bytes::<heapless::String<5>>(&bytes).unwrap(), "abcde");
+    }
+
+    #[test]
+    fn test_invalid_data_is_rejected() {
+        assert_eq!(
+            from_bytes::<bool>(&[0x00, 0x01, 0x00, 0x00, 2]),
+            Err(CdrError::InvalidBool(2))
+        );
+        assert_eq!(
+            from_bytes::<u32>(&[0x00, 0x01, 0x00, 0x00, 1, 0]),
+            Err(CdrError::UnexpectedEnd)
+        );
+        assert_eq!(
+            from_bytes::<u8>(&[0x00, 0x03, 0x00, 0x00, 1]),
+            Err(CdrError::InvalidEncapsulation([0x00, 0x03]))
+        );
+        // The length claims more elements than there is data
+        assert_eq!(
+            from_bytes::<Vec<u8>>(&[0x00, 0x01, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff]),
+            Err(CdrError::UnexpectedEnd)
+        );
+        assert_eq!(
+            from_bytes::<String>(&[0x00, 0x01, 0x00, 0x00, 1, 0, 0, 0, b'a']),
+            Err(CdrError::MissingNullTerminator)
+        );
+    }
+}
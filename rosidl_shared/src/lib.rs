@@ -1,7 +1,50 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "serde")]
+pub mod byte_serde;
+pub mod cdr;
+pub mod metadata;
+pub mod validation;
+
+use metadata::{ConstantMetadata, FieldMetadata};
 
 pub trait MiddlewareMessage {
     /// Type name of the message in the ROS middleware
-    /// 
+    ///
+    /// This usually follows a pattern "<package_name>/<subfolder>/<type_name>".
+    const TYPE_NAME: &'static str;
+
+    /// Fields of the message, in the order of the interface definition
+    ///
+    /// This is empty for messages that don't describe their fields.
+    const FIELDS: &'static [FieldMetadata] = &[];
+
+    /// Constants of the message, in the order of the interface definition
+    const CONSTANTS: &'static [ConstantMetadata] = &[];
+
+    /// Type description hash of the message as defined in REP-2011, e.g. "RIHS01_<hex>"
+    ///
+    /// This is `None` for distributions that don't provide type description hashes.
+    const TYPE_HASH: Option<&'static str> = None;
+}
+
+pub trait MiddlewareService {
+    /// Type name of the service in the ROS middleware
+    ///
     /// This usually follows a pattern "<package_name>/<subfolder>/<type_name>".
     const TYPE_NAME: &'static str;
-}
\ No newline at end of file
+
+    /// The request message of the service
+    type Request: MiddlewareMessage;
+
+    /// The response message of the service
+    type Response: MiddlewareMessage;
+
+    /// Type description hash of the service as defined in REP-2011, e.g. "RIHS01_<hex>"
+    ///
+    /// This is `None` for distributions that don't provide type description hashes.
+    const TYPE_HASH: Option<&'static str> = None;
+}
@@ -0,0 +1,170 @@
+//! Descriptions of message types that don't require the C type support.
+
+/// The type of a single value, i.e. of a field without its array or sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    /// `bool`
+    Boolean,
+    /// `byte`
+    Byte,
+    /// `char`
+    Char,
+    /// `float32`
+    Float32,
+    /// `float64`
+    Float64,
+    /// `int8`
+    Int8,
+    /// `uint8`
+    Uint8,
+    /// `int16`
+    Int16,
+    /// `uint16`
+    Uint16,
+    /// `int32`
+    Int32,
+    /// `uint32`
+    Uint32,
+    /// `int64`
+    Int64,
+    /// `uint64`
+    Uint64,
+    /// `string`, with the maximum length in characters if it is bounded.
+    String(Option<usize>),
+    /// `wstring`, with the maximum length in characters if it is bounded.
+    WString(Option<usize>),
+    /// A nested message, identified by its type name, e.g. `"std_msgs/msg/Header"`.
+    Message(&'static str),
+}
+
+/// The type of a field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    /// A single value.
+    Single(ValueType),
+    /// An array with a fixed number of elements.
+    Array(ValueType, usize),
+    /// A sequence with a maximum number of elements.
+    BoundedSequence(ValueType, usize),
+    /// A sequence with any number of elements.
+    UnboundedSequence(ValueType),
+}
+
+impl FieldType {
+    /// Returns the type of the elements, or of the value if this is not an array or sequence.
+    pub fn value_type(&self) -> ValueType {
+        match *self {
+            Self::Single(value_type)
+            | Self::Array(value_type, _)
+            | Self::BoundedSequence(value_type, _)
+            | Self::UnboundedSequence(value_type) => value_type,
+        }
+    }
+}
+
+/// Description of a field of a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FieldMetadata {
+    /// The name of the field in the interface definition.
+    ///
+    /// This can differ from the name of the Rust field, which has a trailing underscore if the
+    /// name is a Rust keyword.
+    pub name: &'static str,
+    /// The type of the field.
+    pub field_type: FieldType,
+    /// The default value as written in the interface definition, if the field has one.
+    pub default_value: Option<&'static str>,
+}
+
+/// Description of a constant of a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConstantMetadata {
+    /// The name of the constant.
+    pub name: &'static str,
+    /// The type of the constant.
+    pub value_type: ValueType,
+    /// The value as written in the interface definition.
+    pub value: &'static str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MiddlewareMessage;
+
+    // A hand-written message that only provides the required items
+    struct Empty;
+
+    impl MiddlewareMessage for Empty {
+        const TYPE_NAME: &'static str = "test_msgs/msg/Empty";
+    }
+
+    // The metadata that the generator emits for a message with a few kinds of fields
+    struct Described;
+
+    impl MiddlewareMessage for Described {
+        const TYPE_NAME: &'static str = "test_msgs/msg/Described";
+
+        const FIELDS: &'static [FieldMetadata] = &[
+            FieldMetadata {
+                name: "header",
+                field_type: FieldType::Single(ValueType::Message("std_msgs/msg/Header")),
+                default_value: None,
+            },
+            FieldMetadata {
+                name: "names",
+                field_type: FieldType::BoundedSequence(ValueType::String(Some(8)), 3),
+                default_value: Some("['a', 'b']"),
+            },
+            FieldMetadata {
+                name: "data",
+                field_type: FieldType::Array(ValueType::Uint8, 4),
+                default_value: None,
+            },
+        ];
+
+        const CONSTANTS: &'static [ConstantMetadata] = &[ConstantMetadata {
+            name: "LIMIT",
+            value_type: ValueType::Int32,
+            value: "-1",
+        }];
+
+        const TYPE_HASH: Option<&'static str> = Some("RIHS01_00");
+    }
+
+    #[test]
+    fn test_defaults_for_hand_written_messages() {
+        assert!(Empty::FIELDS.is_empty());
+        assert!(Empty::CONSTANTS.is_empty());
+        assert_eq!(Empty::TYPE_HASH, None);
+    }
+
+    #[test]
+    fn test_generated_metadata() {
+        let names: heapless::Vec<&str, 3> = Described::FIELDS.iter().map(|f| f.name).collect();
+        assert_eq!(names, ["header", "names", "data"]);
+        assert_eq!(
+            Described::FIELDS[1].field_type.value_type(),
+            ValueType::String(Some(8))
+        );
+        assert_eq!(Described::FIELDS[1].default_value, Some("['a', 'b']"));
+        assert_eq!(
+            Described::FIELDS[2].field_type.value_type(),
+            ValueType::Uint8
+        );
+        assert_eq!(Described::CONSTANTS[0].value, "-1");
+        assert_eq!(Described::TYPE_HASH, Some("RIHS01_00"));
+    }
+
+    #[test]
+    fn test_value_type_of_field_type() {
+        for field_type in [
+            FieldType::Single(ValueType::Char),
+            FieldType::Array(ValueType::Char, 2),
+            FieldType::BoundedSequence(ValueType::Char, 2),
+            FieldType::UnboundedSequence(ValueType::Char),
+        ] {
+            assert_eq!(field_type.value_type(), ValueType::Char);
+        }
+    }
+}
@@ -0,0 +1,542 @@
+//! Checked conversions into bounded fields, used by the generated message builders.
+//!
+//! The bounds of most fields of idiomatic messages are enforced by their types, e.g. a bounded
+//! sequence is a [`heapless::Vec`]. The builders convert their inputs with the functions in this
+//! module, which record a [`Violation`] instead of panicking or truncating when a value doesn't
+//! fit. The bounds of strings can't be expressed by their types, since a [`heapless::String`] has
+//! a capacity in bytes, so the generated `validate()` methods of the messages check them with
+//! [`check_bounded_string()`] and [`check_bounded_wstring()`].
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+use core::ops::Deref;
+
+/// The number of violations that are kept without an allocator.
+///
+/// Further violations are only counted.
+pub const MAX_VIOLATIONS: usize = 16;
+
+/// The kind of bound that a field violates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// A fixed-size array got the wrong number of elements.
+    Array,
+    /// A bounded sequence got more elements than its upper bound.
+    BoundedSequence,
+    /// A bounded string got more characters than its upper bound.
+    BoundedString,
+    /// A bounded wide string got more UTF-16 code units than its upper bound.
+    BoundedWString,
+}
+
+/// A field whose value violates its bound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// The name of the field.
+    pub field: &'static str,
+    /// The index of the element, if the violating value is an element of an array or sequence.
+    pub index: Option<usize>,
+    /// The kind of bound that is violated.
+    pub kind: ViolationKind,
+    /// The length of the value, in elements for arrays and sequences, in characters for strings,
+    /// and in UTF-16 code units for wide strings.
+    pub length: usize,
+    /// The bound of the field, in the same unit as `length`.
+    pub bound: usize,
+}
+
+impl Violation {
+    fn path(&self) -> ViolationPath {
+        ViolationPath(self.field, self.index)
+    }
+}
+
+// The path of the field, e.g. `names[2]` for the third element of the field `names`.
+struct ViolationPath(&'static str, Option<usize>);
+
+impl Display for ViolationPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.1 {
+            Some(index) => write!(f, "{}[{}]", self.0, index),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ViolationKind::Array => write!(
+                f,
+                "Field '{}' has {} elements instead of {}",
+                self.path(),
+                self.length,
+                self.bound
+            ),
+            ViolationKind::BoundedSequence => write!(
+                f,
+                "Field '{}' has {} elements, more than the upper bound of {}",
+                self.path(),
+                self.length,
+                self.bound
+            ),
+            ViolationKind::BoundedString => write!(
+                f,
+                "Field '{}' has {} characters, more than the upper bound of {}",
+                self.path(),
+                self.length,
+                self.bound
+            ),
+            ViolationKind::BoundedWString => write!(
+                f,
+                "Field '{}' has {} UTF-16 code units, more than the upper bound of {}",
+                self.path(),
+                self.length,
+                self.bound
+            ),
+        }
+    }
+}
+
+/// The violations recorded by a builder, in the order the fields were set.
+///
+/// Without an allocator, only the first [`MAX_VIOLATIONS`] violations are kept.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Violations {
+    #[cfg(feature = "alloc")]
+    list: Vec<Violation>,
+    #[cfg(not(feature = "alloc"))]
+    list: heapless::Vec<Violation, MAX_VIOLATIONS>,
+    omitted: usize,
+}
+
+impl Violations {
+    /// Records a violation.
+    pub fn push(&mut self, violation: Violation) {
+        #[cfg(feature = "alloc")]
+        self.list.push(violation);
+        #[cfg(not(feature = "alloc"))]
+        if self.list.push(violation).is_err() {
+            self.omitted += 1;
+        }
+    }
+
+    /// Returns the number of violations that were not kept for lack of an allocator.
+    pub fn omitted(&self) -> usize {
+        self.omitted
+    }
+
+    /// Removes the violations of a field and its elements, because it is being set again.
+    pub fn clear_field(&mut self, field: &str) {
+        self.list.retain(|violation| violation.field != field);
+    }
+}
+
+impl Deref for Violations {
+    type Target = [Violation];
+
+    fn deref(&self) -> &[Violation] {
+        &self.list
+    }
+}
+
+/// Error returned by the generated builders when fields violate their bounds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Every violation, in the order the fields were set.
+    pub violations: Violations,
+}
+
+impl ValidationError {
+    /// Returns an error if there are any violations.
+    // Without an allocator, the violations are stored inline
+    #[cfg_attr(not(feature = "alloc"), allow(clippy::result_large_err))]
+    pub fn check(violations: &Violations) -> Result<(), Self> {
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Self {
+                violations: violations.clone(),
+            })
+        }
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, violation) in self.violations.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", violation)?;
+        }
+        if self.violations.omitted() > 0 {
+            write!(f, "; and {} more", self.violations.omitted())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationError {}
+
+/// Converts a string into a bounded string, or records a violation and returns an empty string.
+///
+/// The `index` is the index of the element if the string is an element of an array or sequence.
+/// The `bound` is the upper bound in characters. The capacity `N` of the returned string is at
+/// least four times that, so that every string within the bound fits.
+pub fn to_bounded_string<const N: usize>(
+    field: &'static str,
+    index: Option<usize>,
+    value: &str,
+    bound: usize,
+    violations: &mut Violations,
+) -> heapless::String<N> {
+    if !check_bounded_string(field, index, value, bound, violations) {
+        return heapless::String::new();
+    }
+    to_capacity(value)
+}
+
+/// Records a violation if a string has more characters than the bound, and returns whether it is
+/// within the bound.
+///
+/// The `index` is the index of the element if the string is an element of an array or sequence.
+pub fn check_bounded_string(
+    field: &'static str,
+    index: Option<usize>,
+    value: &str,
+    bound: usize,
+    violations: &mut Violations,
+) -> bool {
+    let length = value.chars().count();
+    if length > bound {
+        violations.push(Violation {
+            field,
+            index,
+            kind: ViolationKind::BoundedString,
+            length,
+            bound,
+        });
+    }
+    length <= bound
+}
+
+/// Converts a string into a bounded wide string, or records a violation and returns an empty
+/// string.
+///
+/// The `bound` is the upper bound in UTF-16 code units. The capacity `N` of the returned string is
+/// at least three times that, so that every string within the bound fits.
+pub fn to_bounded_wstring<const N: usize>(
+    field: &'static str,
+    index: Option<usize>,
+    value: &str,
+    bound: usize,
+    violations: &mut Violations,
+) -> heapless::String<N> {
+    if !check_bounded_wstring(field, index, value, bound, violations) {
+        return heapless::String::new();
+    }
+    to_capacity(value)
+}
+
+// The capacity of bounded strings fits every string within the bound, so this can't truncate
+fn to_capacity<const N: usize>(value: &str) -> heapless::String<N> {
+    let mut string = heapless::String::new();
+    let _ = string.push_str(value);
+    string
+}
+
+/// Records a violation if a wide string has more UTF-16 code units than the bound, and returns
+/// whether it is within the bound.
+///
+/// The `index` is the index of the element if the string is an element of an array or sequence.
+pub fn check_bounded_wstring(
+    field: &'static str,
+    index: Option<usize>,
+    value: &str,
+    bound: usize,
+    violations: &mut Violations,
+) -> bool {
+    let length = value.encode_utf16().count();
+    if length > bound {
+        violations.push(Violation {
+            field,
+            index,
+            kind: ViolationKind::BoundedWString,
+            length,
+            bound,
+        });
+    }
+    length <= bound
+}
+
+/// Converts the values into an array, or records a violation and returns `None` if the number of
+/// values is wrong.
+///
+/// Each value is converted with `convert`, which receives its index.
+pub fn to_array<T, I, F, const N: usize>(
+    field: &'static str,
+    values: I,
+    convert: F,
+    violations: &mut Violations,
+) -> Option<[T; N]>
+where
+    I: IntoIterator,
+    F: FnMut(usize, I::Item, &mut Violations) -> T,
+{
+    let mut values = values.into_iter();
+    let elements: heapless::Vec<T, N> = to_bounded_elements(values.by_ref(), convert, violations);
+    let length = elements.len() + values.count();
+    match elements.into_array() {
+        Ok(array) if length == N => Some(array),
+        _ => {
+            violations.push(Violation {
+                field,
+                index: None,
+                kind: ViolationKind::Array,
+                length,
+                bound: N,
+            });
+            None
+        }
+    }
+}
+
+/// Converts the values into a bounded sequence, or records a violation and returns the first `N`
+/// values if there are too many.
+///
+/// Each value is converted with `convert`, which receives its index.
+pub fn to_bounded_sequence<T, I, F, const N: usize>(
+    field: &'static str,
+    values: I,
+    convert: F,
+    violations: &mut Violations,
+) -> heapless::Vec<T, N>
+where
+    I: IntoIterator,
+    F: FnMut(usize, I::Item, &mut Violations) -> T,
+{
+    let mut values = values.into_iter();
+    let elements = to_bounded_elements(values.by_ref(), convert, violations);
+    let excess = values.count();
+    if excess > 0 {
+        violations.push(Violation {
+            field,
+            index: None,
+            kind: ViolationKind::BoundedSequence,
+            length: N + excess,
+            bound: N,
+        });
+    }
+    elements
+}
+
+/// Converts the values into an unbounded sequence.
+///
+/// Each value is converted with `convert`, which receives its index.
+#[cfg(feature = "alloc")]
+pub fn to_sequence<T, I, F>(
+    _field: &'static str,
+    values: I,
+    mut convert: F,
+    violations: &mut Violations,
+) -> Vec<T>
+where
+    I: IntoIterator,
+    F: FnMut(usize, I::Item, &mut Violations) -> T,
+{
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| convert(index, value, violations))
+        .collect()
+}
+
+// Converts at most N values, and leaves the rest in the iterator.
+fn to_bounded_elements<T, I, F, const N: usize>(
+    values: I,
+    mut convert: F,
+    violations: &mut Violations,
+) -> heapless::Vec<T, N>
+where
+    I: Iterator,
+    F: FnMut(usize, I::Item, &mut Violations) -> T,
+{
+    let mut elements = heapless::Vec::new();
+    for (index, value) in values.take(N).enumerate() {
+        // The length is at most N, so this can't fail
+        let _ = elements.push(convert(index, value, violations));
+    }
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "alloc")]
+    use alloc::string::ToString;
+
+    use super::*;
+
+    fn violation(field: &'static str, index: Option<usize>) -> Violation {
+        Violation {
+            field,
+            index,
+            kind: ViolationKind::BoundedString,
+            length: 2,
+            bound: 1,
+        }
+    }
+
+    #[test]
+    fn test_to_bounded_string() {
+        let mut violations = Violations::default();
+        // Within the bound of 3 characters, but 6 bytes in UTF-8
+        assert_eq!(
+            to_bounded_string::<12>("name", None, "üüü", 3, &mut violations),
+            "üüü"
+        );
+        assert!(violations.is_empty());
+        assert_eq!(
+            to_bounded_string::<12>("name", Some(2), "abcd", 3, &mut violations),
+            ""
+        );
+        assert_eq!(
+            violations[..],
+            [Violation {
+                field: "name",
+                index: Some(2),
+                kind: ViolationKind::BoundedString,
+                length: 4,
+                bound: 3,
+            }]
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_to_bounded_wstring() {
+        let mut violations = Violations::default();
+        // Within the bound of 3 code units, but 9 bytes in UTF-8
+        assert_eq!(
+            to_bounded_wstring::<9>("name", None, "€€€", 3, &mut violations),
+            "€€€"
+        );
+        assert!(violations.is_empty());
+        assert_eq!(
+            to_bounded_wstring::<9>("name", None, "abcd", 3, &mut violations),
+            ""
+        );
+        let error = ValidationError::check(&violations).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Field 'name' has 4 UTF-16 code units, more than the upper bound of 3"
+        );
+    }
+
+    #[test]
+    fn test_check_bounded_wstring() {
+        let mut violations = Violations::default();
+        // '𝄞' is two UTF-16 code units
+        assert!(check_bounded_wstring(
+            "name",
+            None,
+            "a𝄞",
+            3,
+            &mut violations
+        ));
+        assert!(violations.is_empty());
+        assert!(!check_bounded_wstring(
+            "name",
+            Some(1),
+            "a𝄞𝄞",
+            3,
+            &mut violations
+        ));
+        assert_eq!(
+            violations[..],
+            [Violation {
+                field: "name",
+                index: Some(1),
+                kind: ViolationKind::BoundedWString,
+                length: 5,
+                bound: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_to_array() {
+        let mut violations = Violations::default();
+        let identity = |_, value, _: &mut Violations| value;
+        assert_eq!(
+            to_array::<_, _, _, 2>("values", [1, 2], identity, &mut violations),
+            Some([1, 2])
+        );
+        assert!(violations.is_empty());
+        assert_eq!(
+            to_array::<_, _, _, 2>("values", [1, 2, 3], identity, &mut violations),
+            None
+        );
+        assert_eq!(
+            to_array::<_, _, _, 2>("values", [1], identity, &mut violations),
+            None
+        );
+        assert_eq!(violations[0].kind, ViolationKind::Array);
+        assert_eq!(violations[0].length, 3);
+        assert_eq!(violations[1].length, 1);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_to_bounded_sequence_reports_every_violation() {
+        let mut violations = Violations::default();
+        let names: heapless::Vec<heapless::String<8>, 2> = to_bounded_sequence(
+            "names",
+            ["a", "bcd", "e"],
+            |index, name, violations| to_bounded_string("names", Some(index), name, 2, violations),
+            &mut violations,
+        );
+        assert_eq!(names, ["a", ""]);
+        let error = ValidationError::check(&violations).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Field 'names[1]' has 3 characters, more than the upper bound of 2; \
+             Field 'names' has 3 elements, more than the upper bound of 2"
+        );
+    }
+
+    #[test]
+    fn test_clear_field() {
+        let mut violations = Violations::default();
+        violations.push(violation("names", None));
+        violations.push(violation("names", Some(0)));
+        violations.push(violation("names_long", None));
+        violations.push(violation("other", None));
+        violations.clear_field("names");
+        assert_eq!(
+            violations[..],
+            [violation("names_long", None), violation("other", None)]
+        );
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    #[test]
+    fn test_violations_without_allocator() {
+        use core::fmt::Write;
+
+        let mut violations = Violations::default();
+        for index in 0..MAX_VIOLATIONS + 2 {
+            violations.push(violation("names", Some(index)));
+        }
+        assert_eq!(violations.len(), MAX_VIOLATIONS);
+        assert_eq!(violations.omitted(), 2);
+        let error = ValidationError::check(&violations).unwrap_err();
+        let mut message = heapless::String::<2048>::new();
+        write!(message, "{}", error).unwrap();
+        assert!(message
+            .starts_with("Field 'names[0]' has 2 characters, more than the upper bound of 1; "));
+        assert!(message.ends_with("; and 2 more"));
+    }
+}